@@ -25,6 +25,16 @@ pub struct RelayPublishCmd {
     pub pubsub_topic: String,
     #[arg(long)]
     pub content_topic: String,
+    /// The number of times the payload is published. Zero publishes until interrupted.
+    #[arg(long, default_value_t = 1)]
+    pub count: u64,
+    /// The interval between publications, in milliseconds.
+    #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub interval_ms: u64,
+    /// The time to wait for the peer connection, the subscription and the mesh formation, in
+    /// seconds. The mesh is formed on a relay heartbeat, the first one 5 seconds after start.
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout_secs: u64,
 
     #[arg(required = true)]
     pub payload: String,
@@ -39,6 +49,7 @@ pub async fn run_cmd(args: RelayPublishCmd) -> anyhow::Result<()> {
     let pubsub_topic = args.pubsub_topic.parse::<PubsubTopic>().unwrap();
     let content_topic = args.content_topic.parse::<ContentTopic>().unwrap();
     let payload = hex::decode(args.payload)?;
+    let wait_timeout = Duration::from_secs(args.timeout_secs);
 
     // Build the waku node
     let keypair = Keypair::generate_secp256k1();
@@ -53,7 +64,7 @@ pub async fn run_cmd(args: RelayPublishCmd) -> anyhow::Result<()> {
             ping: None,
             relay: Some(Default::default()),
         };
-        let behaviour = NodeBehaviour::new(conf);
+        let behaviour = NodeBehaviour::new(conf)?;
 
         SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build()
     };
@@ -74,7 +85,7 @@ pub async fn run_cmd(args: RelayPublishCmd) -> anyhow::Result<()> {
         .map_err(|e| anyhow::anyhow!("Failed to dial peer: {e}"))?;
 
     // Await dial confirmation
-    timeout(Duration::from_secs(5), async {
+    let remote_peer_id = timeout(wait_timeout, async {
         loop {
             if let SwarmEvent::ConnectionEstablished {
                 peer_id, endpoint, ..
@@ -83,7 +94,7 @@ pub async fn run_cmd(args: RelayPublishCmd) -> anyhow::Result<()> {
                 let addr = endpoint.get_remote_address();
                 if addr == &peer {
                     info!("Peer connection established: {addr} ({peer_id})");
-                    return;
+                    return peer_id;
                 }
 
                 debug!("Connection established: {addr} ({peer_id})");
@@ -104,7 +115,7 @@ pub async fn run_cmd(args: RelayPublishCmd) -> anyhow::Result<()> {
         .map_err(|e| anyhow::anyhow!("Failed to join pubsub topic: {e}"))?;
 
     // Await subscription confirmation
-    timeout(Duration::from_secs(5), async {
+    timeout(wait_timeout, async {
         loop {
             if let SwarmEvent::Behaviour(waku_node::behaviour::Event::WakuRelay(
                 waku_relay::Event::Subscribed {
@@ -125,24 +136,62 @@ pub async fn run_cmd(args: RelayPublishCmd) -> anyhow::Result<()> {
     .await
     .map_err(|e| anyhow::anyhow!("Failed to subscribe to pubsub topic (timeout): {e}"))?;
 
-    // Build and publish the message
-    let meta = Bytes::from(Ulid::new().0.to_be_bytes().to_vec());
-    let payload = Bytes::from(payload);
-    let message = WakuMessage {
-        content_topic,
-        meta: Some(meta),
-        payload,
-        ephemeral: true,
-    };
+    // Await mesh formation, so the first messages are not lost
+    timeout(wait_timeout, async {
+        while !switch
+            .behaviour()
+            .waku_relay
+            .as_ref()
+            .expect("Waku relay behaviour is enabled")
+            .is_mesh_peer(&remote_peer_id)
+        {
+            let event = switch.select_next_some().await;
+            debug!("{event:?}");
+        }
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to form the pubsub topic mesh (timeout): {e}"))?;
+    info!("Pubsub topic mesh formed: {pubsub_topic}");
 
-    info!("Publishing message: {message:?}");
-    switch
-        .behaviour_mut()
-        .waku_relay
-        .as_mut()
-        .expect("Waku relay behaviour is enabled")
-        .publish(&pubsub_topic, message)
-        .map_err(|e| anyhow::anyhow!("Failed to publish message: {e}"))?;
+    // Build and publish the messages
+    let payload = Bytes::from(payload);
+    let mut published = 0;
+
+    let mut ticker = tokio::time::interval(Duration::from_millis(args.interval_ms));
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    while args.count == 0 || published < args.count {
+        tokio::select! {
+            _ = ticker.tick() => {
+                // A unique meta field per message, so the messages are not deduplicated
+                let meta = Bytes::from(Ulid::new().0.to_be_bytes().to_vec());
+                let message = WakuMessage {
+                    content_topic: content_topic.clone(),
+                    meta: Some(meta),
+                    payload: payload.clone(),
+                    ephemeral: true,
+                };
+
+                debug!("Publishing message: {message:?}");
+                let message_id = switch
+                    .behaviour_mut()
+                    .waku_relay
+                    .as_mut()
+                    .expect("Waku relay behaviour is enabled")
+                    .publish(&pubsub_topic, message)
+                    .map_err(|e| anyhow::anyhow!("Failed to publish message: {e}"))?;
+
+                published += 1;
+                info!("Message published ({published}): {message_id}");
+            },
+            event = switch.select_next_some() => debug!("{event:?}"),
+            _ = &mut ctrl_c => {
+                info!("Interrupted");
+                break;
+            },
+        }
+    }
 
     let _ = timeout(Duration::from_millis(200), async {
         loop {
@@ -152,7 +201,7 @@ pub async fn run_cmd(args: RelayPublishCmd) -> anyhow::Result<()> {
     })
     .await;
 
-    info!("Message published");
+    info!("Messages published: {published}");
 
     Ok(())
 }
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -18,8 +19,9 @@ use waku_node::default_transport;
 pub struct RelaySubscribeCmd {
     #[arg(long)]
     pub peer: String,
-    #[arg(long)]
-    pub pubsub_topic: String,
+    /// The pubsub topic to subscribe to. Repeat it to subscribe to several pubsub topics.
+    #[arg(long = "topic", alias = "pubsub-topic")]
+    pub pubsub_topics: Vec<String>,
     #[arg(long)]
     pub content_topic: String,
 }
@@ -30,7 +32,14 @@ pub async fn run_cmd(args: RelaySubscribeCmd) -> anyhow::Result<()> {
         .peer
         .parse::<Multiaddr>()
         .map_err(|e| anyhow::anyhow!("Invalid peer address: {e}"))?;
-    let pubsub_topic = args.pubsub_topic.parse::<PubsubTopic>().unwrap();
+    if args.pubsub_topics.is_empty() {
+        anyhow::bail!("At least one pubsub topic is required");
+    }
+    let pubsub_topics = args
+        .pubsub_topics
+        .iter()
+        .map(|topic| topic.parse::<PubsubTopic>().unwrap())
+        .collect::<HashSet<_>>();
     let content_topic = args.content_topic.parse::<ContentTopic>().unwrap();
 
     // Build the waku node
@@ -46,7 +55,7 @@ pub async fn run_cmd(args: RelaySubscribeCmd) -> anyhow::Result<()> {
             ping: None,
             relay: Some(Default::default()),
         };
-        let behaviour = NodeBehaviour::new(conf);
+        let behaviour = NodeBehaviour::new(conf)?;
 
         SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build()
     };
@@ -86,19 +95,22 @@ pub async fn run_cmd(args: RelaySubscribeCmd) -> anyhow::Result<()> {
     .await
     .map_err(|e| anyhow::anyhow!("Failed to dial peer (timeout): {e}"))?;
 
-    // Join/Subscribe pubsub topic
-    info!("Subscribing to pubsub topic: {pubsub_topic}");
-    switch
-        .behaviour_mut()
-        .waku_relay
-        .as_mut()
-        .expect("Waku relay behaviour is enabled")
-        .subscribe(&pubsub_topic)
-        .map_err(|e| anyhow::anyhow!("Failed to join pubsub topic: {e}"))?;
-
-    // Await subscription confirmation
+    // Join/Subscribe pubsub topics
+    for pubsub_topic in &pubsub_topics {
+        info!("Subscribing to pubsub topic: {pubsub_topic}");
+        switch
+            .behaviour_mut()
+            .waku_relay
+            .as_mut()
+            .expect("Waku relay behaviour is enabled")
+            .subscribe(pubsub_topic)
+            .map_err(|e| anyhow::anyhow!("Failed to join pubsub topic: {e}"))?;
+    }
+
+    // Await subscription confirmations
+    let mut pending_topics = pubsub_topics.clone();
     timeout(Duration::from_secs(5), async {
-        loop {
+        while !pending_topics.is_empty() {
             if let SwarmEvent::Behaviour(waku_node::behaviour::Event::WakuRelay(
                 waku_relay::Event::Subscribed {
                     pubsub_topic: topic,
@@ -106,9 +118,9 @@ pub async fn run_cmd(args: RelaySubscribeCmd) -> anyhow::Result<()> {
                 },
             )) = switch.select_next_some().await
             {
-                if topic == pubsub_topic {
+                if pending_topics.remove(&topic) {
                     info!("Joined pubsub topic: {topic}");
-                    return;
+                    continue;
                 }
 
                 debug!("Joined pubsub topic: {topic}");
@@ -124,10 +136,11 @@ pub async fn run_cmd(args: RelaySubscribeCmd) -> anyhow::Result<()> {
             waku_relay::Event::Message {
                 message,
                 pubsub_topic: topic,
+                ..
             },
         )) = switch.select_next_some().await
         {
-            if pubsub_topic != topic {
+            if !pubsub_topics.contains(&topic) {
                 continue;
             }
 
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use libp2p::identity::{secp256k1, Keypair};
 use log::info;
 use multiaddr::Multiaddr;
@@ -7,12 +9,17 @@ use waku_node::{Event, Node, NodeConfig, NodeConfigBuilder};
 
 use crate::config::Wakunode2Conf;
 
+/// The time given to the node to leave the network gracefully before closing the remaining
+/// connections abruptly.
+const GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone)]
 pub struct AppConf {
     pub node_conf: NodeConfig,
     pub listen_addresses: Vec<Multiaddr>,
     pub bootstrap_nodes: Vec<Multiaddr>,
     pub topics: Vec<PubsubTopic>,
+    pub graceful_shutdown: bool,
 }
 
 fn try_into_multiaddr(addr: &[String]) -> anyhow::Result<Vec<Multiaddr>> {
@@ -63,6 +70,7 @@ impl TryFrom<Wakunode2Conf> for AppConf {
         let listen_addresses = try_into_multiaddr(&c.listen_addresses)?;
         let bootstrap_nodes = try_into_multiaddr(&c.bootstrap_nodes)?;
         let topics = to_pubsub_topic(&c.topics)?;
+        let graceful_shutdown = c.graceful_shutdown;
 
         let node_conf = c.try_into()?;
         Ok(Self {
@@ -70,6 +78,7 @@ impl TryFrom<Wakunode2Conf> for AppConf {
             listen_addresses,
             bootstrap_nodes,
             topics,
+            graceful_shutdown,
         })
     }
 }
@@ -114,4 +123,13 @@ impl App {
     pub async fn run(&mut self) -> Option<Event> {
         self.node.recv_event().await
     }
+
+    pub async fn shutdown(&mut self) -> anyhow::Result<()> {
+        if self.conf.graceful_shutdown {
+            info!("Leaving the network");
+            self.node.leave_network(GRACEFUL_SHUTDOWN_TIMEOUT).await?;
+        }
+
+        Ok(())
+    }
 }
@@ -12,6 +12,9 @@ pub struct Wakunode2Conf {
     pub bootstrap_nodes: Vec<String>,
     #[serde(default)]
     pub keepalive: bool,
+    /// Leave the network gracefully on shutdown, instead of dropping all the connections.
+    #[serde(default)]
+    pub graceful_shutdown: bool,
 
     #[serde(default)]
     pub relay: bool,
@@ -39,5 +39,5 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    Ok(())
+    app.shutdown().await
 }
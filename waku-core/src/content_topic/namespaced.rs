@@ -0,0 +1,265 @@
+use crate::content_topic::ContentTopic;
+
+/// A content topic following the `/{application}/{version}/{name}/{encoding}` format.
+///
+/// See [23/WAKU2-TOPICS](https://rfc.vac.dev/spec/23/#content-topics) for more information.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct NsContentTopic {
+    pub application: String,
+    pub version: String,
+    pub name: String,
+    pub encoding: String,
+}
+
+fn parse_content_topic(topic: &str) -> anyhow::Result<(String, String, String, String)> {
+    let mut parts = topic
+        .strip_prefix('/')
+        .ok_or_else(|| anyhow::anyhow!("invalid prefix"))?
+        .split('/');
+
+    let mut next_part = |field: &str| {
+        parts
+            .next()
+            .filter(|part| !part.is_empty())
+            .map(ToString::to_string)
+            .ok_or_else(|| anyhow::anyhow!("missing {}", field))
+    };
+
+    let application = next_part("application")?;
+    let version = next_part("version")?;
+    let name = next_part("name")?;
+    let encoding = next_part("encoding")?;
+
+    if parts.next().is_some() {
+        anyhow::bail!("too many parts");
+    }
+
+    Ok((application, version, name, encoding))
+}
+
+impl NsContentTopic {
+    pub fn new<A, V, N, E>(application: A, version: V, name: N, encoding: E) -> Self
+    where
+        A: Into<String>,
+        V: Into<String>,
+        N: Into<String>,
+        E: Into<String>,
+    {
+        Self {
+            application: application.into(),
+            version: version.into(),
+            name: name.into(),
+            encoding: encoding.into(),
+        }
+    }
+}
+
+impl std::str::FromStr for NsContentTopic {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (application, version, name, encoding) = parse_content_topic(s)?;
+        Ok(Self {
+            application,
+            version,
+            name,
+            encoding,
+        })
+    }
+}
+
+impl ToString for NsContentTopic {
+    fn to_string(&self) -> String {
+        format!(
+            "/{}/{}/{}/{}",
+            self.application, self.version, self.name, self.encoding
+        )
+    }
+}
+
+impl std::fmt::Debug for NsContentTopic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "NsContentTopic(application={},version={},name={},encoding={})",
+            self.application, self.version, self.name, self.encoding
+        )
+    }
+}
+
+impl TryFrom<ContentTopic> for NsContentTopic {
+    type Error = anyhow::Error;
+
+    fn try_from(topic: ContentTopic) -> Result<Self, Self::Error> {
+        topic.as_str().parse()
+    }
+}
+
+impl From<NsContentTopic> for ContentTopic {
+    fn from(topic: NsContentTopic) -> Self {
+        ContentTopic::new(topic.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::content_topic::ContentTopic;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_content_topic_valid_string() {
+        // Given
+        let topic = "/toychat/2/huilong/proto";
+
+        // When
+        let (application, version, name, encoding) = parse_content_topic(topic).unwrap();
+
+        // Then
+        assert_eq!(application, "toychat");
+        assert_eq!(version, "2");
+        assert_eq!(name, "huilong");
+        assert_eq!(encoding, "proto");
+    }
+
+    #[test]
+    fn test_parse_content_topic_invalid_prefix() {
+        // Given
+        let topic = "toychat/2/huilong/proto";
+
+        // When
+        let result = parse_content_topic(topic);
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_content_topic_invalid_too_many_parts() {
+        // Given
+        let topic = "/toychat/2/huilong/proto/extra";
+
+        // When
+        let result = parse_content_topic(topic);
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_content_topic_missing_encoding() {
+        // Given
+        let topic = "/toychat/2/huilong";
+
+        // When
+        let result = parse_content_topic(topic);
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_content_topic_empty_part() {
+        // Given
+        let topic = "/toychat//huilong/proto";
+
+        // When
+        let result = parse_content_topic(topic);
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_content_topic_trailing_slash() {
+        // Given
+        let topic = "/toychat/2/huilong/proto/";
+
+        // When
+        let result = parse_content_topic(topic);
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_content_topic_empty_string() {
+        // Given
+        let topic = "";
+
+        // When
+        let result = parse_content_topic(topic);
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_ns_content_topic_from_str() {
+        // Given
+        let topic = "/toychat/2/huilong/proto";
+
+        // When
+        let ns_topic = topic.parse::<NsContentTopic>().unwrap();
+
+        // Then
+        assert_matches!(
+            ns_topic,
+            NsContentTopic { application, version, name, encoding }
+                if application == "toychat" && version == "2" && name == "huilong" && encoding == "proto"
+        );
+    }
+
+    #[test]
+    fn test_ns_content_topic_to_string() {
+        // Given
+        let ns_topic = NsContentTopic::new("toychat", "2", "huilong", "proto");
+
+        // When
+        let topic = ns_topic.to_string();
+
+        // Then
+        assert_eq!(topic, "/toychat/2/huilong/proto");
+    }
+
+    #[test]
+    fn test_ns_content_topic_round_trip() {
+        // Given
+        let topic = "/statusim/01/community-chat/cbor";
+
+        // When
+        let ns_topic = topic.parse::<NsContentTopic>().unwrap();
+
+        // Then
+        assert_eq!(ns_topic.to_string(), topic);
+    }
+
+    #[test]
+    fn test_ns_content_topic_from_content_topic() {
+        // Given
+        let content_topic = ContentTopic::new("/toychat/2/huilong/proto");
+
+        // When
+        let ns_topic = NsContentTopic::try_from(content_topic).unwrap();
+
+        // Then
+        assert_eq!(
+            ns_topic,
+            NsContentTopic::new("toychat", "2", "huilong", "proto")
+        );
+    }
+
+    #[test]
+    fn test_ns_content_topic_from_invalid_content_topic() {
+        // Given
+        let content_topic = ContentTopic::new("huilong");
+
+        // When
+        let result = NsContentTopic::try_from(content_topic);
+
+        // Then
+        assert!(result.is_err());
+    }
+}
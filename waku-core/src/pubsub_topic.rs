@@ -1,5 +1,7 @@
 pub use namespaced::*;
+pub use sharding::*;
 pub use topic::*;
 
 mod namespaced;
+mod sharding;
 mod topic;
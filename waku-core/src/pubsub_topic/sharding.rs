@@ -0,0 +1,79 @@
+use sha2::{Digest, Sha256};
+
+use crate::content_topic::NsContentTopic;
+use crate::pubsub_topic::NsPubsubTopic;
+
+/// Map a content topic to a static sharding pubsub topic of the given cluster.
+///
+/// The shard index is the SHA-256 hash of the content topic application and version, taking the
+/// last 64 bits as a big-endian integer, modulo the cluster shard count.
+///
+/// See [51/WAKU2-RELAY-SHARDING](https://rfc.vac.dev/spec/51/#automatic-sharding) for more
+/// information.
+///
+/// # Panics
+///
+/// Panics if `shard_count` is zero.
+pub fn auto_shard(content_topic: &NsContentTopic, cluster: u16, shard_count: u16) -> NsPubsubTopic {
+    assert!(shard_count > 0, "the shard count must be greater than zero");
+
+    let hash = Sha256::new()
+        .chain_update(content_topic.application.as_bytes())
+        .chain_update(content_topic.version.as_bytes())
+        .finalize();
+
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&hash[24..]);
+    let shard = u64::from_be_bytes(value) % u64::from(shard_count);
+
+    NsPubsubTopic::new_static_sharding(cluster, shard as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn test_auto_shard_rfc_example_vectors() {
+        // Given
+        let vectors = [
+            ("/toychat/2/huilong/proto", 3),
+            ("/myapp/1/latest/proto", 0),
+            ("/waku/2/content/test.js", 1),
+            ("/app/22/sometopic/someencoding", 2),
+            ("/app/27/sometopic/someencoding", 5),
+            ("/app/20/sometopic/someencoding", 7),
+            ("/app/29/sometopic/someencoding", 6),
+        ];
+
+        for (topic, expected_shard) in vectors {
+            let content_topic = topic.parse::<NsContentTopic>().unwrap();
+
+            // When
+            let pubsub_topic = auto_shard(&content_topic, 1, 8);
+
+            // Then
+            assert_matches!(
+                pubsub_topic,
+                NsPubsubTopic::StaticSharding { cluster: 1, shard } if shard == expected_shard,
+                "unexpected shard for {topic}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_auto_shard_ignores_content_topic_name_and_encoding() {
+        // Given
+        let topic = NsContentTopic::new("toychat", "2", "huilong", "proto");
+        let other_topic = NsContentTopic::new("toychat", "2", "other", "rlp");
+
+        // When
+        let pubsub_topic = auto_shard(&topic, 0, 8);
+        let other_pubsub_topic = auto_shard(&other_topic, 0, 8);
+
+        // Then
+        assert_eq!(pubsub_topic.to_string(), other_pubsub_topic.to_string());
+    }
+}
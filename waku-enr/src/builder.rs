@@ -0,0 +1,53 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use enr::{Enr, EnrBuilder, EnrError, EnrKey};
+
+use crate::capabilities::WakuEnrCapabilities;
+use crate::enr_ext::{EnrBuilderExt, WAKU2_CAPABILITIES_ENR_KEY};
+
+/// Build a Waku v2 ENR advertising the node IPv4 address, TCP and UDP ports, and capabilities.
+///
+/// The record is signed with the given key, either a secp256k1 or an ed25519 key.
+pub fn build_waku_enr<K: EnrKey>(
+    key: &K,
+    ip: Ipv4Addr,
+    tcp: u16,
+    udp: u16,
+    capabilities: WakuEnrCapabilities,
+) -> Result<Enr<K>, EnrError> {
+    EnrBuilder::new("v4")
+        .ip4(ip)
+        .tcp4(tcp)
+        .udp4(udp)
+        .waku2(capabilities)
+        .build(key)
+}
+
+/// Update the Waku v2 ENR fields set by [`build_waku_enr`], re-signing the record with the given
+/// key and increasing its sequence number by one.
+///
+/// The rest of the record fields are preserved. The record is left unchanged if the update fails.
+pub fn update_waku_enr<K: EnrKey>(
+    enr: &mut Enr<K>,
+    key: &K,
+    ip: Ipv4Addr,
+    tcp: u16,
+    udp: u16,
+    capabilities: WakuEnrCapabilities,
+) -> Result<(), EnrError> {
+    let mut updated = enr.clone();
+    updated.set_ip(IpAddr::V4(ip), key)?;
+    updated.set_tcp4(tcp, key)?;
+    updated.set_udp4(udp, key)?;
+    updated.insert(
+        WAKU2_CAPABILITIES_ENR_KEY,
+        &vec![capabilities.to_bitfield()],
+        key,
+    )?;
+
+    // Every setter bumps the sequence number, the whole update is a single new version
+    updated.set_seq(enr.seq() + 1, key)?;
+
+    *enr = updated;
+    Ok(())
+}
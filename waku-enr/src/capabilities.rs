@@ -10,3 +10,49 @@ bitflags! {
         const LIGHTPUSH = 0b00001000;
     }
 }
+
+/// A Waku v2 node capability, as advertised in the ENR `waku2` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    Relay,
+    Store,
+    Filter,
+    Lightpush,
+}
+
+impl From<Capability> for WakuEnrCapabilities {
+    fn from(capability: Capability) -> Self {
+        match capability {
+            Capability::Relay => Self::RELAY,
+            Capability::Store => Self::STORE,
+            Capability::Filter => Self::FILTER,
+            Capability::Lightpush => Self::LIGHTPUSH,
+        }
+    }
+}
+
+impl WakuEnrCapabilities {
+    /// Create the capabilities from the `waku2` field bitfield.
+    ///
+    /// Unknown bits are retained so future capabilities are not dropped on re-encoding.
+    pub fn from_bitfield(bitfield: u8) -> Self {
+        Self::from_bits_retain(bitfield)
+    }
+
+    /// The `waku2` field bitfield, including any unknown bits.
+    pub fn to_bitfield(&self) -> u8 {
+        self.bits()
+    }
+
+    /// Whether the given capability is supported.
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.contains(capability.into())
+    }
+}
+
+impl FromIterator<Capability> for WakuEnrCapabilities {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::empty(), |caps, capability| caps | capability.into())
+    }
+}
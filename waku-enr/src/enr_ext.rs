@@ -1,5 +1,5 @@
 use enr::{Enr, EnrBuilder, EnrKey};
-use multiaddr::Multiaddr;
+use multiaddr::{Multiaddr, Protocol};
 
 use crate::capabilities::WakuEnrCapabilities;
 use crate::multiaddrs;
@@ -8,14 +8,83 @@ use crate::multiaddrs;
 pub const WAKU2_MULTIADDR_ENR_KEY: &str = "multiaddrs";
 /// The ENR field specifying the node Waku v2 capabilities.
 pub const WAKU2_CAPABILITIES_ENR_KEY: &str = "waku2";
+/// The ENR field specifying the node Waku v2 network identifier.
+pub const WAKU2_NETWORK_ID_ENR_KEY: &str = "network_id";
+
+/// The ENR fields known to this implementation: the EIP-778 pre-defined keys and the Waku v2 keys.
+const KNOWN_ENR_KEYS: &[&str] = &[
+    "id",
+    "secp256k1",
+    "ed25519",
+    "ip",
+    "tcp",
+    "udp",
+    "ip6",
+    "tcp6",
+    "udp6",
+    WAKU2_MULTIADDR_ENR_KEY,
+    WAKU2_CAPABILITIES_ENR_KEY,
+    WAKU2_NETWORK_ID_ENR_KEY,
+];
 
 /// Extension trait for Waku v2 ENRs
 pub trait EnrExt {
     /// The multiaddrs field associated with the ENR.
     fn multiaddrs(&self) -> Option<Vec<Multiaddr>>;
 
-    /// The waku node capabilities bitfield associated with the ENR.
+    /// The QUIC multiaddrs, i.e., the UDP and QUIC addresses, of the multiaddrs field.
+    fn quic_multiaddrs(&self) -> Vec<Multiaddr> {
+        self.multiaddrs()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(is_quic_multiaddr)
+            .collect()
+    }
+
+    /// The TCP multiaddrs of the multiaddrs field, excluding the QUIC ones.
+    fn tcp_multiaddrs(&self) -> Vec<Multiaddr> {
+        self.multiaddrs()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|addr| !is_quic_multiaddr(addr) && is_tcp_multiaddr(addr))
+            .collect()
+    }
+
+    /// The multiaddrs field addresses that are neither TCP nor QUIC addresses.
+    fn other_multiaddrs(&self) -> Vec<Multiaddr> {
+        self.multiaddrs()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|addr| !is_quic_multiaddr(addr) && !is_tcp_multiaddr(addr))
+            .collect()
+    }
+
+    /// The waku node capabilities bitfield associated with the ENR, retaining the capability bits
+    /// unknown to this implementation.
     fn waku2(&self) -> Option<WakuEnrCapabilities>;
+
+    /// The waku network identifier associated with the ENR.
+    fn network_id(&self) -> Option<u32>;
+
+    /// Whether the ENR belongs to the given network. An ENR without a network identifier is
+    /// considered compatible with any network, while a malformed network identifier is considered
+    /// incompatible with all of them.
+    fn is_network_compatible(&self, network_id: u32) -> bool;
+
+    /// The keys of the ENR fields unknown to this implementation.
+    ///
+    /// Unknown fields are preserved when decoding, and kept when the record is updated and
+    /// re-signed, so they are forwarded unchanged.
+    fn unknown_fields(&self) -> Vec<Vec<u8>>;
+}
+
+fn is_quic_multiaddr(addr: &Multiaddr) -> bool {
+    addr.iter()
+        .any(|p| matches!(p, Protocol::Udp(_) | Protocol::Quic | Protocol::QuicV1))
+}
+
+fn is_tcp_multiaddr(addr: &Multiaddr) -> bool {
+    addr.iter().any(|p| matches!(p, Protocol::Tcp(_)))
 }
 
 impl<T: EnrKey> EnrExt for Enr<T> {
@@ -27,20 +96,64 @@ impl<T: EnrKey> EnrExt for Enr<T> {
     }
 
     fn waku2(&self) -> Option<WakuEnrCapabilities> {
-        if let Some(bitfield) = self.get(WAKU2_CAPABILITIES_ENR_KEY) {
-            return match bitfield.len() {
-                1 => WakuEnrCapabilities::from_bits(bitfield[0]),
-                _ => None,
-            };
+        match self.get(WAKU2_CAPABILITIES_ENR_KEY)? {
+            [bitfield] => Some(WakuEnrCapabilities::from_bitfield(*bitfield)),
+            _ => None,
+        }
+    }
+
+    fn network_id(&self) -> Option<u32> {
+        if let Some(id_bytes) = self.get(WAKU2_NETWORK_ID_ENR_KEY) {
+            // RLP-encoded integers are big-endian without leading zeros
+            if id_bytes.len() > 4 {
+                return None;
+            }
+            let mut buffer = [0u8; 4];
+            buffer[4 - id_bytes.len()..].copy_from_slice(id_bytes);
+            return Some(u32::from_be_bytes(buffer));
         }
         None
     }
+
+    fn is_network_compatible(&self, network_id: u32) -> bool {
+        if self.get(WAKU2_NETWORK_ID_ENR_KEY).is_none() {
+            return true;
+        }
+        self.network_id() == Some(network_id)
+    }
+
+    fn unknown_fields(&self) -> Vec<Vec<u8>> {
+        self.iter()
+            .map(|(key, _)| key)
+            .filter(|key| {
+                !KNOWN_ENR_KEYS
+                    .iter()
+                    .any(|known| known.as_bytes() == &key[..])
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// The circuit relay (`/p2p-circuit`) multiaddrs advertised in the ENR multiaddrs field.
+///
+/// Malformed multiaddrs entries are skipped.
+pub fn circuit_relay_multiaddrs<K: EnrKey>(enr: &Enr<K>) -> Vec<Multiaddr> {
+    if let Some(multiaddrs_bytes) = enr.get(WAKU2_MULTIADDR_ENR_KEY) {
+        return multiaddrs::decode_lossy(multiaddrs_bytes)
+            .into_iter()
+            .filter(|addr| addr.iter().any(|p| matches!(p, Protocol::P2pCircuit)))
+            .collect();
+    }
+    Vec::new()
 }
 
 pub trait EnrBuilderExt {
     fn multiaddrs(&mut self, multiaddrs: Vec<Multiaddr>) -> &mut Self;
 
     fn waku2(&mut self, capabilities: WakuEnrCapabilities) -> &mut Self;
+
+    fn network_id(&mut self, network_id: u32) -> &mut Self;
 }
 
 impl<T: EnrKey> EnrBuilderExt for EnrBuilder<T> {
@@ -53,8 +166,14 @@ impl<T: EnrKey> EnrBuilderExt for EnrBuilder<T> {
 
     /// Adds a Waku `waku2` capabilities bitfield to the EnrBuilder.
     fn waku2(&mut self, cap: WakuEnrCapabilities) -> &mut Self {
-        let cap = vec![cap.bits()];
+        let cap = vec![cap.to_bitfield()];
         self.add_value(WAKU2_CAPABILITIES_ENR_KEY, &cap);
         self
     }
+
+    /// Adds a Waku `network_id` field to the EnrBuilder.
+    fn network_id(&mut self, network_id: u32) -> &mut Self {
+        self.add_value(WAKU2_NETWORK_ID_ENR_KEY, &network_id);
+        self
+    }
 }
@@ -13,6 +13,11 @@ pub fn encode(multiaddrs: &[Multiaddr]) -> Vec<u8> {
     buffer
 }
 
+/// Decode the multiaddrs field: a sequence of binary multiaddrs, each prefixed with its length as a
+/// 2-byte big-endian integer.
+///
+/// The multiaddr components carrying variable-length values, e.g., the `/certhash` multihashes of
+/// WebTransport addresses, are decoded intact.
 pub fn decode(data: &[u8]) -> anyhow::Result<Vec<Multiaddr>> {
     let mut buffer = Vec::from(data);
     let mut multiaddrs: Vec<Multiaddr> = Vec::new();
@@ -35,11 +40,37 @@ pub fn decode(data: &[u8]) -> anyhow::Result<Vec<Multiaddr>> {
     Ok(multiaddrs)
 }
 
+/// Decode the multiaddrs, skipping the malformed entries instead of failing.
+///
+/// Decoding stops at the first entry whose length prefix exceeds the remaining bytes, as the
+/// boundaries of the following entries cannot be determined.
+pub fn decode_lossy(data: &[u8]) -> Vec<Multiaddr> {
+    let mut multiaddrs: Vec<Multiaddr> = Vec::new();
+
+    let mut remaining = data;
+    while remaining.len() > 2 {
+        let length = u16::from_be_bytes([remaining[0], remaining[1]]) as usize;
+        remaining = &remaining[2..];
+        if length > remaining.len() {
+            break;
+        }
+
+        let (addr_bytes, rest) = remaining.split_at(length);
+        remaining = rest;
+
+        if let Ok(addr) = Multiaddr::try_from(addr_bytes.to_vec()) {
+            multiaddrs.push(addr);
+        }
+    }
+
+    multiaddrs
+}
+
 #[cfg(test)]
 mod tests {
     use multiaddr::Multiaddr;
 
-    use super::{decode, encode};
+    use super::{decode, decode_lossy, encode};
 
     #[test]
     fn test_multiaddrs_codec() {
@@ -59,4 +90,43 @@ mod tests {
         // Then
         assert!(matches!(decoded, Ok(addrs) if addrs == multiaddrs));
     }
+
+    #[test]
+    fn test_multiaddrs_decode_lossy_skips_malformed_entries() {
+        // Given
+        let valid_addr: Multiaddr = "/dns4/example.com/tcp/443/wss".parse().unwrap();
+        let mut encoded = encode(&[valid_addr.clone()]);
+        // A malformed entry: an unknown protocol code
+        encoded.extend_from_slice(&[0x00, 0x02, 0xff, 0xff]);
+        encoded.extend(encode(&[valid_addr.clone()]));
+
+        // When
+        let strict = decode(&encoded);
+        let lossy = decode_lossy(&encoded);
+
+        // Then
+        assert!(strict.is_err());
+        assert_eq!(lossy, vec![valid_addr.clone(), valid_addr]);
+    }
+
+    #[test]
+    fn test_multiaddrs_codec_webtransport_certhash() {
+        // Given
+        let webtransport_addr: Multiaddr = "/ip4/1.2.3.4/udp/4433/quic-v1/webtransport\
+            /certhash/uEiDKBK3UTkz_2cx_MIfOcW3WSiE8np6eXTMqBuPQluhmwA\
+            /certhash/uEiBJngL83KbpNZrxL--yNSOqpGcY1TL4Uk_FVs6urECBug"
+            .parse()
+            .unwrap();
+        let multiaddrs: Vec<Multiaddr> = vec![
+            webtransport_addr,
+            "/dns4/example.com/tcp/443/wss".parse().unwrap(),
+        ];
+
+        // When
+        let encoded = encode(&multiaddrs);
+        let decoded = decode(&encoded);
+
+        // Then
+        assert!(matches!(decoded, Ok(addrs) if addrs == multiaddrs));
+    }
 }
@@ -4,7 +4,10 @@ use base64::{engine::general_purpose::STANDARD, Engine as _};
 use multiaddr::Multiaddr;
 
 use waku_enr::enr::{CombinedKey, Enr, EnrBuilder, EnrKey};
-use waku_enr::{EnrBuilderExt, EnrExt, WakuEnrCapabilities};
+use waku_enr::{
+    build_waku_enr, circuit_relay_multiaddrs, update_waku_enr, Capability, EnrBuilderExt, EnrExt,
+    WakuEnrCapabilities, WAKU2_MULTIADDR_ENR_KEY, WAKU2_NETWORK_ID_ENR_KEY,
+};
 
 ///! https://rfc.vac.dev/spec/31/#many-connection-types
 #[test]
@@ -93,3 +96,320 @@ fn test_decode_waku_enr() {
     assert!(matches!(capabilities, Some(value) if value == expected_capabilities));
     assert!(matches!(multiaddrs, Some(value) if value == expected_multiaddrs));
 }
+
+#[test]
+fn test_waku_enr_network_id() {
+    // Given
+    let network_id: u32 = 42;
+
+    // Signing key
+    let key_secp256k1_base64 = "MaZivCR1kZsI2/1MuSw9mhnLQYqETWwjfcWpyiS20uw=";
+    let mut key_secp256k1_bytes = STANDARD.decode(key_secp256k1_base64).unwrap();
+    let key = CombinedKey::secp256k1_from_bytes(&mut key_secp256k1_bytes).unwrap();
+
+    // When
+    let enr = EnrBuilder::new("v4")
+        .network_id(network_id)
+        .build(&key)
+        .expect("valid enr");
+    let enr_without_network_id = EnrBuilder::new("v4").build(&key).expect("valid enr");
+
+    // Then
+    assert!(matches!(enr.network_id(), Some(value) if value == network_id));
+    assert!(enr.is_network_compatible(network_id));
+    assert!(!enr.is_network_compatible(network_id + 1));
+
+    assert!(enr_without_network_id.network_id().is_none());
+    assert!(enr_without_network_id.is_network_compatible(network_id));
+}
+
+#[test]
+fn test_waku_enr_oversized_network_id_is_incompatible() {
+    // Given
+    let key_secp256k1_base64 = "MaZivCR1kZsI2/1MuSw9mhnLQYqETWwjfcWpyiS20uw=";
+    let mut key_secp256k1_bytes = STANDARD.decode(key_secp256k1_base64).unwrap();
+    let key = CombinedKey::secp256k1_from_bytes(&mut key_secp256k1_bytes).unwrap();
+
+    // A network identifier that does not fit in 4 bytes
+    let network_id: u64 = (1 << 32) + 42;
+
+    // When
+    let enr = EnrBuilder::new("v4")
+        .add_value(WAKU2_NETWORK_ID_ENR_KEY, &network_id)
+        .build(&key)
+        .expect("valid enr");
+
+    // Then
+    assert!(enr.network_id().is_none());
+    assert!(!enr.is_network_compatible(42));
+}
+
+///! https://rfc.vac.dev/spec/31/#many-connection-types
+#[test]
+fn test_decode_waku_enr_capabilities() {
+    // Given
+    let enr_base64 = "enr:-PC4QPdY95OvXxYSdzPnWTCEY3u0jr0t925ArgGDGJfsDemgMvl-PuXr23r9fJnJGncdx1yPYT7oB6OJoqsiUjSnF7sBgmlkgnY0gmlwhAECAwSDaXA2kBI0VgABAQABAAAAAAAAAUKKbXVsdGlhZGRyc60AEjYLZXhhbXBsZS5jb20GAbveAwAXNhBxdWljLmV4YW1wbGUuY29tBgG7zAOJc2VjcDI1NmsxoQL72vzMVCejPltbXNukOvJc8Mqj-IiawTVxiYY1WCRSX4N0Y3CCJ3WEdGNwNoJ2X4N1ZHCCTuqEdWRwNoKd1IV3YWt1MgM";
+    let enr: Enr<CombinedKey> = enr_base64.parse().expect("valid enr string");
+
+    // When
+    let capabilities = enr.waku2().expect("waku2 field present");
+
+    // Then
+    assert_eq!(capabilities.to_bitfield(), 0b00000011);
+    assert!(capabilities.supports(Capability::Relay));
+    assert!(capabilities.supports(Capability::Store));
+    assert!(!capabilities.supports(Capability::Filter));
+    assert!(!capabilities.supports(Capability::Lightpush));
+    assert_eq!(
+        capabilities,
+        [Capability::Relay, Capability::Store].into_iter().collect()
+    );
+}
+
+#[test]
+fn test_waku_enr_capabilities_retain_unknown_bits() {
+    // Given
+    let bitfield: u8 = 0b10000101;
+    let capabilities = WakuEnrCapabilities::from_bitfield(bitfield);
+
+    // Signing key
+    let key_secp256k1_base64 = "MaZivCR1kZsI2/1MuSw9mhnLQYqETWwjfcWpyiS20uw=";
+    let mut key_secp256k1_bytes = STANDARD.decode(key_secp256k1_base64).unwrap();
+    let key = CombinedKey::secp256k1_from_bytes(&mut key_secp256k1_bytes).unwrap();
+
+    // When
+    let enr = EnrBuilder::new("v4")
+        .waku2(capabilities)
+        .build(&key)
+        .expect("valid enr");
+    let decoded: Enr<CombinedKey> = enr.to_base64().parse().expect("valid enr string");
+
+    // Then
+    let decoded_capabilities = decoded.waku2().expect("waku2 field present");
+    assert_eq!(decoded_capabilities.to_bitfield(), bitfield);
+    assert!(decoded_capabilities.supports(Capability::Relay));
+    assert!(decoded_capabilities.supports(Capability::Filter));
+    assert!(!decoded_capabilities.supports(Capability::Store));
+}
+
+#[test]
+fn test_waku_enr_circuit_relay_multiaddrs() {
+    // Given
+    let circuit_addr: Multiaddr = "/ip4/7.7.7.7/tcp/3003/p2p/QmYyQSo1c1Ym7orWxLYvCrM2EmxFTANf8wXmmE7DWjhx5N/p2p-circuit/p2p/QmUWYRp3mkQUUVeyGVjcM1fC7kbVxmDieGpGsQzopXivyk"
+        .parse()
+        .unwrap();
+    let wss_addr: Multiaddr = "/dns4/example.com/tcp/443/wss".parse().unwrap();
+
+    // A multiaddrs field mixing a circuit relay, a malformed and a websocket address
+    let mut multiaddrs_field = Vec::new();
+    for addr_bytes in [circuit_addr.to_vec(), vec![0xff, 0xff], wss_addr.to_vec()] {
+        multiaddrs_field.extend_from_slice(&(addr_bytes.len() as u16).to_be_bytes());
+        multiaddrs_field.extend(addr_bytes);
+    }
+
+    // Signing key
+    let key_secp256k1_base64 = "MaZivCR1kZsI2/1MuSw9mhnLQYqETWwjfcWpyiS20uw=";
+    let mut key_secp256k1_bytes = STANDARD.decode(key_secp256k1_base64).unwrap();
+    let key = CombinedKey::secp256k1_from_bytes(&mut key_secp256k1_bytes).unwrap();
+
+    let enr = EnrBuilder::new("v4")
+        .add_value(WAKU2_MULTIADDR_ENR_KEY, &multiaddrs_field)
+        .build(&key)
+        .expect("valid enr");
+
+    // When
+    let circuit_addrs = circuit_relay_multiaddrs(&enr);
+
+    // Then
+    assert!(enr.multiaddrs().is_none(), "the strict decoding fails");
+    assert_eq!(circuit_addrs, vec![circuit_addr]);
+}
+
+#[test]
+fn test_waku_enr_multiaddrs_by_transport() {
+    // Given
+    // /ip4/1.2.3.4/tcp/60000
+    let tcp_addr_bytes = vec![0x04, 1, 2, 3, 4, 0x06, 0xea, 0x60];
+    // /ip4/1.2.3.4/udp/9000/quic-v1
+    let quic_addr_bytes = vec![0x04, 1, 2, 3, 4, 0x91, 0x02, 0x23, 0x28, 0xcc, 0x03];
+    // /ip4/1.2.3.4/udp/9001
+    let udp_addr_bytes = vec![0x04, 1, 2, 3, 4, 0x91, 0x02, 0x23, 0x29];
+    // /dns4/example.com/tcp/443/wss
+    let mut wss_addr_bytes = vec![0x36, 11];
+    wss_addr_bytes.extend_from_slice(b"example.com");
+    wss_addr_bytes.extend_from_slice(&[0x06, 0x01, 0xbb, 0xdd, 0x03]);
+    // /ip4/1.2.3.4
+    let ip_addr_bytes = vec![0x04, 1, 2, 3, 4];
+
+    let mut multiaddrs_field = Vec::new();
+    for addr_bytes in [
+        tcp_addr_bytes,
+        quic_addr_bytes,
+        udp_addr_bytes,
+        wss_addr_bytes,
+        ip_addr_bytes,
+    ] {
+        multiaddrs_field.extend_from_slice(&(addr_bytes.len() as u16).to_be_bytes());
+        multiaddrs_field.extend(addr_bytes);
+    }
+
+    // Signing key
+    let key_secp256k1_base64 = "MaZivCR1kZsI2/1MuSw9mhnLQYqETWwjfcWpyiS20uw=";
+    let mut key_secp256k1_bytes = STANDARD.decode(key_secp256k1_base64).unwrap();
+    let key = CombinedKey::secp256k1_from_bytes(&mut key_secp256k1_bytes).unwrap();
+
+    let enr = EnrBuilder::new("v4")
+        .add_value(WAKU2_MULTIADDR_ENR_KEY, &multiaddrs_field)
+        .build(&key)
+        .expect("valid enr");
+
+    // When
+    let tcp_addrs = enr.tcp_multiaddrs();
+    let quic_addrs = enr.quic_multiaddrs();
+    let other_addrs = enr.other_multiaddrs();
+
+    // Then
+    assert_eq!(
+        tcp_addrs,
+        vec![
+            "/ip4/1.2.3.4/tcp/60000".parse::<Multiaddr>().unwrap(),
+            "/dns4/example.com/tcp/443/wss".parse().unwrap(),
+        ]
+    );
+    assert_eq!(
+        quic_addrs,
+        vec![
+            "/ip4/1.2.3.4/udp/9000/quic-v1"
+                .parse::<Multiaddr>()
+                .unwrap(),
+            "/ip4/1.2.3.4/udp/9001".parse().unwrap(),
+        ]
+    );
+    assert_eq!(
+        other_addrs,
+        vec!["/ip4/1.2.3.4".parse::<Multiaddr>().unwrap()]
+    );
+}
+
+#[test]
+fn test_waku_enr_webtransport_certhash_multiaddr() {
+    // Given
+    let webtransport_addr: Multiaddr = "/ip4/1.2.3.4/udp/4433/quic-v1/webtransport\
+        /certhash/uEiDKBK3UTkz_2cx_MIfOcW3WSiE8np6eXTMqBuPQluhmwA\
+        /certhash/uEiBJngL83KbpNZrxL--yNSOqpGcY1TL4Uk_FVs6urECBug"
+        .parse()
+        .unwrap();
+
+    // Signing key
+    let key_secp256k1_base64 = "MaZivCR1kZsI2/1MuSw9mhnLQYqETWwjfcWpyiS20uw=";
+    let mut key_secp256k1_bytes = STANDARD.decode(key_secp256k1_base64).unwrap();
+    let key = CombinedKey::secp256k1_from_bytes(&mut key_secp256k1_bytes).unwrap();
+
+    let enr_base64 = EnrBuilder::new("v4")
+        .multiaddrs(vec![webtransport_addr.clone()])
+        .build(&key)
+        .expect("valid enr")
+        .to_base64();
+
+    // When
+    let enr: Enr<CombinedKey> = enr_base64.parse().expect("valid enr");
+
+    // Then
+    assert!(matches!(enr.multiaddrs(), Some(addrs) if addrs == vec![webtransport_addr.clone()]));
+    assert_eq!(enr.quic_multiaddrs(), vec![webtransport_addr]);
+}
+
+#[test]
+fn test_waku_enr_unknown_fields_are_preserved() {
+    // Given
+    // Signing key
+    let key_secp256k1_base64 = "MaZivCR1kZsI2/1MuSw9mhnLQYqETWwjfcWpyiS20uw=";
+    let mut key_secp256k1_bytes = STANDARD.decode(key_secp256k1_base64).unwrap();
+    let key = CombinedKey::secp256k1_from_bytes(&mut key_secp256k1_bytes).unwrap();
+
+    let custom_value = b"custom-value".to_vec();
+    let enr_base64 = EnrBuilder::new("v4")
+        .network_id(42)
+        .add_value("custom", &custom_value)
+        .build(&key)
+        .expect("valid enr")
+        .to_base64();
+
+    // When
+    let mut enr: Enr<CombinedKey> = enr_base64.parse().expect("valid enr");
+    enr.insert(WAKU2_NETWORK_ID_ENR_KEY, &7u32, &key)
+        .expect("re-sign the enr");
+    let reencoded: Enr<CombinedKey> = enr.to_base64().parse().expect("valid enr");
+
+    // Then
+    assert_eq!(enr.unknown_fields(), vec![b"custom".to_vec()]);
+    assert_eq!(reencoded.unknown_fields(), vec![b"custom".to_vec()]);
+    assert_eq!(reencoded.get("custom"), Some(custom_value.as_slice()));
+    assert!(matches!(reencoded.network_id(), Some(7)));
+}
+
+#[test]
+fn test_build_waku_enr_with_secp256k1_and_ed25519_keys() {
+    // Given
+    let ip: Ipv4Addr = "1.2.3.4".parse().unwrap();
+    let capabilities = WakuEnrCapabilities::RELAY | WakuEnrCapabilities::STORE;
+
+    let keys = [
+        CombinedKey::generate_secp256k1(),
+        CombinedKey::generate_ed25519(),
+    ];
+
+    for key in keys {
+        // When
+        let enr = build_waku_enr(&key, ip, 60000, 9000, capabilities).expect("valid enr");
+        let decoded: Enr<CombinedKey> = enr.to_base64().parse().expect("valid enr");
+
+        // Then
+        assert!(enr.verify());
+        assert_eq!(decoded, enr);
+        assert_eq!(decoded.ip4(), Some(ip));
+        assert_eq!(decoded.tcp4(), Some(60000));
+        assert_eq!(decoded.udp4(), Some(9000));
+        assert!(matches!(decoded.waku2(), Some(value) if value == capabilities));
+    }
+}
+
+#[test]
+fn test_update_waku_enr_bumps_the_sequence_number() {
+    // Given
+    let key = CombinedKey::generate_ed25519();
+    let mut enr = build_waku_enr(
+        &key,
+        "1.2.3.4".parse().unwrap(),
+        60000,
+        9000,
+        WakuEnrCapabilities::RELAY,
+    )
+    .expect("valid enr");
+    let seq = enr.seq();
+
+    let new_ip: Ipv4Addr = "5.6.7.8".parse().unwrap();
+
+    // When
+    update_waku_enr(
+        &mut enr,
+        &key,
+        new_ip,
+        60001,
+        9001,
+        WakuEnrCapabilities::RELAY | WakuEnrCapabilities::FILTER,
+    )
+    .expect("update the enr");
+    let decoded: Enr<CombinedKey> = enr.to_base64().parse().expect("valid enr");
+
+    // Then
+    assert!(enr.verify());
+    assert_eq!(enr.seq(), seq + 1);
+    assert_eq!(decoded, enr);
+    assert_eq!(decoded.ip4(), Some(new_ip));
+    assert_eq!(decoded.tcp4(), Some(60001));
+    assert_eq!(decoded.udp4(), Some(9001));
+    assert!(decoded
+        .waku2()
+        .is_some_and(|caps| caps.supports(Capability::Filter)));
+}
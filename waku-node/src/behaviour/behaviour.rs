@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use libp2p::identity::PublicKey;
 use libp2p::swarm::behaviour::toggle;
 use libp2p::swarm::keep_alive;
@@ -23,20 +24,26 @@ pub struct Behaviour {
 }
 
 impl Behaviour {
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: Config) -> anyhow::Result<Self> {
         let keep_alive = toggle::Toggle::from(config.keep_alive.map(|_| Default::default()));
         let ping = toggle::Toggle::from(config.ping.map(|_| Default::default()));
         let identify = identify::Behaviour::new(
             identify::Config::new("/ipfs/id/1.0.0".to_owned(), config.local_public_key)
                 .with_agent_version(format!("rust-waku/{}", env!("CARGO_PKG_VERSION"))),
         );
-        let waku_relay = toggle::Toggle::from(config.relay.map(|_| Default::default()));
+        let waku_relay = toggle::Toggle::from(
+            config
+                .relay
+                .map(|relay| waku_relay::Behaviour::new(relay.validate_messages))
+                .transpose()
+                .map_err(|e| anyhow!("failed to create the waku relay behaviour: {e}"))?,
+        );
 
-        Self {
+        Ok(Self {
             keep_alive,
             ping,
             identify,
             waku_relay,
-        }
+        })
     }
 }
@@ -1,6 +1,9 @@
+use std::time::Duration;
+
 use libp2p::identity::Keypair;
 
 use crate::config::waku_relay_config::WakuRelayConfig;
+use crate::transport::TransportKind;
 
 #[derive(Debug, Clone)]
 pub struct NodeConfig {
@@ -8,6 +11,21 @@ pub struct NodeConfig {
     pub keepalive: bool,
     pub ping: bool,
     pub relay: Option<WakuRelayConfig>,
+    /// Time during which a discovered peer that failed to connect is not redialed.
+    pub discovery_dial_cooldown: Duration,
+    /// The Waku network identifier. Peers advertising a different network are not dialed.
+    pub network_id: Option<u32>,
+    /// The maximum number of connected peers. When reached, the lowest-scored peers are
+    /// disconnected to make room for new connections.
+    pub max_peers: Option<usize>,
+    /// Count the relay messages received per content topic, for at most the given number of
+    /// distinct content topics. Messages on further content topics are counted together.
+    pub content_topic_metrics: Option<usize>,
+    /// The number of events buffered until the application receives them. The node stops
+    /// processing network events while the buffer is full. Must be greater than zero.
+    pub event_buffer_size: usize,
+    /// The network transport the node dials and listens on.
+    pub transport: TransportKind,
 }
 
 impl Default for NodeConfig {
@@ -17,6 +35,12 @@ impl Default for NodeConfig {
             keepalive: false,
             ping: false,
             relay: None,
+            discovery_dial_cooldown: Duration::from_secs(60),
+            network_id: None,
+            max_peers: None,
+            content_topic_metrics: None,
+            event_buffer_size: 32,
+            transport: TransportKind::Tcp,
         }
     }
 }
@@ -54,4 +78,34 @@ impl NodeConfigBuilder {
         self.config.relay = Some(config);
         self
     }
+
+    pub fn with_discovery_dial_cooldown(mut self, cooldown: Duration) -> Self {
+        self.config.discovery_dial_cooldown = cooldown;
+        self
+    }
+
+    pub fn with_network_id(mut self, network_id: u32) -> Self {
+        self.config.network_id = Some(network_id);
+        self
+    }
+
+    pub fn with_max_peers(mut self, max_peers: usize) -> Self {
+        self.config.max_peers = Some(max_peers);
+        self
+    }
+
+    pub fn with_content_topic_metrics(mut self, max_topics: usize) -> Self {
+        self.config.content_topic_metrics = Some(max_topics);
+        self
+    }
+
+    pub fn with_event_buffer_size(mut self, size: usize) -> Self {
+        self.config.event_buffer_size = size;
+        self
+    }
+
+    pub fn with_transport(mut self, kind: TransportKind) -> Self {
+        self.config.transport = kind;
+        self
+    }
 }
@@ -3,6 +3,9 @@ use libp2p::PeerId;
 #[derive(Debug, Clone, Default)]
 pub struct WakuRelayConfig {
     pub static_nodes: Vec<PeerId>,
+    /// Whether received messages are held until the application reports their validation
+    /// result. Otherwise, messages are forwarded as soon as they are received.
+    pub validate_messages: bool,
 }
 
 #[derive(Default)]
@@ -23,4 +26,9 @@ impl WakuRelayConfigBuilder {
         self.config.static_nodes = nodes;
         self
     }
+
+    pub fn validate_messages(&mut self, enable: bool) -> &mut Self {
+        self.config.validate_messages = enable;
+        self
+    }
 }
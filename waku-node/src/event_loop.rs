@@ -1,7 +1,9 @@
 pub use command::*;
 pub use event::*;
 pub use event_loop::*;
+pub use validation_result::*;
 
 mod command;
 mod event;
 mod event_loop;
+mod validation_result;
@@ -1,4 +1,6 @@
-use libp2p::Multiaddr;
+use std::time::Duration;
+
+use libp2p::{Multiaddr, PeerId};
 use strum_macros::Display;
 use tokio::sync::oneshot;
 
@@ -15,6 +17,31 @@ pub enum Command {
         address: Multiaddr,
         sender: oneshot::Sender<anyhow::Result<()>>,
     },
+    SwitchDialPeer {
+        peer_id: PeerId,
+        addresses: Vec<Multiaddr>,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
+    SwitchAddExternalAddress {
+        address: Multiaddr,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
+    SwitchAddObservedAddress {
+        address: Multiaddr,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
+    RefreshEnr {
+        sender: oneshot::Sender<anyhow::Result<String>>,
+    },
+    DialDiscoveredPeer {
+        peer_id: PeerId,
+        address: Multiaddr,
+        sender: oneshot::Sender<anyhow::Result<bool>>,
+    },
+    LeaveNetwork {
+        timeout: Duration,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    },
     RelaySubscribe {
         pubsub_topic: PubsubTopic,
         sender: oneshot::Sender<anyhow::Result<()>>,
@@ -42,6 +69,52 @@ impl Command {
         Command::SwitchDial { address, sender }
     }
 
+    pub fn switch_dial_peer(
+        peer_id: PeerId,
+        addresses: Vec<Multiaddr>,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    ) -> Self {
+        Command::SwitchDialPeer {
+            peer_id,
+            addresses,
+            sender,
+        }
+    }
+
+    pub fn switch_add_external_address(
+        address: Multiaddr,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    ) -> Self {
+        Command::SwitchAddExternalAddress { address, sender }
+    }
+
+    pub fn switch_add_observed_address(
+        address: Multiaddr,
+        sender: oneshot::Sender<anyhow::Result<()>>,
+    ) -> Self {
+        Command::SwitchAddObservedAddress { address, sender }
+    }
+
+    pub fn refresh_enr(sender: oneshot::Sender<anyhow::Result<String>>) -> Self {
+        Command::RefreshEnr { sender }
+    }
+
+    pub fn dial_discovered_peer(
+        peer_id: PeerId,
+        address: Multiaddr,
+        sender: oneshot::Sender<anyhow::Result<bool>>,
+    ) -> Self {
+        Command::DialDiscoveredPeer {
+            peer_id,
+            address,
+            sender,
+        }
+    }
+
+    pub fn leave_network(timeout: Duration, sender: oneshot::Sender<anyhow::Result<()>>) -> Self {
+        Command::LeaveNetwork { timeout, sender }
+    }
+
     pub fn relay_subscribe(
         topic: PubsubTopic,
         sender: oneshot::Sender<anyhow::Result<()>>,
@@ -1,12 +1,87 @@
+use std::io;
+
+use libp2p::core::transport::TransportError;
+use libp2p::swarm::{DialError, ListenError};
+use libp2p::PeerId;
 use strum_macros::Display;
 
 use waku_core::message::WakuMessage;
 use waku_core::pubsub_topic::PubsubTopic;
+use waku_relay::gossipsub::MessageId;
+
+use crate::transport::UpgradeFailure;
 
 #[derive(Debug, Display)]
 pub enum Event {
     WakuRelayMessage {
+        /// The peer that forwarded us the message.
+        propagation_source: PeerId,
+        /// The message identifier, referenced when reporting the message validation result.
+        message_id: MessageId,
         pubsub_topic: PubsubTopic,
         message: WakuMessage,
     },
+    /// A connection with a peer failed during the transport upgrade.
+    UpgradeError {
+        peer: Option<PeerId>,
+        error: UpgradeError,
+    },
+}
+
+/// The outcome of emitting an event to the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventDelivery {
+    /// The event was queued in the event buffer.
+    Buffered,
+    /// The event was dropped, the event buffer being full or the application no longer receiving
+    /// events.
+    Dropped,
+}
+
+/// A connection transport upgrade failure.
+#[derive(Debug, thiserror::Error)]
+pub enum UpgradeError {
+    /// The security handshake or the stream multiplexer negotiation failed.
+    #[error("transport upgrade failed: {0}")]
+    Transport(String),
+    /// The peer identity obtained during the security handshake does not match the expected one.
+    #[error("wrong peer id obtained: {obtained}")]
+    WrongPeerId { obtained: PeerId },
+}
+
+impl UpgradeError {
+    pub(crate) fn from_dial_error(error: &DialError) -> Option<Self> {
+        match error {
+            DialError::WrongPeerId { obtained, .. } => Some(Self::WrongPeerId {
+                obtained: *obtained,
+            }),
+            DialError::Transport(errors) => errors.iter().find_map(|(_, error)| match error {
+                TransportError::Other(err) => Self::from_transport_error(err),
+                TransportError::MultiaddrNotSupported(_) => None,
+            }),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_listen_error(error: &ListenError) -> Option<Self> {
+        match error {
+            ListenError::WrongPeerId { obtained, .. } => Some(Self::WrongPeerId {
+                obtained: *obtained,
+            }),
+            ListenError::Transport(TransportError::Other(err)) => Self::from_transport_error(err),
+            _ => None,
+        }
+    }
+
+    /// Looks for an upgrade failure within the nested transport errors. Raw transport failures,
+    /// e.g., a refused connection, are not upgrade errors.
+    fn from_transport_error(error: &io::Error) -> Option<Self> {
+        let mut inner = error.get_ref()?;
+        loop {
+            if let Some(failure) = inner.downcast_ref::<UpgradeFailure>() {
+                return Some(Self::Transport(failure.to_string()));
+            }
+            inner = inner.downcast_ref::<io::Error>()?.get_ref()?;
+        }
+    }
 }
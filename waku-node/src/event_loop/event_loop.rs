@@ -1,17 +1,70 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
 use anyhow::anyhow;
 use futures::StreamExt;
-use libp2p::swarm::SwarmEvent;
-use log::{debug, error, info, trace};
-use tokio::sync::mpsc;
+use libp2p::swarm::dial_opts::DialOpts;
+use libp2p::swarm::{AddressScore, SwarmEvent};
+use libp2p::{Multiaddr, PeerId};
+use log::{debug, error, info, trace, warn};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::sleep_until;
+
+use waku_relay::gossipsub::MessageAcceptance;
 
 use crate::behaviour;
 use crate::event_loop::command::Command;
-use crate::event_loop::event::Event;
+use crate::event_loop::event::{Event, EventDelivery, UpgradeError};
+use crate::event_loop::validation_result::ValidationResult;
+use crate::local_enr::LocalEnr;
+use crate::metrics::ContentTopicMetrics;
+use crate::peer_pruning::{select_peers_to_prune, PruningCandidate};
+
+/// Queue an event in the application event buffer, without waiting for the buffer to have room.
+///
+/// Returns whether the event was buffered or dropped. Dropped events are logged.
+pub fn emit_event(event_sink: &mpsc::Sender<Event>, event: Event) -> EventDelivery {
+    match event_sink.try_send(event) {
+        Ok(()) => EventDelivery::Buffered,
+        Err(mpsc::error::TrySendError::Full(event)) => {
+            warn!("event buffer full, dropping '{event}' event");
+            EventDelivery::Dropped
+        }
+        Err(mpsc::error::TrySendError::Closed(event)) => {
+            error!("send '{event}' event failed: event receiver closed");
+            EventDelivery::Dropped
+        }
+    }
+}
+
+/// Resolves once the deadline passes, or never if there is no deadline.
+async fn sleep_until_deadline(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => sleep_until(deadline).await,
+        None => futures::future::pending().await,
+    }
+}
 
 pub struct EventLoop {
     switch: libp2p::Swarm<behaviour::Behaviour>,
     command_source: mpsc::Receiver<Command>,
     event_sink: mpsc::Sender<Event>,
+    /// The relay message validation results reported by the application.
+    validation_result_source: mpsc::Receiver<ValidationResult>,
+    discovery_dial_cooldown: Duration,
+    /// Discovered peers with an in-flight dial attempt.
+    discovered_peers_dialing: HashSet<PeerId>,
+    /// Discovered peers whose last dial attempt failed, and when it failed.
+    discovered_peers_failed: HashMap<PeerId, Instant>,
+    /// The maximum number of connected peers.
+    max_peers: Option<usize>,
+    /// The local node's ENR, kept in sync with the switch addresses.
+    local_enr: Arc<RwLock<LocalEnr>>,
+    content_topic_metrics: Option<ContentTopicMetrics>,
+    /// The pending leave network request and its deadline. It is answered once all the
+    /// connections are closed, or with an error once the deadline passes.
+    leaving_network: Option<(tokio::time::Instant, oneshot::Sender<anyhow::Result<()>>)>,
 }
 
 impl EventLoop {
@@ -19,28 +72,93 @@ impl EventLoop {
         switch: libp2p::Swarm<behaviour::Behaviour>,
         command_source: mpsc::Receiver<Command>,
         event_sink: mpsc::Sender<Event>,
+        validation_result_source: mpsc::Receiver<ValidationResult>,
+        discovery_dial_cooldown: Duration,
+        max_peers: Option<usize>,
+        local_enr: Arc<RwLock<LocalEnr>>,
+        content_topic_metrics: Option<ContentTopicMetrics>,
     ) -> Self {
         Self {
             switch,
             command_source,
             event_sink,
+            validation_result_source,
+            discovery_dial_cooldown,
+            discovered_peers_dialing: HashSet::new(),
+            discovered_peers_failed: HashMap::new(),
+            max_peers,
+            local_enr,
+            content_topic_metrics,
+            leaving_network: None,
         }
     }
 
     pub async fn dispatch(mut self) {
         loop {
+            // While leaving the network keep polling the switch, dropping the events the
+            // application does not consume, so the connections get closed
+            let event_buffer_full =
+                self.event_sink.capacity() == 0 && self.leaving_network.is_none();
+            let leave_network_deadline =
+                self.leaving_network.as_ref().map(|(deadline, _)| *deadline);
+
             tokio::select! {
                 command = self.command_source.recv() => match command {
                     Some(cmd) => { self.handle_command(cmd).await; },
                     None => { debug!("got empty command. terminating node event loop"); return },
                 },
-                event = self.switch.select_next_some() => match event {
+                Some(result) = self.validation_result_source.recv() => {
+                    self.handle_validation_result(result);
+                },
+                // Pause polling the switch while the event buffer is full, until the application
+                // consumes an event
+                _ = self.event_sink.reserve(), if event_buffer_full => {},
+                _ = sleep_until_deadline(leave_network_deadline) => {
+                    self.leave_network_timed_out();
+                },
+                event = self.switch.select_next_some(), if !event_buffer_full => match event {
                     SwarmEvent::NewListenAddr { address, .. } => {
                         // TODO: Send this event through the event_sink
-                        info!("switch listening on: {address:?}")
+                        info!("switch listening on: {address:?}");
+                        self.update_local_enr();
+                    },
+                    SwarmEvent::ExpiredListenAddr { address, .. } => {
+                        info!("switch no longer listening on: {address:?}");
+                        self.update_local_enr();
+                    },
+                    SwarmEvent::ConnectionEstablished { peer_id, num_established, .. } => {
+                        self.discovered_peers_dialing.remove(&peer_id);
+                        self.discovered_peers_failed.remove(&peer_id);
+
+                        if self.leaving_network.is_some() {
+                            debug!("leaving the network, closing the connection to {peer_id}");
+                            let _ = self.switch.disconnect_peer_id(peer_id);
+                        } else if num_established.get() == 1 {
+                            self.prune_peers();
+                        }
+                    },
+                    SwarmEvent::ConnectionClosed { num_established: 0, .. } => {
+                        self.check_network_left();
+                    },
+                    SwarmEvent::OutgoingConnectionError { peer_id, error } => {
+                        if let Some(peer_id) = peer_id {
+                            if self.discovered_peers_dialing.remove(&peer_id) {
+                                self.prune_discovered_peers_failed();
+                                self.discovered_peers_failed.insert(peer_id, Instant::now());
+                            }
+                        }
+
+                        if let Some(error) = UpgradeError::from_dial_error(&error) {
+                            self.handle_upgrade_error(peer_id, error);
+                        }
+                    },
+                    SwarmEvent::IncomingConnectionError { error, .. } => {
+                        if let Some(error) = UpgradeError::from_listen_error(&error) {
+                            self.handle_upgrade_error(None, error);
+                        }
                     },
                     SwarmEvent::Behaviour(behaviour::Event::WakuRelay(event)) => {
-                        self.handle_waku_relay_event(event).await;
+                        self.handle_waku_relay_event(event);
                     },
                     SwarmEvent::Behaviour(event) => debug!("{event:?}"),
                     _ => {}
@@ -76,6 +194,128 @@ impl EventLoop {
                     error!("send '{}' command response failed: {:?}.", "switch_dial", e);
                 });
             }
+            Command::SwitchDialPeer {
+                peer_id,
+                addresses,
+                sender,
+            } => {
+                trace!("handle command: {}", "switch_dial_peer");
+
+                // The switch attempts each address until a connection is established
+                let opts = DialOpts::peer_id(peer_id).addresses(addresses).build();
+                match self.switch.dial(opts) {
+                    Ok(_) => sender.send(Ok(())),
+                    Err(e) => sender.send(Err(e.into())),
+                }
+                .unwrap_or_else(|e| {
+                    error!(
+                        "send '{}' command response failed: {:?}.",
+                        "switch_dial_peer", e
+                    );
+                });
+            }
+            Command::SwitchAddExternalAddress { address, sender } => {
+                trace!("handle command: {}", "switch_add_external_address");
+
+                self.switch
+                    .add_external_address(address, AddressScore::Infinite);
+                self.update_local_enr();
+
+                sender.send(Ok(())).unwrap_or_else(|e| {
+                    error!(
+                        "send '{}' command response failed: {:?}.",
+                        "switch_add_external_address", e
+                    );
+                });
+            }
+            Command::SwitchAddObservedAddress { address, sender } => {
+                trace!("handle command: {}", "switch_add_observed_address");
+
+                // Scored as the addresses reported by identify, not advertised in the local
+                // ENR until it is refreshed
+                self.switch
+                    .add_external_address(address, AddressScore::Finite(1));
+
+                sender.send(Ok(())).unwrap_or_else(|e| {
+                    error!(
+                        "send '{}' command response failed: {:?}.",
+                        "switch_add_observed_address", e
+                    );
+                });
+            }
+            Command::RefreshEnr { sender } => {
+                trace!("handle command: {}", "refresh_enr");
+
+                sender.send(self.refresh_local_enr()).unwrap_or_else(|e| {
+                    error!("send '{}' command response failed: {:?}.", "refresh_enr", e);
+                });
+            }
+            Command::DialDiscoveredPeer {
+                peer_id,
+                address,
+                sender,
+            } => {
+                trace!("handle command: {}", "dial_discovered_peer");
+
+                self.prune_discovered_peers_failed();
+
+                let suppressed_reason = if self.discovered_peers_dialing.contains(&peer_id) {
+                    Some("a dial attempt is in progress")
+                } else if self.discovered_peers_failed.contains_key(&peer_id) {
+                    Some("the last dial attempt failed within the cooldown")
+                } else {
+                    None
+                };
+                if let Some(reason) = suppressed_reason {
+                    debug!("suppress dial to discovered peer {peer_id}: {reason}");
+                    sender.send(Ok(false)).unwrap_or_else(|e| {
+                        error!(
+                            "send '{}' command response failed: {:?}.",
+                            "dial_discovered_peer", e
+                        );
+                    });
+                    return;
+                }
+
+                let opts = DialOpts::peer_id(peer_id).addresses(vec![address]).build();
+                match self.switch.dial(opts) {
+                    Ok(_) => {
+                        self.discovered_peers_failed.remove(&peer_id);
+                        self.discovered_peers_dialing.insert(peer_id);
+                        sender.send(Ok(true))
+                    }
+                    Err(e) => sender.send(Err(e.into())),
+                }
+                .unwrap_or_else(|e| {
+                    error!(
+                        "send '{}' command response failed: {:?}.",
+                        "dial_discovered_peer", e
+                    );
+                });
+            }
+            Command::LeaveNetwork { timeout, sender } => {
+                trace!("handle command: {}", "leave_network");
+
+                let draining_peers = match self.switch.behaviour_mut().waku_relay.as_mut() {
+                    Some(waku_relay) => waku_relay.leave_network(),
+                    None => Vec::new(),
+                };
+
+                // Close right away the connections not drained by the relay protocol
+                let other_peers = self
+                    .switch
+                    .connected_peers()
+                    .filter(|peer_id| !draining_peers.contains(peer_id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for peer_id in other_peers {
+                    let _ = self.switch.disconnect_peer_id(peer_id);
+                }
+
+                let deadline = tokio::time::Instant::now() + timeout;
+                self.leaving_network = Some((deadline, sender));
+                self.check_network_left();
+            }
 
             Command::RelaySubscribe {
                 pubsub_topic,
@@ -189,23 +429,221 @@ impl EventLoop {
         }
     }
 
-    async fn handle_waku_relay_event(&mut self, event: waku_relay::Event) {
+    /// Answer the pending leave network request once all the connections are closed.
+    fn check_network_left(&mut self) {
+        if self.switch.connected_peers().next().is_some() {
+            return;
+        }
+
+        if let Some((_, sender)) = self.leaving_network.take() {
+            info!("left the network, all connections closed");
+            sender.send(Ok(())).unwrap_or_else(|e| {
+                error!(
+                    "send '{}' command response failed: {:?}.",
+                    "leave_network", e
+                );
+            });
+        }
+    }
+
+    /// Close abruptly the connections still open past the leave network deadline, and answer the
+    /// pending leave network request with an error.
+    fn leave_network_timed_out(&mut self) {
+        let sender = match self.leaving_network.take() {
+            Some((_, sender)) => sender,
+            None => return,
+        };
+
+        let remaining_peers = self.switch.connected_peers().cloned().collect::<Vec<_>>();
+        warn!(
+            "leave network timed out, closing {} remaining connections",
+            remaining_peers.len()
+        );
+        for peer_id in &remaining_peers {
+            let _ = self.switch.disconnect_peer_id(*peer_id);
+        }
+
+        sender
+            .send(Err(anyhow!(
+                "leave network timed out with {} peers connected",
+                remaining_peers.len()
+            )))
+            .unwrap_or_else(|e| {
+                error!(
+                    "send '{}' command response failed: {:?}.",
+                    "leave_network", e
+                );
+            });
+    }
+
+    /// Forget the discovered peers whose last dial attempt failed before the cooldown.
+    fn prune_discovered_peers_failed(&mut self) {
+        let cooldown = self.discovery_dial_cooldown;
+        self.discovered_peers_failed
+            .retain(|_, failed_at| failed_at.elapsed() < cooldown);
+    }
+
+    /// Rebuild the local ENR with the switch addresses. External addresses take precedence over
+    /// the listen addresses.
+    fn update_local_enr(&mut self) {
+        if let Err(e) = self.refresh_local_enr() {
+            warn!("local ENR update failed: {e}");
+        }
+    }
+
+    /// Rebuild and re-sign the local ENR if the switch addresses changed, bumping its sequence
+    /// number. Returns the `enr:` text representation of the current local ENR.
+    fn refresh_local_enr(&mut self) -> anyhow::Result<String> {
+        let mut addresses: Vec<Multiaddr> = self
+            .switch
+            .external_addresses()
+            .map(|record| record.addr.clone())
+            .collect();
+        if addresses.is_empty() {
+            addresses = self.switch.listeners().cloned().collect();
+        }
+
+        let mut local_enr = self.local_enr.write().expect("local ENR lock poisoned");
+        let updated = local_enr.update_addresses(&addresses)?;
+
+        let enr = local_enr.enr().to_base64();
+        if updated {
+            info!("local ENR updated: {enr}");
+        }
+
+        Ok(enr)
+    }
+
+    /// Disconnect the lowest-scored peers if the number of connected peers reached the limit.
+    ///
+    /// Relay mesh peers and explicit peers are never disconnected. The pending relay control
+    /// messages are sent to the pruned peers before disconnecting them. The peers already being
+    /// drained are not counted, nor pruned again.
+    fn prune_peers(&mut self) {
+        let max_peers = match self.max_peers {
+            Some(max_peers) => max_peers,
+            None => return,
+        };
+
+        let waku_relay = self.switch.behaviour().waku_relay.as_ref();
+        let candidates = self
+            .switch
+            .connected_peers()
+            .filter(|peer_id| {
+                !waku_relay
+                    .map(|relay| relay.is_draining_peer(peer_id))
+                    .unwrap_or(false)
+            })
+            .map(|peer_id| PruningCandidate {
+                peer_id: *peer_id,
+                score: waku_relay.and_then(|relay| relay.peer_score(peer_id)),
+                protected: waku_relay
+                    .map(|relay| relay.is_mesh_peer(peer_id) || relay.is_explicit_peer(peer_id))
+                    .unwrap_or(false),
+            })
+            .collect::<Vec<_>>();
+
+        for peer_id in select_peers_to_prune(&candidates, max_peers) {
+            debug!("peer limit reached, disconnecting low-scored peer: {peer_id}");
+            match self.switch.behaviour_mut().waku_relay.as_mut() {
+                Some(waku_relay) => waku_relay.disconnect_peer(&peer_id, true),
+                None => {
+                    let _ = self.switch.disconnect_peer_id(peer_id);
+                }
+            }
+        }
+    }
+
+    /// Forward the application validation result of a relay message to the relay behaviour.
+    fn handle_validation_result(&mut self, result: ValidationResult) {
+        trace!("handle validation result: {}", result.message_id);
+
+        let waku_relay = match self.switch.behaviour_mut().waku_relay.as_mut() {
+            Some(waku_relay) => waku_relay,
+            None => {
+                warn!("relay protocol disabled, dropping message validation result");
+                return;
+            }
+        };
+
+        match waku_relay.report_message_validation_result(
+            &result.message_id,
+            &result.propagation_source,
+            result.acceptance,
+        ) {
+            Ok(true) => {}
+            Ok(false) => debug!(
+                "message no longer in the message cache: {}",
+                result.message_id
+            ),
+            Err(e) => error!("report message validation result failed: {e}"),
+        }
+    }
+
+    /// Queue an event in the application event buffer.
+    ///
+    /// The switch is not polled while the buffer is full, so events are only dropped if the
+    /// application no longer receives them, or while leaving the network.
+    fn emit_event(&mut self, event: Event) -> EventDelivery {
+        emit_event(&self.event_sink, event)
+    }
+
+    fn handle_upgrade_error(&mut self, peer: Option<PeerId>, error: UpgradeError) {
+        debug!("connection upgrade failed (peer: {peer:?}): {error}");
+
+        let _ = self.emit_event(Event::UpgradeError { peer, error });
+    }
+
+    fn handle_waku_relay_event(&mut self, event: waku_relay::Event) {
         match event {
             waku_relay::Event::Message {
+                propagation_source,
+                message_id,
                 pubsub_topic,
                 message,
             } => {
                 trace!("handle event: {}", "waku_relay_message");
 
-                self.event_sink
-                    .send(Event::WakuRelayMessage {
-                        pubsub_topic,
-                        message,
-                    })
-                    .await
-                    .unwrap_or_else(|e| {
-                        error!("send '{}' event failed: {:?}.", "waku_relay_message", e);
-                    });
+                if let Some(metrics) = &self.content_topic_metrics {
+                    metrics.register_message(&message.content_topic);
+                }
+
+                let delivery = self.emit_event(Event::WakuRelayMessage {
+                    propagation_source,
+                    message_id: message_id.clone(),
+                    pubsub_topic,
+                    message,
+                });
+
+                // The application will never report the validation result of a dropped message,
+                // ignore it so it is not held back in the message cache
+                let validate_messages = self
+                    .switch
+                    .behaviour()
+                    .waku_relay
+                    .as_ref()
+                    .map_or(false, |waku_relay| waku_relay.config().validate_messages());
+                if delivery == EventDelivery::Dropped && validate_messages {
+                    self.handle_validation_result(ValidationResult::new(
+                        message_id,
+                        propagation_source,
+                        MessageAcceptance::Ignore,
+                    ));
+                }
+            }
+            waku_relay::Event::InvalidMessage {
+                propagation_source,
+                message_id,
+            } => {
+                trace!("handle event: {}", "waku_relay_invalid_message");
+
+                // Not a Waku message, reject it so it is neither forwarded nor held back
+                // waiting for the application validation result
+                self.handle_validation_result(ValidationResult::new(
+                    message_id,
+                    propagation_source,
+                    MessageAcceptance::Reject,
+                ));
             }
             _ => {}
         }
@@ -0,0 +1,26 @@
+use libp2p::PeerId;
+
+use waku_relay::gossipsub::{MessageAcceptance, MessageId};
+
+/// The application validation result of a received Waku relay message.
+#[derive(Debug)]
+pub struct ValidationResult {
+    pub message_id: MessageId,
+    /// The peer that forwarded us the message.
+    pub propagation_source: PeerId,
+    pub acceptance: MessageAcceptance,
+}
+
+impl ValidationResult {
+    pub fn new(
+        message_id: MessageId,
+        propagation_source: PeerId,
+        acceptance: MessageAcceptance,
+    ) -> Self {
+        Self {
+            message_id,
+            propagation_source,
+            acceptance,
+        }
+    }
+}
@@ -1,10 +1,18 @@
 pub use config::*;
 pub use event_loop::*;
+pub use local_enr::*;
+pub use metrics::*;
 pub use node::*;
+pub use peer_pruning::*;
+pub use subscription_manager::*;
 pub use transport::*;
 
 pub mod behaviour;
 mod config;
 mod event_loop;
+mod local_enr;
+mod metrics;
 mod node;
+mod peer_pruning;
+mod subscription_manager;
 pub mod transport;
@@ -0,0 +1,90 @@
+use anyhow::anyhow;
+use libp2p::identity::secp256k1;
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+
+use waku_enr::enr::{CombinedKey, Enr, EnrBuilder};
+use waku_enr::EnrBuilderExt;
+
+/// The local node's ENR.
+///
+/// The record is rebuilt, and its sequence number increased, every time the node's addresses
+/// change.
+pub struct LocalEnr {
+    key: CombinedKey,
+    network_id: Option<u32>,
+    addresses: Vec<Multiaddr>,
+    enr: Enr<CombinedKey>,
+}
+
+impl LocalEnr {
+    /// The ENR `v4` identity scheme requires a secp256k1 key.
+    pub fn new(keypair: &secp256k1::Keypair, network_id: Option<u32>) -> anyhow::Result<Self> {
+        let mut secret_key = keypair.secret().to_bytes();
+        let key = CombinedKey::secp256k1_from_bytes(&mut secret_key)
+            .map_err(|e| anyhow!("invalid ENR key: {e}"))?;
+
+        let enr = build_enr(&key, network_id, 1, &[])?;
+
+        Ok(Self {
+            key,
+            network_id,
+            addresses: Vec::new(),
+            enr,
+        })
+    }
+
+    pub fn enr(&self) -> &Enr<CombinedKey> {
+        &self.enr
+    }
+
+    /// Rebuild the ENR advertising the given addresses.
+    ///
+    /// Returns `false`, leaving the ENR and its sequence number untouched, if the addresses did
+    /// not change.
+    pub fn update_addresses(&mut self, addresses: &[Multiaddr]) -> anyhow::Result<bool> {
+        if self.addresses == addresses {
+            return Ok(false);
+        }
+
+        let seq = self.enr.seq() + 1;
+        self.enr = build_enr(&self.key, self.network_id, seq, addresses)?;
+        self.addresses = addresses.to_vec();
+        Ok(true)
+    }
+}
+
+fn build_enr(
+    key: &CombinedKey,
+    network_id: Option<u32>,
+    seq: u64,
+    addresses: &[Multiaddr],
+) -> anyhow::Result<Enr<CombinedKey>> {
+    let mut builder = EnrBuilder::new("v4");
+    builder.seq(seq);
+
+    // The first IPv4/TCP address is advertised through the standard ENR fields
+    let ip4_tcp = addresses.iter().find_map(|addr| {
+        let mut protocols = addr.iter();
+        match (protocols.next(), protocols.next()) {
+            (Some(Protocol::Ip4(ip)), Some(Protocol::Tcp(port))) => Some((ip, port)),
+            _ => None,
+        }
+    });
+    if let Some((ip, port)) = ip4_tcp {
+        builder.ip4(ip);
+        builder.tcp4(port);
+    }
+
+    if !addresses.is_empty() {
+        builder.multiaddrs(addresses.to_vec());
+    }
+
+    if let Some(network_id) = network_id {
+        builder.network_id(network_id);
+    }
+
+    builder
+        .build(key)
+        .map_err(|e| anyhow!("ENR build failed: {e}"))
+}
@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::registry::Registry;
+
+use waku_core::content_topic::{ContentTopic, NsContentTopic};
+
+/// The label aggregating the content topics past the distinct content topics limit.
+pub const OTHER_CONTENT_TOPICS_LABEL: &str = "other";
+/// The label aggregating the content topics not following the
+/// `/{application}/{version}/{name}/{encoding}` format.
+pub const MALFORMED_CONTENT_TOPICS_LABEL: &str = "malformed";
+
+/// The maximum length of a content topic label. Longer content topics are truncated.
+const MAX_CONTENT_TOPIC_LABEL_LENGTH: usize = 128;
+
+/// Normalize the content topic into a label value.
+///
+/// Characters other than ASCII alphanumerics and `/`, `-`, `_` and `.` are replaced with `_`, and
+/// the label is truncated to [`MAX_CONTENT_TOPIC_LABEL_LENGTH`] characters. Malformed content
+/// topics are labeled as [`MALFORMED_CONTENT_TOPICS_LABEL`].
+fn sanitize_content_topic_label(content_topic: &ContentTopic) -> String {
+    if content_topic.as_str().parse::<NsContentTopic>().is_err() {
+        return MALFORMED_CONTENT_TOPICS_LABEL.to_string();
+    }
+
+    content_topic
+        .as_str()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_CONTENT_TOPIC_LABEL_LENGTH)
+        .collect()
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ContentTopicLabel {
+    content_topic: String,
+}
+
+/// Counters of the relay messages delivered to the application, labeled by content topic.
+///
+/// The content topics are sanitized into safe label values, and messages on malformed content
+/// topics are counted under the [`MALFORMED_CONTENT_TOPICS_LABEL`] label. To bound the label
+/// cardinality, only the first `max_topics` distinct content topics get their own label. Messages
+/// on any other content topic are counted under the [`OTHER_CONTENT_TOPICS_LABEL`] label.
+#[derive(Clone)]
+pub struct ContentTopicMetrics {
+    max_topics: usize,
+    /// The content topics with their own label.
+    topics: Arc<Mutex<HashSet<String>>>,
+    /// The counters of the labels with at least one message, to read them without creating a
+    /// label in the family.
+    counters: Arc<Mutex<HashMap<String, Counter>>>,
+    messages: Family<ContentTopicLabel, Counter>,
+}
+
+impl ContentTopicMetrics {
+    pub fn new(registry: &mut Registry, max_topics: usize) -> Self {
+        let messages = Family::default();
+        registry.register(
+            "waku_relay_content_topic_messages",
+            "Number of relay messages received per content topic",
+            messages.clone(),
+        );
+
+        Self {
+            max_topics,
+            topics: Default::default(),
+            counters: Default::default(),
+            messages,
+        }
+    }
+
+    /// Register a relay message received on the content topic.
+    pub fn register_message(&self, content_topic: &ContentTopic) {
+        let label = self.label(content_topic);
+
+        let mut counters = self.counters.lock().expect("counters lock poisoned");
+        counters
+            .entry(label)
+            .or_insert_with_key(|label| {
+                self.messages
+                    .get_or_create(&ContentTopicLabel {
+                        content_topic: label.clone(),
+                    })
+                    .clone()
+            })
+            .inc();
+    }
+
+    /// The label the relay messages on the content topic are counted under.
+    ///
+    /// Malformed content topics do not count towards the distinct content topics limit.
+    fn label(&self, content_topic: &ContentTopic) -> String {
+        let label = sanitize_content_topic_label(content_topic);
+        if label == MALFORMED_CONTENT_TOPICS_LABEL {
+            return label;
+        }
+
+        let mut topics = self.topics.lock().expect("content topics lock poisoned");
+        if topics.contains(&label) || topics.len() < self.max_topics {
+            topics.insert(label.clone());
+            label
+        } else {
+            OTHER_CONTENT_TOPICS_LABEL.to_string()
+        }
+    }
+
+    /// The number of relay messages counted under the label, either a sanitized content topic,
+    /// [`OTHER_CONTENT_TOPICS_LABEL`] or [`MALFORMED_CONTENT_TOPICS_LABEL`].
+    pub fn messages(&self, label: &str) -> u64 {
+        self.counters
+            .lock()
+            .expect("counters lock poisoned")
+            .get(label)
+            .map_or(0, Counter::get)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_content_topic_labels() {
+        // Setup
+        let mut registry = Registry::default();
+        let metrics = ContentTopicMetrics::new(&mut registry, 3);
+
+        // Given
+        let long_name = "a".repeat(200);
+        let long_topic = format!("/test/1/{long_name}/proto");
+        let content_topics = vec![
+            "/test/1/chat room{}/proto".to_string(),
+            long_topic.clone(),
+            "not-a-content-topic".to_string(),
+            "/test/1/missing-encoding".to_string(),
+            "/test/1/chat-a/proto".to_string(),
+            "/test/1/chat-b/proto".to_string(),
+            "/test/1/chat-c/proto".to_string(),
+        ];
+
+        // When
+        for content_topic in &content_topics {
+            metrics.register_message(&ContentTopic::new(content_topic.as_str()));
+        }
+
+        // Then
+        let truncated_label = &long_topic[..128];
+        assert_eq!(metrics.messages("/test/1/chat_room__/proto"), 1);
+        assert_eq!(metrics.messages(truncated_label), 1);
+        assert_eq!(metrics.messages(MALFORMED_CONTENT_TOPICS_LABEL), 2);
+        assert_eq!(metrics.messages("/test/1/chat-a/proto"), 1);
+        assert_eq!(metrics.messages("/test/1/chat-b/proto"), 0);
+        assert_eq!(metrics.messages(OTHER_CONTENT_TOPICS_LABEL), 2);
+    }
+}
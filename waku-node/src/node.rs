@@ -1,21 +1,109 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use libp2p::identity::{secp256k1, Keypair, PublicKey};
+use libp2p::multiaddr::Protocol;
 use libp2p::swarm::SwarmBuilder;
 use libp2p::{Multiaddr, PeerId};
 use log::debug;
-use tokio::sync::{mpsc, oneshot};
+use prometheus_client::registry::Registry;
+use tokio::sync::{mpsc, oneshot, Mutex};
 
+use waku_core::content_topic::ContentTopic;
 use waku_core::message::WakuMessage;
 use waku_core::pubsub_topic::PubsubTopic;
+use waku_enr::enr::{Enr, EnrKey, EnrPublicKey};
+use waku_enr::EnrExt;
 
 use crate::behaviour::Behaviour;
 use crate::behaviour::Config as BehaviourConfig;
-use crate::event_loop::{Command, Event, EventLoop};
-use crate::transport::{default_transport, BoxedP2PTransport};
-use crate::NodeConfig;
+use crate::event_loop::{Command, Event, EventLoop, ValidationResult};
+use crate::local_enr::LocalEnr;
+use crate::metrics::ContentTopicMetrics;
+use crate::transport::{new_transport, BoxedP2PTransport};
+use crate::{NodeConfig, NodeConfigBuilder, SubscriptionManager, WakuRelayConfig};
+
+fn new_behaviour(config: &NodeConfig) -> anyhow::Result<Behaviour> {
+    Behaviour::new(BehaviourConfig {
+        local_public_key: config.keypair.public(),
+        keep_alive: config.keepalive.then_some(config.keepalive),
+        ping: config.ping.then_some(config.ping),
+        relay: config.relay.clone(),
+    })
+}
+
+/// Fluent builder of a [`Node`], composing the behaviours of the enabled protocols.
+///
+/// Wraps a [`NodeConfigBuilder`], but requires the transport keypair to be provided. The node
+/// options other than the protocol toggles are set on the wrapped builder via
+/// [`NodeBuilder::configure()`].
+#[derive(Debug, Default)]
+pub struct NodeBuilder {
+    keypair: Option<Keypair>,
+    config: NodeConfigBuilder,
+}
+
+impl NodeBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn keypair(mut self, keypair: Keypair) -> Self {
+        self.keypair = Some(keypair);
+        self
+    }
+
+    pub fn with_relay(mut self, config: WakuRelayConfig) -> Self {
+        self.config = self.config.with_waku_relay(config);
+        self
+    }
+
+    /// Apply the given settings to the wrapped node configuration builder.
+    pub fn configure(
+        mut self,
+        configure: impl FnOnce(NodeConfigBuilder) -> NodeConfigBuilder,
+    ) -> Self {
+        self.config = configure(self.config);
+        self
+    }
+
+    /// The node configuration. Fails if no keypair was provided.
+    pub fn build_config(self) -> anyhow::Result<NodeConfig> {
+        let keypair = self
+            .keypair
+            .ok_or_else(|| anyhow!("the node requires a transport keypair"))?;
+
+        Ok(self.config.keypair(keypair).build())
+    }
+
+    /// The composed swarm behaviour of the enabled protocols. Fails if no keypair was provided.
+    pub fn build_behaviour(self) -> anyhow::Result<Behaviour> {
+        let config = self.build_config()?;
+        new_behaviour(&config)
+    }
+
+    pub fn build_with_transport(self, transport: BoxedP2PTransport) -> anyhow::Result<Node> {
+        let config = self.build_config()?;
+        Node::new_with_transport(config, transport)
+    }
+
+    pub fn build(self) -> anyhow::Result<Node> {
+        let config = self.build_config()?;
+        Node::new(config)
+    }
+}
 
 pub struct Node {
     peer_id: PeerId,
+    network_id: Option<u32>,
     command_sender: mpsc::Sender<Command>,
     event_receiver: mpsc::Receiver<Event>,
+    validation_result_sender: mpsc::Sender<ValidationResult>,
+    subscriptions: Mutex<SubscriptionManager>,
+    local_enr: Arc<RwLock<LocalEnr>>,
+    metrics_registry: Registry,
+    content_topic_metrics: Option<ContentTopicMetrics>,
 }
 
 impl Node {
@@ -23,34 +111,61 @@ impl Node {
         config: NodeConfig,
         transport: BoxedP2PTransport,
     ) -> anyhow::Result<Self> {
+        if config.event_buffer_size == 0 {
+            anyhow::bail!("the event buffer size must be greater than zero");
+        }
+
         let peer_id = PeerId::from(&config.keypair.public());
+        // The ENR identity scheme requires a secp256k1 key
+        let enr_keypair = config
+            .keypair
+            .clone()
+            .try_into_secp256k1()
+            .map_err(|_| anyhow!("the node key must be a secp256k1 key"))?;
+        let local_enr = Arc::new(RwLock::new(LocalEnr::new(&enr_keypair, config.network_id)?));
 
         let switch = {
-            let behaviour = Behaviour::new(BehaviourConfig {
-                local_public_key: config.keypair.public(),
-                keep_alive: config.keepalive.then_some(config.keepalive),
-                ping: config.ping.then_some(config.ping),
-                relay: config.relay,
-            });
+            let behaviour = new_behaviour(&config)?;
             SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build()
         };
 
+        let mut metrics_registry = Registry::default();
+        let content_topic_metrics = config
+            .content_topic_metrics
+            .map(|max_topics| ContentTopicMetrics::new(&mut metrics_registry, max_topics));
+
         let (command_sender, command_receiver) = mpsc::channel(32);
-        let (event_sender, event_receiver) = mpsc::channel(32);
-        let ev_loop = EventLoop::new(switch, command_receiver, event_sender);
+        let (event_sender, event_receiver) = mpsc::channel(config.event_buffer_size);
+        let (validation_result_sender, validation_result_receiver) = mpsc::channel(32);
+        let ev_loop = EventLoop::new(
+            switch,
+            command_receiver,
+            event_sender,
+            validation_result_receiver,
+            config.discovery_dial_cooldown,
+            config.max_peers,
+            local_enr.clone(),
+            content_topic_metrics.clone(),
+        );
 
         debug!("start node event loop");
         tokio::spawn(ev_loop.dispatch());
 
         Ok(Self {
             peer_id,
+            network_id: config.network_id,
             command_sender,
             event_receiver,
+            validation_result_sender,
+            subscriptions: Mutex::new(SubscriptionManager::new()),
+            local_enr,
+            metrics_registry,
+            content_topic_metrics,
         })
     }
 
     pub fn new(config: NodeConfig) -> anyhow::Result<Self> {
-        let transport = default_transport(&config.keypair)?;
+        let transport = new_transport(&config.keypair, config.transport)?;
         Self::new_with_transport(config, transport)
     }
 
@@ -58,10 +173,40 @@ impl Node {
         self.peer_id
     }
 
+    /// The base64 `enr:` text representation of the node's current ENR.
+    ///
+    /// The ENR advertises the node's external addresses, or its listen addresses if no external
+    /// address is known.
+    pub fn local_enr_string(&self) -> String {
+        self.local_enr
+            .read()
+            .expect("local ENR lock poisoned")
+            .enr()
+            .to_base64()
+    }
+
+    /// The registry of the node-level metrics.
+    pub fn metrics_registry(&self) -> &Registry {
+        &self.metrics_registry
+    }
+
+    /// The per content topic relay message counters, if enabled in the node configuration.
+    pub fn content_topic_metrics(&self) -> Option<&ContentTopicMetrics> {
+        self.content_topic_metrics.as_ref()
+    }
+
     pub async fn recv_event(&mut self) -> Option<Event> {
         self.event_receiver.recv().await
     }
 
+    /// A channel to asynchronously report the validation result of the received relay messages.
+    ///
+    /// Only used if the relay protocol is configured to validate messages. Accepted messages are
+    /// forwarded to the mesh peers, rejected and ignored messages are dropped.
+    pub fn validation_result_sender(&self) -> mpsc::Sender<ValidationResult> {
+        self.validation_result_sender.clone()
+    }
+
     pub async fn switch_listen_on(&self, address: &Multiaddr) -> anyhow::Result<()> {
         let (resp_tx, resp_rx) = oneshot::channel();
         self.command_sender
@@ -80,6 +225,131 @@ impl Node {
         resp_rx.await?
     }
 
+    /// Add an address the node is reachable at, and advertise it in the node's ENR.
+    pub async fn switch_add_external_address(&self, address: &Multiaddr) -> anyhow::Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.command_sender
+            .send(Command::switch_add_external_address(
+                address.clone(),
+                resp_tx,
+            ))
+            .await?;
+
+        resp_rx.await?
+    }
+
+    /// Add an address a remote peer observed the node at, as the addresses reported via identify.
+    ///
+    /// Like those, the address is not advertised in the node's ENR until [`Self::refresh_enr()`]
+    /// is called.
+    pub async fn switch_add_observed_address(&self, address: &Multiaddr) -> anyhow::Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.command_sender
+            .send(Command::switch_add_observed_address(
+                address.clone(),
+                resp_tx,
+            ))
+            .await?;
+
+        resp_rx.await?
+    }
+
+    /// Rebuild and re-sign the node's ENR with its current addresses, e.g., after the external
+    /// address observed via identify or autonat changed.
+    ///
+    /// The ENR sequence number is only bumped if the advertised addresses changed. Returns the
+    /// `enr:` text representation of the current ENR.
+    pub async fn refresh_enr(&self) -> anyhow::Result<String> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.command_sender
+            .send(Command::refresh_enr(resp_tx))
+            .await?;
+
+        resp_rx.await?
+    }
+
+    /// Dial a peer surfaced by a discovery mechanism.
+    ///
+    /// Returns `false` if the dial was suppressed because a previous dial attempt to this peer
+    /// failed within the configured discovery dial cooldown.
+    pub async fn dial_discovered_peer(
+        &self,
+        peer_id: PeerId,
+        address: &Multiaddr,
+    ) -> anyhow::Result<bool> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.command_sender
+            .send(Command::dial_discovered_peer(
+                peer_id,
+                address.clone(),
+                resp_tx,
+            ))
+            .await?;
+
+        resp_rx.await?
+    }
+
+    /// Dial the peer described by the ENR.
+    ///
+    /// All the ENR addresses are attempted until a connection is established, so addresses of
+    /// transports the node does not support are skipped.
+    ///
+    /// Returns `false` if the ENR advertises a network identifier different from the node's one.
+    /// ENRs with no network identifier are considered compatible, while ENRs with a malformed
+    /// network identifier are not.
+    pub async fn dial_enr<K: EnrKey>(&self, enr: &Enr<K>) -> anyhow::Result<bool> {
+        if let Some(network_id) = self.network_id {
+            if !enr.is_network_compatible(network_id) {
+                debug!(
+                    "skip dialing peer on a different network: {:?}",
+                    enr.network_id()
+                );
+                return Ok(false);
+            }
+        }
+
+        let peer_id = secp256k1::PublicKey::try_from_bytes(&enr.public_key().encode())
+            .map(|key| PublicKey::from(key).to_peer_id())
+            .map_err(|_| anyhow!("unsupported ENR public key type"))?;
+
+        let mut addresses = enr.multiaddrs().unwrap_or_default();
+        if let (Some(ip), Some(tcp)) = (enr.ip4(), enr.tcp4()) {
+            addresses.push(
+                Multiaddr::empty()
+                    .with(Protocol::Ip4(ip))
+                    .with(Protocol::Tcp(tcp)),
+            );
+        }
+        if addresses.is_empty() {
+            return Err(anyhow!("no dialable address in ENR"));
+        }
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.command_sender
+            .send(Command::switch_dial_peer(peer_id, addresses, resp_tx))
+            .await?;
+
+        resp_rx.await??;
+        Ok(true)
+    }
+
+    /// Leave the network gracefully, as opposed to abruptly dropping the node.
+    ///
+    /// Unsubscribes from all the relay topics, sending the PRUNEs to the mesh peers, flushes the
+    /// pending control messages and closes all the connections. Resolves once all the connections
+    /// are closed.
+    ///
+    /// New connections are closed while leaving the network. If the connections are not closed
+    /// within the timeout, the remaining ones are closed abruptly and an error is returned.
+    pub async fn leave_network(&self, timeout: Duration) -> anyhow::Result<()> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.command_sender
+            .send(Command::leave_network(timeout, resp_tx))
+            .await?;
+
+        resp_rx.await?
+    }
+
     pub async fn relay_subscribe(&self, topic: &PubsubTopic) -> anyhow::Result<()> {
         let (resp_tx, resp_rx) = oneshot::channel();
         self.command_sender
@@ -110,4 +380,36 @@ impl Node {
 
         resp_rx.await?
     }
+
+    /// Subscribe to a content topic on the given pubsub topic.
+    ///
+    /// The pubsub topic is only subscribed if no other content topic is mapped to it.
+    pub async fn content_subscribe(
+        &self,
+        content_topic: &ContentTopic,
+        pubsub_topic: &PubsubTopic,
+    ) -> anyhow::Result<()> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        if !subscriptions.subscribe(content_topic.clone(), pubsub_topic.clone()) {
+            return Ok(());
+        }
+
+        if let Err(err) = self.relay_subscribe(pubsub_topic).await {
+            subscriptions.unsubscribe(content_topic);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Unsubscribe from a content topic.
+    ///
+    /// The underlying pubsub topic is only unsubscribed if no other content topic is mapped to it.
+    pub async fn content_unsubscribe(&self, content_topic: &ContentTopic) -> anyhow::Result<()> {
+        let mut subscriptions = self.subscriptions.lock().await;
+        match subscriptions.unsubscribe(content_topic) {
+            Some(pubsub_topic) => self.relay_unsubscribe(&pubsub_topic).await,
+            None => Ok(()),
+        }
+    }
 }
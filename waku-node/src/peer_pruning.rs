@@ -0,0 +1,42 @@
+use libp2p::PeerId;
+
+/// A connected peer considered for pruning.
+#[derive(Debug, Clone)]
+pub struct PruningCandidate {
+    pub peer_id: PeerId,
+    /// The peer's relay score, if known.
+    pub score: Option<f64>,
+    /// Whether the peer is a relay mesh peer or an explicit peer. Protected peers are never
+    /// pruned.
+    pub protected: bool,
+}
+
+/// Select the connected peers to disconnect to make room for new connections.
+///
+/// Once the number of connected peers reaches `max_peers`, the lowest-scored unprotected peers
+/// are selected until a tenth of the slots (at least one) are free. Peers without a score are
+/// ranked as neutral, i.e. with a score of zero.
+pub fn select_peers_to_prune(candidates: &[PruningCandidate], max_peers: usize) -> Vec<PeerId> {
+    if candidates.len() < max_peers {
+        return Vec::new();
+    }
+
+    let headroom = (max_peers / 10).max(1);
+    let excess = candidates.len() - max_peers.saturating_sub(headroom);
+
+    let mut unprotected = candidates
+        .iter()
+        .filter(|candidate| !candidate.protected)
+        .collect::<Vec<_>>();
+    unprotected.sort_by(|a, b| {
+        let score_a = a.score.unwrap_or(0.0);
+        let score_b = b.score.unwrap_or(0.0);
+        score_a.total_cmp(&score_b)
+    });
+
+    unprotected
+        .into_iter()
+        .take(excess)
+        .map(|candidate| candidate.peer_id)
+        .collect()
+}
@@ -0,0 +1,71 @@
+use std::collections::{HashMap, HashSet};
+
+use waku_core::content_topic::ContentTopic;
+use waku_core::pubsub_topic::PubsubTopic;
+
+/// Tracks the content topic subscriptions and the pubsub topics (shards) they are mapped to.
+///
+/// Pubsub topic subscriptions are reference counted by the number of content topics mapped to
+/// them: the pubsub topic subscription is only needed while at least one of its content topics
+/// is subscribed.
+#[derive(Debug, Default)]
+pub struct SubscriptionManager {
+    content_topics: HashMap<ContentTopic, PubsubTopic>,
+    pubsub_topics: HashMap<PubsubTopic, HashSet<ContentTopic>>,
+}
+
+impl SubscriptionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a content topic subscription on the given pubsub topic.
+    ///
+    /// Returns `true` if this is the first content topic mapped to the pubsub topic, i.e. the
+    /// pubsub topic must be subscribed. Subscribing an already subscribed content topic is a
+    /// no-op.
+    pub fn subscribe(&mut self, content_topic: ContentTopic, pubsub_topic: PubsubTopic) -> bool {
+        if self.content_topics.contains_key(&content_topic) {
+            return false;
+        }
+
+        self.content_topics
+            .insert(content_topic.clone(), pubsub_topic.clone());
+
+        let content_topics = self.pubsub_topics.entry(pubsub_topic).or_default();
+        content_topics.insert(content_topic);
+        content_topics.len() == 1
+    }
+
+    /// Remove a content topic subscription.
+    ///
+    /// Returns the pubsub topic the content topic was mapped to if no other content topic
+    /// references it anymore, i.e. the pubsub topic must be unsubscribed.
+    pub fn unsubscribe(&mut self, content_topic: &ContentTopic) -> Option<PubsubTopic> {
+        let pubsub_topic = self.content_topics.remove(content_topic)?;
+
+        let content_topics = self.pubsub_topics.get_mut(&pubsub_topic)?;
+        content_topics.remove(content_topic);
+        if !content_topics.is_empty() {
+            return None;
+        }
+
+        self.pubsub_topics.remove(&pubsub_topic);
+        Some(pubsub_topic)
+    }
+
+    /// Whether the pubsub topic is referenced by any content topic subscription.
+    pub fn is_subscribed(&self, pubsub_topic: &PubsubTopic) -> bool {
+        self.pubsub_topics.contains_key(pubsub_topic)
+    }
+
+    /// The pubsub topic the content topic subscription is mapped to.
+    pub fn pubsub_topic(&self, content_topic: &ContentTopic) -> Option<&PubsubTopic> {
+        self.content_topics.get(content_topic)
+    }
+
+    /// The pubsub topics referenced by at least one content topic subscription.
+    pub fn pubsub_topics(&self) -> impl Iterator<Item = &PubsubTopic> {
+        self.pubsub_topics.keys()
+    }
+}
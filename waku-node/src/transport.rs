@@ -1,9 +1,13 @@
+use std::io;
 use std::time::Duration;
 
+use either::Either;
+use futures::{AsyncRead, AsyncWrite};
 use libp2p::core::muxing::StreamMuxerBox;
 use libp2p::core::transport;
+use libp2p::core::transport::timeout::TransportTimeoutError;
 use libp2p::identity::Keypair;
-use libp2p::{core, dns, noise, tcp, yamux, PeerId, Transport};
+use libp2p::{core, dns, noise, tcp, websocket, yamux, PeerId, Transport};
 use libp2p_mplex as mplex;
 
 /// Type alias for libp2p transport
@@ -11,30 +15,75 @@ pub type P2PTransport = (PeerId, StreamMuxerBox);
 /// Type alias for boxed libp2p transport
 pub type BoxedP2PTransport = transport::Boxed<P2PTransport>;
 
+/// The security handshake or the stream multiplexer negotiation of a connection failed.
+///
+/// Wrapped in the transport [`io::Error`], telling upgrade failures apart from the raw transport
+/// ones, e.g., a refused connection.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub(crate) struct UpgradeFailure(Box<dyn std::error::Error + Send + Sync>);
+
+/// The network transport the node dials and listens on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransportKind {
+    /// Plain TCP, e.g. `/ip4/0.0.0.0/tcp/60000`.
+    #[default]
+    Tcp,
+    /// WebSocket over TCP, e.g. `/ip4/0.0.0.0/tcp/8000/ws`.
+    WebSocket,
+    /// Both TCP and WebSocket over TCP.
+    Both,
+}
+
 // create the libp2p transport for the node
 pub fn default_transport(keypair: &Keypair) -> std::io::Result<BoxedP2PTransport> {
-    let transport = {
+    new_transport(keypair, TransportKind::Tcp)
+}
+
+/// Create the libp2p transport of the given kind for the node
+pub fn new_transport(keypair: &Keypair, kind: TransportKind) -> std::io::Result<BoxedP2PTransport> {
+    let tcp_transport = || {
         dns::TokioDnsConfig::system(tcp::tokio::Transport::new(
             tcp::Config::default().nodelay(true),
-        ))?
+        ))
     };
 
-    Ok(transport
-        .upgrade(core::upgrade::Version::V1)
-        .authenticate(noise::Config::new(keypair).unwrap())
-        .multiplex(core::upgrade::SelectUpgrade::new(
-            yamux::Config::default(),
-            mplex::MplexConfig::default(),
-        ))
-        .timeout(Duration::from_secs(20))
-        .boxed())
+    let transport = match kind {
+        TransportKind::Tcp => upgrade_transport(tcp_transport()?, keypair),
+        TransportKind::WebSocket => {
+            upgrade_transport(websocket::WsConfig::new(tcp_transport()?), keypair)
+        }
+        TransportKind::Both => {
+            let ws = upgrade_transport(websocket::WsConfig::new(tcp_transport()?), keypair);
+            let tcp = upgrade_transport(tcp_transport()?, keypair);
+            ws.or_transport(tcp)
+                .map(|output, _| output.into_inner())
+                .map_err(Either::into_inner)
+                .boxed()
+        }
+    };
+
+    Ok(transport)
 }
 
 /// In memory transport
 pub fn memory_transport(keypair: &Keypair) -> std::io::Result<BoxedP2PTransport> {
-    let transport = transport::MemoryTransport::default();
+    Ok(upgrade_transport(
+        transport::MemoryTransport::default(),
+        keypair,
+    ))
+}
 
-    Ok(transport
+// upgrade the raw transport with the noise security and the yamux/mplex multiplexing protocols
+fn upgrade_transport<T>(transport: T, keypair: &Keypair) -> BoxedP2PTransport
+where
+    T: Transport + Send + Unpin + 'static,
+    T::Output: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T::Error: Send + Sync,
+    T::Dial: Send + 'static,
+    T::ListenerUpgrade: Send + 'static,
+{
+    transport
         .upgrade(core::upgrade::Version::V1)
         .authenticate(noise::Config::new(keypair).unwrap())
         .multiplex(core::upgrade::SelectUpgrade::new(
@@ -42,5 +91,14 @@ pub fn memory_transport(keypair: &Keypair) -> std::io::Result<BoxedP2PTransport>
             mplex::MplexConfig::default(),
         ))
         .timeout(Duration::from_secs(20))
-        .boxed())
+        .map_err(|err| match err {
+            TransportTimeoutError::Other(Either::Left(Either::Right(err))) => {
+                io::Error::new(io::ErrorKind::Other, UpgradeFailure(err.into()))
+            }
+            TransportTimeoutError::Other(Either::Right(err)) => {
+                io::Error::new(io::ErrorKind::Other, UpgradeFailure(err.into()))
+            }
+            err => io::Error::new(io::ErrorKind::Other, err),
+        })
+        .boxed()
 }
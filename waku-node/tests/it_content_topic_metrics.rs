@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use libp2p::identity::secp256k1;
+use libp2p::Multiaddr;
+use prometheus_client::encoding::text::encode;
+use tokio::time::{sleep, timeout};
+
+use waku_core::message::WakuMessage;
+use waku_core::pubsub_topic::PubsubTopic;
+use waku_node::{memory_transport, Event, Node, NodeConfigBuilder, OTHER_CONTENT_TOPICS_LABEL};
+
+fn new_node(key: &str) -> Node {
+    let keypair = {
+        let raw_key = hex::decode(key).expect("key to be valid");
+        let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+        secp256k1::Keypair::from(secret_key).into()
+    };
+
+    let config = NodeConfigBuilder::new()
+        .keypair(keypair)
+        .with_keepalive(true)
+        .with_waku_relay(Default::default())
+        .with_content_topic_metrics(2)
+        .build();
+
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    Node::new_with_transport(config, transport).expect("node creation to succeed")
+}
+
+fn new_message(content_topic: &str, payload: &'static [u8]) -> WakuMessage {
+    WakuMessage {
+        payload: Bytes::from_static(payload),
+        content_topic: content_topic.parse().unwrap(),
+        meta: None,
+        ephemeral: false,
+    }
+}
+
+#[tokio::test]
+async fn it_count_received_messages_per_content_topic() {
+    //// Setup
+    let publisher_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let publisher_addr: Multiaddr = "/memory/71".parse().unwrap();
+    let publisher = new_node(publisher_key);
+    publisher
+        .switch_listen_on(&publisher_addr)
+        .await
+        .expect("listen on address");
+
+    let subscriber_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let mut subscriber = new_node(subscriber_key);
+    subscriber
+        .switch_dial(&publisher_addr)
+        .await
+        .expect("dial to succeed");
+
+    let pubsub_topic: PubsubTopic = "/waku/2/it-waku/test".parse().unwrap();
+    publisher
+        .relay_subscribe(&pubsub_topic)
+        .await
+        .expect("subscribe to topic");
+    subscriber
+        .relay_subscribe(&pubsub_topic)
+        .await
+        .expect("subscribe to topic");
+
+    // Wait for pub-sub network to establish
+    sleep(Duration::from_millis(100)).await;
+
+    //// Given
+    // The metrics track at most 2 distinct content topics
+    let messages = vec![
+        new_message("/test/1/chat-a/proto", b"A1"),
+        new_message("/test/1/chat-b/proto", b"B1"),
+        new_message("/test/1/chat-a/proto", b"A2"),
+        new_message("/test/1/chat-c/proto", b"C1"),
+        new_message("/test/1/chat-d/proto", b"D1"),
+    ];
+
+    //// When
+    for message in &messages {
+        publisher
+            .relay_publish(&pubsub_topic, message.clone())
+            .await
+            .expect("publish the message");
+    }
+
+    for _ in 0..messages.len() {
+        let event = timeout(Duration::from_secs(5), subscriber.recv_event())
+            .await
+            .expect("message to be received");
+        assert!(matches!(event, Some(Event::WakuRelayMessage { .. })));
+    }
+
+    //// Then
+    let metrics = subscriber
+        .content_topic_metrics()
+        .expect("content topic metrics enabled");
+    assert_eq!(metrics.messages("/test/1/chat-a/proto"), 2);
+    assert_eq!(metrics.messages("/test/1/chat-b/proto"), 1);
+    assert_eq!(metrics.messages("/test/1/chat-c/proto"), 0);
+    assert_eq!(metrics.messages("/test/1/chat-d/proto"), 0);
+    assert_eq!(metrics.messages(OTHER_CONTENT_TOPICS_LABEL), 2);
+
+    // Reading the counters does not create their labels
+    let mut encoded = String::new();
+    encode(&mut encoded, subscriber.metrics_registry()).expect("encode the metrics");
+    assert!(encoded.contains("/test/1/chat-a/proto"));
+    assert!(!encoded.contains("/test/1/chat-c/proto"));
+    assert!(!encoded.contains("/test/1/chat-d/proto"));
+}
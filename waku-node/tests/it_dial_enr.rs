@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use libp2p::identity::secp256k1;
+use libp2p::Multiaddr;
+use tokio::time::{sleep, timeout};
+
+use waku_core::message::WakuMessage;
+use waku_core::pubsub_topic::PubsubTopic;
+use waku_enr::enr::{CombinedKey, Enr, EnrBuilder};
+use waku_enr::EnrBuilderExt;
+use waku_node::{memory_transport, Event, Node, NodeConfigBuilder};
+
+fn new_node(key: &str, network_id: u32) -> Node {
+    let keypair = {
+        let raw_key = hex::decode(key).expect("key to be valid");
+        let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+        secp256k1::Keypair::from(secret_key).into()
+    };
+
+    let config = NodeConfigBuilder::new()
+        .keypair(keypair)
+        .with_keepalive(true)
+        .with_waku_relay(Default::default())
+        .with_network_id(network_id)
+        .build();
+
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    Node::new_with_transport(config, transport).expect("node creation to succeed")
+}
+
+fn new_enr(key: &str, addresses: &[Multiaddr], network_id: Option<u32>) -> Enr<CombinedKey> {
+    let mut raw_key = hex::decode(key).expect("key to be valid");
+    let enr_key = CombinedKey::secp256k1_from_bytes(&mut raw_key).expect("valid secp256k1 key");
+
+    let mut builder = EnrBuilder::<CombinedKey>::new("v4");
+    builder.multiaddrs(addresses.to_vec());
+    if let Some(network_id) = network_id {
+        builder.network_id(network_id);
+    }
+    builder.build(&enr_key).expect("valid enr")
+}
+
+#[tokio::test]
+async fn it_dial_enr_on_same_network() {
+    //// Setup
+    let peer_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let peer_addr: Multiaddr = "/memory/51".parse().unwrap();
+    let peer = new_node(peer_key, 1);
+    peer.switch_listen_on(&peer_addr)
+        .await
+        .expect("listen on address");
+
+    let node_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let node = new_node(node_key, 1);
+
+    //// Given
+    let enr = new_enr(peer_key, &[peer_addr.clone()], Some(1));
+
+    //// When
+    let dialed = node.dial_enr(&enr).await.expect("dial to succeed");
+
+    //// Then
+    assert!(dialed);
+}
+
+#[tokio::test]
+async fn it_dial_enr_without_network_id() {
+    //// Setup
+    let peer_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let peer_addr: Multiaddr = "/memory/52".parse().unwrap();
+    let peer = new_node(peer_key, 1);
+    peer.switch_listen_on(&peer_addr)
+        .await
+        .expect("listen on address");
+
+    let node_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let node = new_node(node_key, 1);
+
+    //// Given
+    let enr = new_enr(peer_key, &[peer_addr.clone()], None);
+
+    //// When
+    let dialed = node.dial_enr(&enr).await.expect("dial to succeed");
+
+    //// Then
+    assert!(dialed);
+}
+
+#[tokio::test]
+async fn it_skip_dial_enr_on_different_network() {
+    //// Setup
+    let node_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let node = new_node(node_key, 1);
+
+    //// Given
+    let peer_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let peer_addr: Multiaddr = "/memory/53".parse().unwrap();
+    let enr = new_enr(peer_key, &[peer_addr.clone()], Some(2));
+
+    //// When
+    let dialed = node.dial_enr(&enr).await.expect("dial decision");
+
+    //// Then
+    assert!(!dialed);
+}
+
+#[tokio::test]
+async fn it_dial_enr_skips_unsupported_addresses() {
+    //// Setup
+    let peer_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let peer_addr: Multiaddr = "/memory/56".parse().unwrap();
+    let mut peer = new_node(peer_key, 1);
+    peer.switch_listen_on(&peer_addr)
+        .await
+        .expect("listen on address");
+
+    let node_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let node = new_node(node_key, 1);
+
+    let pubsub_topic: PubsubTopic = "/waku/2/it-waku/test".parse().unwrap();
+    peer.relay_subscribe(&pubsub_topic)
+        .await
+        .expect("subscribe to topic");
+    node.relay_subscribe(&pubsub_topic)
+        .await
+        .expect("subscribe to topic");
+
+    //// Given
+    // The first advertised address is not dialable over the node's memory transport
+    let unsupported_addr: Multiaddr = "/ip4/127.0.0.1/udp/60000/quic-v1".parse().unwrap();
+    let enr = new_enr(peer_key, &[unsupported_addr, peer_addr.clone()], Some(1));
+
+    //// When
+    let dialed = node.dial_enr(&enr).await.expect("dial to succeed");
+
+    // Wait for pub-sub network to establish
+    sleep(Duration::from_millis(100)).await;
+
+    let message = WakuMessage {
+        payload: Bytes::from_static(b"TEST"),
+        content_topic: "/test/v1/it/text".parse().unwrap(),
+        meta: None,
+        ephemeral: false,
+    };
+    node.relay_publish(&pubsub_topic, message)
+        .await
+        .expect("publish the message");
+
+    //// Then
+    assert!(dialed);
+
+    let event = timeout(Duration::from_secs(1), peer.recv_event())
+        .await
+        .expect("the peer to be connected");
+    assert!(matches!(event, Some(Event::WakuRelayMessage { .. })));
+}
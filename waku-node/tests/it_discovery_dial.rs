@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use libp2p::identity::secp256k1;
+use libp2p::{Multiaddr, PeerId};
+use tokio::time::sleep;
+
+use waku_node::{memory_transport, Node, NodeConfigBuilder};
+
+fn new_node(key: &str, cooldown: Duration) -> Node {
+    let keypair = {
+        let raw_key = hex::decode(key).expect("key to be valid");
+        let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+        secp256k1::Keypair::from(secret_key).into()
+    };
+
+    let config = NodeConfigBuilder::new()
+        .keypair(keypair)
+        .with_discovery_dial_cooldown(cooldown)
+        .build();
+
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    Node::new_with_transport(config, transport).expect("node creation to succeed")
+}
+
+#[tokio::test]
+async fn it_suppress_redial_of_failed_discovered_peer() {
+    //// Setup
+    let node_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let cooldown = Duration::from_millis(500);
+    let node = new_node(node_key, cooldown);
+
+    //// Given
+    // No node is listening on this address, so the dial attempt fails
+    let discovered_peer = PeerId::random();
+    let discovered_addr: Multiaddr = "/memory/47".parse().unwrap();
+
+    //// When
+    let first_dial = node
+        .dial_discovered_peer(discovered_peer, &discovered_addr)
+        .await
+        .expect("dial command to succeed");
+
+    // Wait for the dial attempt to fail
+    sleep(Duration::from_millis(100)).await;
+
+    let dial_within_cooldown = node
+        .dial_discovered_peer(discovered_peer, &discovered_addr)
+        .await
+        .expect("dial command to succeed");
+
+    // Wait for the cooldown to expire
+    sleep(cooldown).await;
+
+    let dial_after_cooldown = node
+        .dial_discovered_peer(discovered_peer, &discovered_addr)
+        .await
+        .expect("dial command to succeed");
+
+    //// Then
+    assert!(first_dial, "the first dial should be attempted");
+    assert!(
+        !dial_within_cooldown,
+        "the redial within the cooldown should be suppressed"
+    );
+    // Only a failed (i.e., no longer in-flight) dial attempt can be retried after the cooldown
+    assert!(
+        dial_after_cooldown,
+        "the redial after the cooldown should be attempted"
+    );
+}
+
+#[tokio::test]
+async fn it_redial_failed_discovered_peer_after_cooldown() {
+    //// Setup
+    let node_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let node = new_node(node_key, Duration::from_millis(50));
+
+    //// Given
+    let discovered_peer = PeerId::random();
+    let discovered_addr: Multiaddr = "/memory/48".parse().unwrap();
+
+    //// When
+    let first_dial = node
+        .dial_discovered_peer(discovered_peer, &discovered_addr)
+        .await
+        .expect("dial command to succeed");
+
+    // Wait for the dial attempt to fail and the cooldown to expire
+    sleep(Duration::from_millis(150)).await;
+
+    let second_dial = node
+        .dial_discovered_peer(discovered_peer, &discovered_addr)
+        .await
+        .expect("dial command to succeed");
+
+    //// Then
+    assert!(first_dial);
+    assert!(second_dial);
+}
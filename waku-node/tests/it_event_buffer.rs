@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use libp2p::identity::secp256k1;
+use libp2p::Multiaddr;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
+
+use waku_core::message::WakuMessage;
+use waku_core::pubsub_topic::PubsubTopic;
+use waku_node::{
+    emit_event, memory_transport, Event, EventDelivery, Node, NodeConfigBuilder, UpgradeError,
+};
+
+fn new_node(key: &str, event_buffer_size: usize) -> Node {
+    let keypair = {
+        let raw_key = hex::decode(key).expect("key to be valid");
+        let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+        secp256k1::Keypair::from(secret_key).into()
+    };
+
+    let config = NodeConfigBuilder::new()
+        .keypair(keypair)
+        .with_keepalive(true)
+        .with_waku_relay(Default::default())
+        .with_event_buffer_size(event_buffer_size)
+        .build();
+
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    Node::new_with_transport(config, transport).expect("node creation to succeed")
+}
+
+#[tokio::test]
+async fn it_pause_network_processing_while_event_buffer_is_full() {
+    //// Setup
+    let publisher_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let publisher_addr: Multiaddr = "/memory/81".parse().unwrap();
+    let publisher = new_node(publisher_key, 32);
+    publisher
+        .switch_listen_on(&publisher_addr)
+        .await
+        .expect("listen on address");
+
+    let subscriber_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let mut subscriber = new_node(subscriber_key, 2);
+    subscriber
+        .switch_dial(&publisher_addr)
+        .await
+        .expect("dial to succeed");
+
+    let pubsub_topic: PubsubTopic = "/waku/2/it-waku/test".parse().unwrap();
+    publisher
+        .relay_subscribe(&pubsub_topic)
+        .await
+        .expect("subscribe to topic");
+    subscriber
+        .relay_subscribe(&pubsub_topic)
+        .await
+        .expect("subscribe to topic");
+
+    // Wait for pub-sub network to establish
+    sleep(Duration::from_millis(100)).await;
+
+    //// Given
+    // More messages than the subscriber event buffer can hold
+    let messages = (0..5u8)
+        .map(|i| WakuMessage {
+            payload: Bytes::from(vec![i]),
+            content_topic: "/test/v1/it/text".parse().unwrap(),
+            meta: None,
+            ephemeral: false,
+        })
+        .collect::<Vec<_>>();
+
+    //// When
+    for message in &messages {
+        publisher
+            .relay_publish(&pubsub_topic, message.clone())
+            .await
+            .expect("publish the message");
+    }
+
+    // Let the subscriber fill its event buffer without consuming any event
+    sleep(Duration::from_millis(500)).await;
+
+    // The node keeps handling commands while the network processing is paused
+    let other_topic: PubsubTopic = "/waku/2/it-waku/other".parse().unwrap();
+    let command_result = timeout(
+        Duration::from_secs(5),
+        subscriber.relay_subscribe(&other_topic),
+    )
+    .await
+    .expect("command to be handled");
+
+    let mut received = Vec::new();
+    for _ in 0..messages.len() {
+        let event = timeout(Duration::from_secs(5), subscriber.recv_event())
+            .await
+            .expect("event to be received");
+        if let Some(Event::WakuRelayMessage { message, .. }) = event {
+            received.push(message);
+        }
+    }
+
+    //// Then
+    assert!(command_result.is_ok());
+    assert_eq!(
+        received, messages,
+        "no message should be dropped while the event buffer is full"
+    );
+}
+
+#[test]
+fn it_report_whether_events_are_buffered_or_dropped() {
+    //// Setup
+    let (event_sink, mut event_source) = mpsc::channel(1);
+    let new_event = || Event::UpgradeError {
+        peer: None,
+        error: UpgradeError::Transport("test".to_string()),
+    };
+
+    //// Given
+    let buffered = emit_event(&event_sink, new_event());
+
+    //// When
+    let dropped_while_full = emit_event(&event_sink, new_event());
+
+    event_source.try_recv().expect("buffered event");
+    let buffered_after_consume = emit_event(&event_sink, new_event());
+
+    drop(event_source);
+    let dropped_while_closed = emit_event(&event_sink, new_event());
+
+    //// Then
+    assert_eq!(buffered, EventDelivery::Buffered);
+    assert_eq!(dropped_while_full, EventDelivery::Dropped);
+    assert_eq!(buffered_after_consume, EventDelivery::Buffered);
+    assert_eq!(dropped_while_closed, EventDelivery::Dropped);
+}
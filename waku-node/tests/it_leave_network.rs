@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use libp2p::identity::secp256k1;
+use libp2p::Multiaddr;
+use tokio::time::{sleep, timeout};
+
+use waku_core::message::WakuMessage;
+use waku_core::pubsub_topic::PubsubTopic;
+use waku_node::{memory_transport, Node, NodeConfigBuilder};
+
+fn new_node(key: &str) -> Node {
+    let keypair = {
+        let raw_key = hex::decode(key).expect("key to be valid");
+        let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+        secp256k1::Keypair::from(secret_key).into()
+    };
+
+    let config = NodeConfigBuilder::new()
+        .keypair(keypair)
+        .with_keepalive(true)
+        .with_waku_relay(Default::default())
+        .build();
+
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    Node::new_with_transport(config, transport).expect("node creation to succeed")
+}
+
+#[tokio::test]
+async fn it_leave_network_closes_all_connections() {
+    //// Setup
+    let leaving_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let leaving_addr: Multiaddr = "/memory/91".parse().unwrap();
+    let leaving = new_node(leaving_key);
+    leaving
+        .switch_listen_on(&leaving_addr)
+        .await
+        .expect("listen on address");
+
+    let staying_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let staying_addr: Multiaddr = "/memory/92".parse().unwrap();
+    let staying = new_node(staying_key);
+    staying
+        .switch_listen_on(&staying_addr)
+        .await
+        .expect("listen on address");
+
+    staying
+        .switch_dial(&leaving_addr)
+        .await
+        .expect("dial to succeed");
+
+    let pubsub_topics: [PubsubTopic; 2] = [
+        "/waku/2/it-waku/test".parse().unwrap(),
+        "/waku/2/it-waku/other".parse().unwrap(),
+    ];
+    for topic in &pubsub_topics {
+        leaving
+            .relay_subscribe(topic)
+            .await
+            .expect("subscribe to topic");
+        staying
+            .relay_subscribe(topic)
+            .await
+            .expect("subscribe to topic");
+    }
+
+    // Wait for pub-sub network to establish
+    sleep(Duration::from_millis(100)).await;
+
+    //// Given
+    let message = WakuMessage {
+        payload: Bytes::from_static(b"TEST"),
+        content_topic: "/test/v1/it/text".parse().unwrap(),
+        meta: None,
+        ephemeral: false,
+    };
+    staying
+        .relay_publish(&pubsub_topics[0], message.clone())
+        .await
+        .expect("a peer to publish to");
+
+    //// When
+    let left = timeout(
+        Duration::from_secs(5),
+        leaving.leave_network(Duration::from_secs(4)),
+    )
+    .await;
+
+    //// Then
+    assert!(
+        matches!(left, Ok(Ok(()))),
+        "all connections should be closed"
+    );
+
+    // The remaining node is left with no peer to publish to
+    sleep(Duration::from_millis(100)).await;
+    let message = WakuMessage {
+        payload: Bytes::from_static(b"LEFT"),
+        ..message
+    };
+    for topic in &pubsub_topics {
+        let result = staying.relay_publish(topic, message.clone()).await;
+        assert!(result.is_err(), "no peer should be left on {topic}");
+    }
+}
+
+#[tokio::test]
+async fn it_leave_network_closes_the_remaining_connections_past_the_deadline() {
+    //// Setup
+    let leaving_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let leaving_addr: Multiaddr = "/memory/93".parse().unwrap();
+    let leaving = new_node(leaving_key);
+    leaving
+        .switch_listen_on(&leaving_addr)
+        .await
+        .expect("listen on address");
+
+    let staying_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let staying = new_node(staying_key);
+    staying
+        .switch_dial(&leaving_addr)
+        .await
+        .expect("dial to succeed");
+
+    let pubsub_topic: PubsubTopic = "/waku/2/it-waku/test".parse().unwrap();
+    for node in [&leaving, &staying] {
+        node.relay_subscribe(&pubsub_topic)
+            .await
+            .expect("subscribe to topic");
+    }
+
+    // Wait for pub-sub network to establish
+    sleep(Duration::from_millis(100)).await;
+
+    //// When
+    // The relay peers are drained until the next heartbeat, past the deadline
+    let left = timeout(
+        Duration::from_secs(5),
+        leaving.leave_network(Duration::ZERO),
+    )
+    .await;
+
+    //// Then
+    assert!(
+        matches!(left, Ok(Err(_))),
+        "leaving the network should time out"
+    );
+
+    // The remaining connections are closed anyway
+    sleep(Duration::from_millis(100)).await;
+    let message = WakuMessage {
+        payload: Bytes::from_static(b"LEFT"),
+        content_topic: "/test/v1/it/text".parse().unwrap(),
+        meta: None,
+        ephemeral: false,
+    };
+    let result = staying.relay_publish(&pubsub_topic, message).await;
+    assert!(result.is_err(), "no peer should be left on {pubsub_topic}");
+}
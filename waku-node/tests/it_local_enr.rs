@@ -0,0 +1,147 @@
+use libp2p::identity::{secp256k1, Keypair, PublicKey};
+use libp2p::{Multiaddr, PeerId};
+
+use waku_enr::enr::{CombinedKey, Enr, EnrPublicKey};
+use waku_enr::EnrExt;
+use waku_node::{memory_transport, Node, NodeConfigBuilder};
+
+fn new_node(key: &str) -> Node {
+    let keypair = {
+        let raw_key = hex::decode(key).expect("key to be valid");
+        let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+        secp256k1::Keypair::from(secret_key).into()
+    };
+
+    let config = NodeConfigBuilder::new()
+        .keypair(keypair)
+        .with_network_id(1)
+        .build();
+
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    Node::new_with_transport(config, transport).expect("node creation to succeed")
+}
+
+fn enr_peer_id(enr: &Enr<CombinedKey>) -> PeerId {
+    let public_key = secp256k1::PublicKey::try_from_bytes(&enr.public_key().encode())
+        .expect("valid secp256k1 public key");
+    PublicKey::from(public_key).to_peer_id()
+}
+
+fn local_enr(node: &Node) -> Enr<CombinedKey> {
+    node.local_enr_string().parse().expect("valid ENR")
+}
+
+#[tokio::test]
+async fn it_export_local_enr_string() {
+    //// Setup
+    let node_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let node = new_node(node_key);
+
+    //// When
+    let enr_string = node.local_enr_string();
+
+    //// Then
+    assert!(enr_string.starts_with("enr:"));
+
+    let enr: Enr<CombinedKey> = enr_string.parse().expect("valid ENR");
+    assert_eq!(enr_peer_id(&enr), node.peer_id());
+    assert_eq!(enr.network_id(), Some(1));
+}
+
+#[tokio::test]
+async fn it_advertise_external_address_in_local_enr() {
+    //// Setup
+    let node_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let node = new_node(node_key);
+
+    let initial_enr = local_enr(&node);
+
+    //// Given
+    let external_addr: Multiaddr = "/ip4/192.0.2.1/tcp/60000".parse().unwrap();
+    let later_external_addr: Multiaddr = "/ip4/192.0.2.2/tcp/60001".parse().unwrap();
+
+    //// When
+    node.switch_add_external_address(&external_addr)
+        .await
+        .expect("add external address");
+    let enr = local_enr(&node);
+
+    node.switch_add_external_address(&later_external_addr)
+        .await
+        .expect("add external address");
+    let later_enr = local_enr(&node);
+
+    //// Then
+    assert_eq!(enr_peer_id(&enr), node.peer_id());
+    assert!(enr.seq() > initial_enr.seq());
+    assert_eq!(enr.ip4(), Some("192.0.2.1".parse().unwrap()));
+    assert_eq!(enr.tcp4(), Some(60000));
+    assert_eq!(enr.multiaddrs(), Some(vec![external_addr.clone()]));
+
+    assert!(later_enr.seq() > enr.seq());
+    let later_multiaddrs = later_enr.multiaddrs().expect("multiaddrs field");
+    assert!(later_multiaddrs.contains(&external_addr));
+    assert!(later_multiaddrs.contains(&later_external_addr));
+}
+
+#[tokio::test]
+async fn it_refresh_local_enr_after_observed_address_change() {
+    //// Setup
+    let node_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let node = new_node(node_key);
+
+    let initial_enr = local_enr(&node);
+
+    //// Given
+    // The observed addresses are not synced to the local ENR automatically
+    let observed_addr: Multiaddr = "/ip4/192.0.2.1/tcp/60000".parse().unwrap();
+    node.switch_add_observed_address(&observed_addr)
+        .await
+        .expect("add observed address");
+    let stale_enr = local_enr(&node);
+
+    //// When
+    let refreshed_enr: Enr<CombinedKey> = node
+        .refresh_enr()
+        .await
+        .expect("refresh ENR")
+        .parse()
+        .expect("valid ENR");
+    let unchanged_enr: Enr<CombinedKey> = node
+        .refresh_enr()
+        .await
+        .expect("refresh ENR")
+        .parse()
+        .expect("valid ENR");
+
+    //// Then
+    assert_eq!(stale_enr.seq(), initial_enr.seq());
+    assert_eq!(stale_enr.ip4(), None);
+    assert_eq!(stale_enr.multiaddrs(), None);
+
+    assert_eq!(refreshed_enr.seq(), initial_enr.seq() + 1);
+    assert_eq!(refreshed_enr.ip4(), Some("192.0.2.1".parse().unwrap()));
+    assert_eq!(refreshed_enr.multiaddrs(), Some(vec![observed_addr]));
+    assert_eq!(
+        unchanged_enr.seq(),
+        refreshed_enr.seq(),
+        "refreshing with unchanged addresses must not bump the sequence number"
+    );
+    assert_eq!(node.local_enr_string(), refreshed_enr.to_base64());
+}
+
+#[tokio::test]
+async fn it_fail_to_create_node_with_non_secp256k1_key() {
+    //// Given
+    let config = NodeConfigBuilder::new()
+        .keypair(Keypair::generate_ed25519())
+        .build();
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    //// When
+    let result = Node::new_with_transport(config, transport);
+
+    //// Then
+    assert!(result.is_err());
+}
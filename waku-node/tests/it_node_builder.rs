@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use libp2p::identity::{secp256k1, Keypair};
+
+use waku_node::{memory_transport, NodeBuilder, TransportKind};
+
+fn new_keypair(key: &str) -> Keypair {
+    let raw_key = hex::decode(key).expect("key to be valid");
+    let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+    secp256k1::Keypair::from(secret_key).into()
+}
+
+#[test]
+fn it_build_relay_only_behaviour() {
+    //// Given
+    let keypair = new_keypair("dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe");
+
+    //// When
+    let behaviour = NodeBuilder::new()
+        .keypair(keypair)
+        .with_relay(Default::default())
+        .build_behaviour()
+        .expect("behaviour build to succeed");
+
+    //// Then
+    assert!(behaviour.waku_relay.is_enabled());
+    assert!(!behaviour.ping.is_enabled());
+    assert!(!behaviour.keep_alive.is_enabled());
+}
+
+#[test]
+fn it_fail_to_build_without_keypair() {
+    //// When
+    let result = NodeBuilder::new()
+        .with_relay(Default::default())
+        .build_behaviour();
+
+    //// Then
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn it_build_relay_node_with_transport() {
+    //// Setup
+    let keypair = new_keypair("9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c");
+    let transport = memory_transport(&keypair).expect("create the transport");
+
+    //// When
+    let node = NodeBuilder::new()
+        .keypair(keypair.clone())
+        .with_relay(Default::default())
+        .build_with_transport(transport)
+        .expect("node build to succeed");
+
+    //// Then
+    assert_eq!(node.peer_id(), keypair.public().to_peer_id());
+}
+
+#[test]
+fn it_build_config_with_the_node_options() {
+    //// Given
+    let keypair = new_keypair("5b7f5e3d1c23bb1a6f0a0e4b5a27c0e0a9a3b9e1a6a4a4e6f1d7c2b3a4d5e6f7");
+
+    //// When
+    let config = NodeBuilder::new()
+        .keypair(keypair)
+        .with_relay(Default::default())
+        .configure(|config| {
+            config
+                .with_network_id(42)
+                .with_max_peers(8)
+                .with_event_buffer_size(64)
+                .with_discovery_dial_cooldown(Duration::from_secs(5))
+                .with_content_topic_metrics(16)
+                .with_transport(TransportKind::WebSocket)
+        })
+        .build_config()
+        .expect("config build to succeed");
+
+    //// Then
+    assert!(config.relay.is_some());
+    assert_eq!(config.network_id, Some(42));
+    assert_eq!(config.max_peers, Some(8));
+    assert_eq!(config.event_buffer_size, 64);
+    assert_eq!(config.discovery_dial_cooldown, Duration::from_secs(5));
+    assert_eq!(config.content_topic_metrics, Some(16));
+    assert_eq!(config.transport, TransportKind::WebSocket);
+}
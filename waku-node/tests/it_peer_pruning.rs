@@ -0,0 +1,65 @@
+use libp2p::PeerId;
+
+use waku_node::{select_peers_to_prune, PruningCandidate};
+
+fn candidate(score: Option<f64>, protected: bool) -> PruningCandidate {
+    PruningCandidate {
+        peer_id: PeerId::random(),
+        score,
+        protected,
+    }
+}
+
+#[test]
+fn it_keep_all_peers_below_the_limit() {
+    //// Given
+    let candidates = vec![candidate(Some(-50.0), false), candidate(None, false)];
+
+    //// When
+    let pruned = select_peers_to_prune(&candidates, 3);
+
+    //// Then
+    assert!(pruned.is_empty());
+}
+
+#[test]
+fn it_prune_lowest_scored_unprotected_peers_at_the_limit() {
+    //// Setup
+    let max_peers = 5;
+
+    //// Given
+    // A mesh peer with the lowest score, which must be kept
+    let mesh_peer = candidate(Some(-100.0), true);
+    let low_scored_peer = candidate(Some(-20.0), false);
+    let unscored_peer = candidate(None, false);
+    let candidates = vec![
+        candidate(Some(30.0), false),
+        unscored_peer.clone(),
+        mesh_peer.clone(),
+        candidate(Some(10.0), false),
+        low_scored_peer.clone(),
+    ];
+
+    //// When
+    let pruned = select_peers_to_prune(&candidates, max_peers);
+
+    //// Then
+    assert_eq!(pruned, vec![low_scored_peer.peer_id]);
+    assert!(!pruned.contains(&mesh_peer.peer_id));
+    assert!(
+        candidates.len() - pruned.len() < max_peers,
+        "a slot should be freed for a new connection"
+    );
+}
+
+#[test]
+fn it_never_prune_protected_peers() {
+    //// Given
+    let candidates = vec![candidate(Some(-100.0), true), candidate(Some(-90.0), true)];
+
+    //// When
+    let pruned = select_peers_to_prune(&candidates, 2);
+
+    //// Then
+    assert!(pruned.is_empty());
+}
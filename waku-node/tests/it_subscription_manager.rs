@@ -0,0 +1,57 @@
+use waku_core::content_topic::ContentTopic;
+use waku_core::pubsub_topic::PubsubTopic;
+use waku_node::SubscriptionManager;
+
+#[test]
+fn it_keep_shard_subscription_while_referenced() {
+    //// Setup
+    let mut subscriptions = SubscriptionManager::new();
+
+    //// Given
+    let pubsub_topic: PubsubTopic = "/waku/2/rs/0/1".parse().unwrap();
+    let content_topic_a: ContentTopic = "/toychat/2/huilong/proto".parse().unwrap();
+    let content_topic_b: ContentTopic = "/statusim/1/community/cbor".parse().unwrap();
+
+    let subscribe_a = subscriptions.subscribe(content_topic_a.clone(), pubsub_topic.clone());
+    let subscribe_b = subscriptions.subscribe(content_topic_b.clone(), pubsub_topic.clone());
+
+    //// When
+    let unsubscribe_a = subscriptions.unsubscribe(&content_topic_a);
+
+    //// Then
+    assert!(
+        subscribe_a,
+        "the first content topic should subscribe the shard"
+    );
+    assert!(!subscribe_b, "the shard should already be subscribed");
+    assert_eq!(unsubscribe_a, None, "the shard is still referenced");
+    assert!(subscriptions.is_subscribed(&pubsub_topic));
+    assert_eq!(subscriptions.pubsub_topic(&content_topic_a), None);
+    assert_eq!(
+        subscriptions.pubsub_topic(&content_topic_b),
+        Some(&pubsub_topic)
+    );
+}
+
+#[test]
+fn it_drop_shard_subscription_when_unreferenced() {
+    //// Setup
+    let mut subscriptions = SubscriptionManager::new();
+
+    //// Given
+    let pubsub_topic: PubsubTopic = "/waku/2/rs/0/1".parse().unwrap();
+    let content_topic_a: ContentTopic = "/toychat/2/huilong/proto".parse().unwrap();
+    let content_topic_b: ContentTopic = "/statusim/1/community/cbor".parse().unwrap();
+
+    subscriptions.subscribe(content_topic_a.clone(), pubsub_topic.clone());
+    subscriptions.subscribe(content_topic_b.clone(), pubsub_topic.clone());
+
+    //// When
+    let unsubscribe_a = subscriptions.unsubscribe(&content_topic_a);
+    let unsubscribe_b = subscriptions.unsubscribe(&content_topic_b);
+
+    //// Then
+    assert_eq!(unsubscribe_a, None);
+    assert_eq!(unsubscribe_b, Some(pubsub_topic.clone()));
+    assert!(!subscriptions.is_subscribed(&pubsub_topic));
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use libp2p::identity::secp256k1;
+use libp2p::{Multiaddr, PeerId};
+use tokio::time::timeout;
+
+use waku_node::{memory_transport, Event, Node, NodeConfigBuilder, UpgradeError};
+
+fn new_node(key: &str) -> Node {
+    let keypair = {
+        let raw_key = hex::decode(key).expect("key to be valid");
+        let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+        secp256k1::Keypair::from(secret_key).into()
+    };
+
+    let config = NodeConfigBuilder::new().keypair(keypair).build();
+
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    Node::new_with_transport(config, transport).expect("node creation to succeed")
+}
+
+#[tokio::test]
+async fn it_emit_upgrade_error_on_handshake_identity_mismatch() {
+    //// Setup
+    let peer_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let peer_addr: Multiaddr = "/memory/54".parse().unwrap();
+    let peer = new_node(peer_key);
+    peer.switch_listen_on(&peer_addr)
+        .await
+        .expect("listen on address");
+
+    let node_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let mut node = new_node(node_key);
+
+    //// Given
+    // The security handshake authenticates a different identity than the expected one
+    let expected_peer = PeerId::random();
+
+    //// When
+    node.dial_discovered_peer(expected_peer, &peer_addr)
+        .await
+        .expect("dial command to succeed");
+
+    let event = timeout(Duration::from_secs(5), node.recv_event())
+        .await
+        .expect("event to be received");
+
+    //// Then
+    assert!(matches!(
+        event,
+        Some(Event::UpgradeError {
+            peer: Some(peer_id),
+            error: UpgradeError::WrongPeerId { obtained },
+        }) if peer_id == expected_peer && obtained == peer.peer_id()
+    ));
+}
+
+#[tokio::test]
+async fn it_not_emit_upgrade_error_on_refused_dial() {
+    //// Setup
+    let node_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let mut node = new_node(node_key);
+
+    //// Given
+    // No node is listening on the address, so the connection is refused by the transport
+    let unreachable_addr: Multiaddr = "/memory/57".parse().unwrap();
+
+    //// When
+    node.switch_dial(&unreachable_addr)
+        .await
+        .expect("dial command to succeed");
+
+    let event = timeout(Duration::from_millis(500), node.recv_event()).await;
+
+    //// Then
+    assert!(event.is_err(), "no upgrade error event expected");
+}
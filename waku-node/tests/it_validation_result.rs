@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use libp2p::identity::secp256k1;
+use libp2p::Multiaddr;
+use tokio::time::{sleep, timeout};
+
+use waku_core::message::WakuMessage;
+use waku_core::pubsub_topic::PubsubTopic;
+use waku_node::{
+    memory_transport, Event, Node, NodeConfigBuilder, ValidationResult, WakuRelayConfigBuilder,
+};
+use waku_relay::gossipsub::MessageAcceptance;
+
+fn new_node(key: &str, validate_messages: bool) -> Node {
+    let keypair = {
+        let raw_key = hex::decode(key).expect("key to be valid");
+        let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();
+        secp256k1::Keypair::from(secret_key).into()
+    };
+
+    let relay_config = WakuRelayConfigBuilder::new()
+        .validate_messages(validate_messages)
+        .build();
+    let config = NodeConfigBuilder::new()
+        .keypair(keypair)
+        .with_keepalive(true)
+        .with_waku_relay(relay_config)
+        .build();
+
+    let transport = memory_transport(&config.keypair).expect("create the transport");
+
+    Node::new_with_transport(config, transport).expect("node creation to succeed")
+}
+
+#[tokio::test]
+async fn it_forward_message_accepted_via_validation_result_channel() {
+    //// Setup
+    let publisher_key = "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe";
+    let publisher_addr: Multiaddr = "/memory/61".parse().unwrap();
+    let publisher = new_node(publisher_key, false);
+    publisher
+        .switch_listen_on(&publisher_addr)
+        .await
+        .expect("listen on address");
+
+    let validator_key = "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c";
+    let validator_addr: Multiaddr = "/memory/62".parse().unwrap();
+    let mut validator = new_node(validator_key, true);
+    validator
+        .switch_listen_on(&validator_addr)
+        .await
+        .expect("listen on address");
+
+    let receiver_key = "5a2b2b0f3b8f5e4c9e0e6a4f0c3d2b1a09f8e7d6c5b4a3928170f6e5d4c3b2a1";
+    let mut receiver = new_node(receiver_key, false);
+
+    // The receiver is only connected to the validator node
+    validator
+        .switch_dial(&publisher_addr)
+        .await
+        .expect("dial to succeed");
+    receiver
+        .switch_dial(&validator_addr)
+        .await
+        .expect("dial to succeed");
+
+    let pubsub_topic: PubsubTopic = "/waku/2/it-waku/test".parse().unwrap();
+    for node in [&publisher, &validator, &receiver] {
+        node.relay_subscribe(&pubsub_topic)
+            .await
+            .expect("subscribe to topic");
+    }
+
+    // Wait for the pub-sub mesh to form
+    sleep(Duration::from_millis(1500)).await;
+
+    //// Given
+    let message = WakuMessage {
+        payload: Bytes::from_static(b"TEST"),
+        content_topic: "/test/v1/it/text".parse().unwrap(),
+        meta: None,
+        ephemeral: false,
+    };
+
+    publisher
+        .relay_publish(&pubsub_topic, message.clone())
+        .await
+        .expect("publish the message");
+
+    let validator_event = timeout(Duration::from_secs(5), validator.recv_event())
+        .await
+        .expect("message to be received by the validator");
+    let (message_id, propagation_source) = match validator_event {
+        Some(Event::WakuRelayMessage {
+            message_id,
+            propagation_source,
+            ..
+        }) => (message_id, propagation_source),
+        event => panic!("unexpected validator event: {event:?}"),
+    };
+
+    // The message is held back until the validator reports the validation result
+    assert!(
+        timeout(Duration::from_secs(1), receiver.recv_event())
+            .await
+            .is_err(),
+        "message forwarded before its validation"
+    );
+
+    //// When
+    validator
+        .validation_result_sender()
+        .send(ValidationResult::new(
+            message_id,
+            propagation_source,
+            MessageAcceptance::Accept,
+        ))
+        .await
+        .expect("send the validation result");
+
+    let receiver_event = timeout(Duration::from_secs(5), receiver.recv_event())
+        .await
+        .expect("message to be forwarded to the receiver");
+
+    //// Then
+    assert_eq!(propagation_source, publisher.peer_id());
+    assert!(matches!(
+        receiver_event,
+        Some(Event::WakuRelayMessage {
+            pubsub_topic: topic,
+            message: msg,
+            ..
+        }) if topic == pubsub_topic && msg == message
+    ));
+}
@@ -86,6 +86,7 @@ async fn it_publish_and_subscribe() {
     if let Some(Event::WakuRelayMessage {
         pubsub_topic: topic,
         message: msg,
+        ..
     }) = event
     {
         assert_eq!(topic, pubsub_topic);
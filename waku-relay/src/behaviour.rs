@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use libp2p::identity::PeerId;
 use libp2p::swarm::NetworkBehaviour;
 use prost::Message;
@@ -8,7 +10,9 @@ use waku_core::pubsub_topic::PubsubTopic;
 
 use crate::error::{PublishError, SubscriptionError};
 use crate::event::Event;
-use crate::gossipsub::{self, IdentTopic, MessageAuthenticity, MessageId, ValidationMode};
+use crate::gossipsub::{
+    self, IdentTopic, MessageAcceptance, MessageAuthenticity, MessageId, ValidationMode,
+};
 use crate::message_id::deterministic_message_id_fn;
 use crate::proto::MAX_WAKU_RELAY_MESSAGE_SIZE;
 
@@ -20,47 +24,161 @@ pub struct Behaviour {
     pubsub: gossipsub::Behaviour,
 }
 
-impl Default for Behaviour {
-    fn default() -> Self {
-        let pubsub_config = gossipsub::ConfigBuilder::default()
+impl Behaviour {
+    /// Creates a Waku relay behaviour.
+    ///
+    /// If `validate_messages` is set, received messages are not forwarded until the application
+    /// reports them as accepted via [`Self::report_message_validation_result()`].
+    ///
+    /// Fails if the gossipsub configuration is rejected by the underlying gossipsub behaviour.
+    pub fn new(validate_messages: bool) -> Result<Self, gossipsub::BehaviourError> {
+        let mut pubsub_config = gossipsub::ConfigBuilder::default();
+        pubsub_config
             .protocol_id(PROTOCOL_ID, gossipsub::Version::V1_1)
             .validation_mode(ValidationMode::Anonymous) // StrictNoSign
             .message_id_fn(deterministic_message_id_fn)
-            .max_transmit_size(MAX_WAKU_RELAY_MESSAGE_SIZE)
-            .build()
-            .expect("valid pubsub configuration");
+            .max_transmit_size(MAX_WAKU_RELAY_MESSAGE_SIZE);
+        if validate_messages {
+            pubsub_config.validate_messages();
+        }
+        let pubsub_config = pubsub_config.build()?;
 
-        let pubsub = gossipsub::Behaviour::new(MessageAuthenticity::Anonymous, pubsub_config)
-            .expect("valid pubsub configuration");
+        let pubsub = gossipsub::Behaviour::new(MessageAuthenticity::Anonymous, pubsub_config)?;
 
-        Self { pubsub }
+        Ok(Self { pubsub })
     }
-}
 
-impl Behaviour {
     pub fn subscribe(&mut self, topic: &PubsubTopic) -> Result<bool, SubscriptionError> {
-        let ident_topic = IdentTopic::new(topic.to_string());
+        let ident_topic = IdentTopic::from(topic.clone());
         self.pubsub.subscribe(&ident_topic).map_err(Into::into)
     }
 
     pub fn unsubscribe(&mut self, topic: &PubsubTopic) -> Result<bool, PublishError> {
-        let ident_topic = IdentTopic::new(topic.to_string());
+        let ident_topic = IdentTopic::from(topic.clone());
         self.pubsub.unsubscribe(&ident_topic).map_err(Into::into)
     }
 
+    /// Unsubscribes from all the topics, sending the PRUNEs to the mesh peers, and drains and
+    /// disconnects all the connected peers.
+    ///
+    /// Returns the peers being disconnected. Failing to announce the unsubscription from a topic
+    /// does not prevent leaving the network.
+    pub fn leave_network(&mut self) -> Vec<PeerId> {
+        self.pubsub.leave_network()
+    }
+
     pub fn publish(
         &mut self,
         topic: &PubsubTopic,
         msg: WakuMessage,
     ) -> Result<MessageId, PublishError> {
-        let ident_topic = IdentTopic::new(topic.to_string());
+        let ident_topic = IdentTopic::from(topic.clone());
         let message_proto: WakuMessageProto = msg.into();
         self.pubsub
             .publish(ident_topic, message_proto.encode_to_vec())
             .map_err(Into::into)
     }
 
+    /// Checks whether publishing the message on the topic would succeed, without publishing it.
+    ///
+    /// Returns the error [`Self::publish()`] would return.
+    pub fn publish_preflight(
+        &self,
+        topic: &PubsubTopic,
+        msg: WakuMessage,
+    ) -> Result<(), PublishError> {
+        let ident_topic = IdentTopic::from(topic.clone());
+        let message_proto: WakuMessageProto = msg.into();
+        self.pubsub
+            .publish_preflight(ident_topic, message_proto.encode_to_vec())
+            .map_err(Into::into)
+    }
+
+    /// Reports the application validation result of a received message. Accepted messages are
+    /// forwarded to the mesh peers.
+    ///
+    /// Returns `false` if the message is no longer in the message cache.
+    pub fn report_message_validation_result(
+        &mut self,
+        message_id: &MessageId,
+        propagation_source: &PeerId,
+        acceptance: MessageAcceptance,
+    ) -> Result<bool, PublishError> {
+        self.pubsub
+            .report_message_validation_result(message_id, propagation_source, acceptance)
+            .map_err(Into::into)
+    }
+
+    /// The active gossipsub configuration.
+    pub fn config(&self) -> &gossipsub::Config {
+        self.pubsub.config()
+    }
+
     pub fn add_peer(&mut self, peer_id: &PeerId) {
         self.pubsub.add_explicit_peer(peer_id);
     }
+
+    /// Disconnects a peer. If `drain` is set, the pending control messages are sent to the peer
+    /// before the connection is closed, on the first heartbeat past the drain time.
+    pub fn disconnect_peer(&mut self, peer_id: &PeerId, drain: bool) {
+        self.pubsub.disconnect_peer(peer_id, drain);
+    }
+
+    /// Whether the peer is being drained before disconnecting it.
+    pub fn is_draining_peer(&self, peer_id: &PeerId) -> bool {
+        self.pubsub.is_draining_peer(peer_id)
+    }
+
+    /// The peer's score, if peer scoring is enabled.
+    pub fn peer_score(&self, peer_id: &PeerId) -> Option<f64> {
+        self.pubsub.peer_score(peer_id)
+    }
+
+    /// The values of the peer's score components, if peer scoring is enabled.
+    pub fn peer_score_breakdown(&self, peer_id: &PeerId) -> Option<gossipsub::ScoreBreakdown> {
+        self.pubsub.peer_score_breakdown(peer_id)
+    }
+
+    /// Whether the peer is in the mesh of any of the subscribed topics.
+    pub fn is_mesh_peer(&self, peer_id: &PeerId) -> bool {
+        self.pubsub.all_mesh_peers().any(|peer| peer == peer_id)
+    }
+
+    /// Removes the peer from the topic mesh without blacklisting it. The prune backoff applies.
+    ///
+    /// Returns `false` if the peer was not in the topic mesh.
+    pub fn prune_peer(&mut self, peer_id: &PeerId, topic: &PubsubTopic) -> bool {
+        let ident_topic = IdentTopic::from(topic.clone());
+        self.pubsub.prune_peer(peer_id, &ident_topic.hash())
+    }
+
+    /// Lists the peers added as explicit peers.
+    pub fn explicit_peers(&self) -> Vec<PeerId> {
+        self.pubsub.explicit_peers()
+    }
+
+    /// Lists the blacklisted peers.
+    pub fn blacklisted_peers(&self) -> Vec<PeerId> {
+        self.pubsub.blacklisted_peers()
+    }
+
+    /// Blacklists the peers. Their messages, and the messages they created, are rejected.
+    pub fn blacklist_peers(&mut self, peer_ids: &[PeerId]) {
+        self.pubsub.blacklist_peers(peer_ids);
+    }
+
+    /// Removes the peers from the blacklist.
+    pub fn remove_blacklisted_peers(&mut self, peer_ids: &[PeerId]) {
+        self.pubsub.remove_blacklisted_peers(peer_ids);
+    }
+
+    /// Replaces the whole blacklist, disconnecting the newly blacklisted connected peers.
+    pub fn set_blacklist(&mut self, peer_ids: HashSet<PeerId>) {
+        self.pubsub.set_blacklist(peer_ids);
+    }
+
+    /// Whether the peer was added as an explicit peer.
+    pub fn is_explicit_peer(&self, peer_id: &PeerId) -> bool {
+        self.pubsub.is_explicit_peer(peer_id)
+    }
 }
@@ -54,3 +54,14 @@ impl From<gossipsub::SubscriptionError> for SubscriptionError {
         }
     }
 }
+
+/// Error associated with converting a gossipsub topic hash into a Waku pubsub topic.
+#[derive(Debug, thiserror::Error)]
+pub enum TopicHashError {
+    /// The topic hash is empty.
+    #[error("empty topic hash")]
+    Empty,
+    /// The topic hash was not created by the identity hasher, e.g., it is a SHA256 hash.
+    #[error("not an identity topic hash")]
+    NotIdentityHash,
+}
@@ -1,17 +1,26 @@
 use libp2p::identity::PeerId;
+use libp2p::Multiaddr;
 use prost::Message;
 use strum_macros::Display;
 
-use waku_core::message::MAX_WAKU_MESSAGE_SIZE;
 use waku_core::message::proto::waku::message::v1::WakuMessage as WakuMessageProto;
 use waku_core::message::WakuMessage;
+use waku_core::message::MAX_WAKU_MESSAGE_SIZE;
 use waku_core::pubsub_topic::PubsubTopic;
 
 use crate::gossipsub;
+use crate::gossipsub::MessageId;
 
 #[derive(Debug, Display)]
 pub enum Event {
-    InvalidMessage,
+    /// A received message that is not a valid Waku message. If the messages are validated by the
+    /// application, it must be reported as rejected for the message to be discarded.
+    InvalidMessage {
+        /// The peer that forwarded us this message.
+        propagation_source: PeerId,
+        /// The message identifier, referenced when reporting the message validation result.
+        message_id: MessageId,
+    },
     Subscribed {
         peer_id: PeerId,
         pubsub_topic: PubsubTopic,
@@ -21,12 +30,34 @@ pub enum Event {
         pubsub_topic: PubsubTopic,
     },
     Message {
+        /// The peer that forwarded us this message.
+        propagation_source: PeerId,
+        /// The message identifier, referenced when reporting the message validation result.
+        message_id: MessageId,
         pubsub_topic: PubsubTopic,
         message: WakuMessage,
     },
     WakuRelayNotSupported {
         peer_id: PeerId,
     },
+    PeerAddressChanged {
+        peer_id: PeerId,
+        old: Multiaddr,
+        new: Multiaddr,
+    },
+    PossibleEclipse {
+        pubsub_topic: PubsubTopic,
+    },
+    PeerGraylisted {
+        peer_id: PeerId,
+    },
+    PeerUngraylisted {
+        peer_id: PeerId,
+    },
+    RpcTruncated {
+        peer_id: PeerId,
+        dropped: usize,
+    },
 }
 
 impl From<gossipsub::Event> for Event {
@@ -40,18 +71,30 @@ impl From<gossipsub::Event> for Event {
                 peer_id,
                 pubsub_topic: PubsubTopic::new(topic.into_string()),
             },
-            gossipsub::Event::Message { message, .. } => {
+            gossipsub::Event::Message {
+                propagation_source,
+                message_id,
+                message,
+            } => {
                 if message.data.len() > MAX_WAKU_MESSAGE_SIZE {
-                    return Self::InvalidMessage;
+                    return Self::InvalidMessage {
+                        propagation_source,
+                        message_id,
+                    };
                 }
 
                 let waku_message = if let Ok(msg) = WakuMessageProto::decode(&message.data[..]) {
                     msg.into()
                 } else {
-                    return Self::InvalidMessage;
+                    return Self::InvalidMessage {
+                        propagation_source,
+                        message_id,
+                    };
                 };
 
                 Self::Message {
+                    propagation_source,
+                    message_id,
                     pubsub_topic: PubsubTopic::new(message.topic.to_string()),
                     message: waku_message,
                 }
@@ -59,6 +102,17 @@ impl From<gossipsub::Event> for Event {
             gossipsub::Event::GossipsubNotSupported { peer_id } => {
                 Self::WakuRelayNotSupported { peer_id }
             }
+            gossipsub::Event::PeerAddressChanged { peer_id, old, new } => {
+                Self::PeerAddressChanged { peer_id, old, new }
+            }
+            gossipsub::Event::PossibleEclipse { topic } => Self::PossibleEclipse {
+                pubsub_topic: PubsubTopic::new(topic.into_string()),
+            },
+            gossipsub::Event::PeerGraylisted { peer_id } => Self::PeerGraylisted { peer_id },
+            gossipsub::Event::PeerUngraylisted { peer_id } => Self::PeerUngraylisted { peer_id },
+            gossipsub::Event::RpcTruncated { peer_id, dropped } => {
+                Self::RpcTruncated { peer_id, dropped }
+            }
         }
     }
 }
@@ -144,14 +144,20 @@
 pub use self::behaviour::Behaviour;
 pub use self::config::MessageAuthenticity;
 pub use self::config::{Config, ConfigBuilder, ValidationMode, Version};
-pub use self::error::{PublishError, SubscriptionError};
+pub use self::error::{
+    BehaviourError, ConnectionRejected, MetricsError, PublishError, SubscriptionError,
+};
 pub use self::event::Event;
 pub use self::message_id::{FastMessageId, MessageId};
 pub use self::metrics::Config as MetricsConfig;
+pub use self::peer_acceptance::{
+    AcceptAllPeersPolicy, CallbackPeerAcceptancePolicy, PeerAcceptancePolicy,
+};
 pub use self::peer_score::{
     score_parameter_decay, score_parameter_decay_with_base, PeerScoreParams, PeerScoreThresholds,
-    TopicScoreParams,
+    ScoreBreakdown, ThresholdStatus, TopicScoreBreakdown, TopicScoreParams,
 };
+pub use self::signing::{SignatureKeyType, SigningKey};
 pub use self::subscription_filter::{
     AllowAllSubscriptionFilter, CallbackSubscriptionFilter, CombinedSubscriptionFilters,
     MaxCountSubscriptionFilter, RegexSubscriptionFilter, TopicSubscriptionFilter,
@@ -159,7 +165,10 @@ pub use self::subscription_filter::{
 };
 pub use self::topic::{Hasher, Topic, TopicHash};
 pub use self::transform::{DataTransform, IdentityTransform};
-pub use self::types::{Message, MessageAcceptance, RawMessage, Rpc};
+pub use self::types::{
+    ControlMessageKind, ControlMessageSummary, McacheStats, MeshInfo, Message, MessageAcceptance,
+    PublishOptions, RawMessage, Rpc, TopicPeerRoles,
+};
 
 mod backoff;
 mod behaviour;
@@ -174,6 +183,7 @@ mod heartbeat;
 mod mcache;
 mod message_id;
 mod metrics;
+mod peer_acceptance;
 mod peer_score;
 mod protocol;
 mod rpc;
@@ -19,10 +19,12 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::cmp::max;
+use std::cmp::min;
 use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
 use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt;
-use std::net::IpAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::task::{Context, Poll};
 use std::time::Duration;
 
@@ -34,19 +36,21 @@ use libp2p::identity::PeerId;
 use libp2p::swarm::{
     behaviour::{AddressChange, ConnectionClosed, ConnectionEstablished, FromSwarm},
     dial_opts::DialOpts,
-    ConnectionDenied, ConnectionId, NetworkBehaviour, NotifyHandler, PollParameters, THandler,
-    THandlerInEvent, THandlerOutEvent, ToSwarm,
+    CloseConnection, ConnectionDenied, ConnectionId, NetworkBehaviour, NotifyHandler,
+    PollParameters, THandler, THandlerInEvent, THandlerOutEvent, ToSwarm,
 };
 use log::{debug, error, trace, warn};
 use prometheus_client::registry::Registry;
 use prost::Message as _;
-use rand::{seq::SliceRandom, thread_rng};
+use rand::rngs::StdRng;
+use rand::{seq::SliceRandom, thread_rng, Rng, RngCore, SeedableRng};
 
 use crate::gossipsub::backoff::BackoffStorage;
 use crate::gossipsub::config::{Config, MessageAuthenticity, ValidationMode};
 use crate::gossipsub::connection_manager::ConnectionManager;
 use crate::gossipsub::error::{
-    MessageValidationError as ValidationError, PublishError, SubscriptionError,
+    BehaviourError, ConnectionRejected, MessageValidationError as ValidationError, PublishError,
+    SubscriptionError,
 };
 use crate::gossipsub::event::Event;
 use crate::gossipsub::handler::{Handler, HandlerEvent, HandlerIn};
@@ -56,10 +60,13 @@ use crate::gossipsub::message_id::{FastMessageId, MessageId};
 use crate::gossipsub::metrics::{
     Churn, Config as MetricsConfig, GossipsubMetrics, Inclusion, Metrics, NoopMetrics, Penalty,
 };
+use crate::gossipsub::peer_acceptance::{AcceptAllPeersPolicy, PeerAcceptancePolicy};
 use crate::gossipsub::peer_score::{
     GossipsubPeerScoreService, NoopPeerScoreService, PeerScore, PeerScoreService, RejectReason,
 };
-use crate::gossipsub::peer_score::{PeerScoreParams, PeerScoreThresholds, TopicScoreParams};
+use crate::gossipsub::peer_score::{
+    PeerScoreParams, PeerScoreThresholds, ScoreBreakdown, ThresholdStatus, TopicScoreParams,
+};
 use crate::gossipsub::protocol::ProtocolUpgrade;
 use crate::gossipsub::rpc::{fragment_rpc_message, validate_message_proto, MessageRpc, RpcProto};
 use crate::gossipsub::seq_no::{
@@ -68,17 +75,21 @@ use crate::gossipsub::seq_no::{
 use crate::gossipsub::signing::{
     AnonymousMessageValidator, AuthorOnlySigner, Libp2pSigner, MessageSigner, MessageValidator,
     NoopMessageValidator, NoopSigner, PermissiveMessageValidator, RandomAuthorSigner,
-    StrictMessageValidator,
+    SignatureKeyType, StrictMessageValidator,
 };
 use crate::gossipsub::subscription_filter::{AllowAllSubscriptionFilter, TopicSubscriptionFilter};
 use crate::gossipsub::time_cache::{DuplicateCache, TimeCache};
 use crate::gossipsub::topic::{Hasher, Topic, TopicHash};
 use crate::gossipsub::transform::{DataTransform, IdentityTransform};
 use crate::gossipsub::types::{
-    ControlAction, Message, MessageAcceptance, PeerInfo, PeerKind, RawMessage, Rpc, Subscription,
-    SubscriptionAction,
+    ControlAction, ControlMessageSummary, McacheStats, MeshInfo, Message, MessageAcceptance,
+    PeerInfo, PeerKind, PublishOptions, RawMessage, Rpc, Subscription, SubscriptionAction,
+    TopicPeerRoles,
 };
 
+/// The maximum number of sent control messages kept for inspection.
+const CONTROL_HISTORY_SIZE: usize = 256;
+
 fn get_ip_addr(addr: &Multiaddr) -> Option<IpAddr> {
     addr.iter().find_map(|p| match p {
         Ip4(addr) => Some(IpAddr::V4(addr)),
@@ -87,6 +98,20 @@ fn get_ip_addr(addr: &Multiaddr) -> Option<IpAddr> {
     })
 }
 
+/// Returns the subnet an IP address belongs to: /24 for IPv4 and /64 for IPv6 addresses.
+fn get_ip_subnet(ip: &IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(ip) => {
+            let [a, b, c, d, ..] = ip.segments();
+            IpAddr::V6(Ipv6Addr::new(a, b, c, d, 0, 0, 0, 0))
+        }
+    }
+}
+
 /// This is called when peers are added to any mesh. It checks if the peer existed
 /// in any other mesh. If this is the first mesh they have joined, it queues a message to notify
 /// the appropriate connection handler to maintain a connection.
@@ -172,6 +197,7 @@ fn get_random_peers_dynamic(
     // maps the number of total peers to the number of selected peers
     n_map: impl Fn(usize) -> usize,
     mut f: impl FnMut(&PeerId) -> bool,
+    rng: &mut impl Rng,
 ) -> BTreeSet<PeerId> {
     let mut gossip_peers = match topic_peers.get(topic_hash) {
         // if they exist, filter the peers by `f`
@@ -191,8 +217,7 @@ fn get_random_peers_dynamic(
     }
 
     // we have more peers than needed, shuffle them and return n of them
-    let mut rng = thread_rng();
-    gossip_peers.partial_shuffle(&mut rng, n);
+    gossip_peers.partial_shuffle(rng, n);
 
     debug!("RANDOM PEERS: Got {:?} peers", n);
 
@@ -207,8 +232,59 @@ fn get_random_peers(
     topic_hash: &TopicHash,
     n: usize,
     f: impl FnMut(&PeerId) -> bool,
+    rng: &mut impl Rng,
 ) -> BTreeSet<PeerId> {
-    get_random_peers_dynamic(topic_peers, connected_peers, topic_hash, |_| n, f)
+    get_random_peers_dynamic(topic_peers, connected_peers, topic_hash, |_| n, f, rng)
+}
+
+/// The peers a message published on a topic is sent to.
+#[derive(Debug)]
+struct PublishPeers {
+    /// The peers the message is sent to directly.
+    recipients: HashSet<PeerId>,
+    /// Whether the message is forwarded to the topic mesh peers.
+    forward_to_mesh: bool,
+    /// Whether the message is published to the topic fanout, the local node not being subscribed
+    /// to the topic.
+    via_fanout: bool,
+    /// If the topic has no fanout yet, the peers the new fanout peers are selected from.
+    fanout_candidates: Option<Vec<PeerId>>,
+}
+
+impl PublishPeers {
+    /// Whether the message would not be sent to any peer.
+    fn is_empty(&self) -> bool {
+        self.recipients.is_empty()
+            && !self.forward_to_mesh
+            && self
+                .fanout_candidates
+                .as_ref()
+                .map_or(true, |candidates| candidates.is_empty())
+    }
+}
+
+/// A [`RngCore`] implementation delegating to the thread-local [`thread_rng`] generator.
+///
+/// Unlike [`rand::rngs::ThreadRng`], this type is `Send`, so it can be stored in the [`Behaviour`].
+#[derive(Debug, Default, Clone, Copy)]
+struct ThreadLocalRng;
+
+impl RngCore for ThreadLocalRng {
+    fn next_u32(&mut self) -> u32 {
+        thread_rng().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        thread_rng().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        thread_rng().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        thread_rng().try_fill_bytes(dest)
+    }
 }
 
 /// Validates the combination of signing, privacy and message validation to ensure the
@@ -241,6 +317,54 @@ fn validate_config(
     Ok(())
 }
 
+/// Validates that the key published messages are signed with matches the key type the network
+/// expects, if any.
+fn validate_signing_key_type(
+    authenticity: &MessageAuthenticity,
+    key_type: Option<SignatureKeyType>,
+) -> Result<(), &'static str> {
+    let public_key = match authenticity {
+        MessageAuthenticity::Signed(keypair) => keypair.public(),
+        MessageAuthenticity::ExternalSigned(key) => key.public(),
+        _ => return Ok(()),
+    };
+
+    match key_type {
+        Some(key_type) if SignatureKeyType::of(&public_key) != Some(key_type) => {
+            Err("The message signing key type does not match the configured signature key type. Published messages would be rejected by the network peers")
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Creates the validator for incoming messages matching the validation mode.
+fn new_message_validator(
+    config: &Config,
+    validation_mode: &ValidationMode,
+) -> Box<dyn MessageValidator + Send> {
+    match validation_mode {
+        ValidationMode::Strict => match config.signature_key_type() {
+            Some(key_type) => Box::new(StrictMessageValidator::with_key_type(key_type)),
+            None => Box::new(StrictMessageValidator::new()),
+        },
+        ValidationMode::Permissive => Box::new(PermissiveMessageValidator::new()),
+        ValidationMode::Anonymous => Box::new(AnonymousMessageValidator::new()),
+        ValidationMode::None => Box::new(NoopMessageValidator::new()),
+    }
+}
+
+/// Truncates the received control entries, or requested message ids, to `max`, if set. Returns
+/// true if any entry was dropped.
+fn truncate_to_limit<T>(entries: &mut Vec<T>, max: Option<usize>) -> bool {
+    match max {
+        Some(max) if entries.len() > max => {
+            entries.truncate(max);
+            true
+        }
+        _ => false,
+    }
+}
+
 /// Network behaviour that handles the gossipsub protocol.
 ///
 /// NOTE: Initialisation requires a [`MessageAuthenticity`] and [`Config`] instance. If
@@ -260,7 +384,24 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     events: VecDeque<ToSwarm<Event, HandlerIn>>,
 
     /// Pools non-urgent control messages between heartbeats.
-    control_pool: HashMap<PeerId, Vec<ControlAction>>,
+    control_pool: HashMap<PeerId, VecDeque<ControlAction>>,
+
+    /// A bounded history of the most recently sent control messages.
+    control_history: VecDeque<ControlMessageSummary>,
+
+    /// The GRAFT control messages sent since the last heartbeat, indexed by peer and topic. This
+    /// prevents sending duplicate GRAFTs within the same heartbeat cycle.
+    grafts_sent: HashSet<(PeerId, TopicHash)>,
+
+    /// The topics we were subscribed to and have since unsubscribed from, with the time of the
+    /// unsubscription. Entries expire after [`Config::unsubscription_rebroadcast_time()`].
+    unsubscribed_topics: HashMap<TopicHash, Instant>,
+
+    /// The number of bytes sent to and received from each connected peer.
+    peer_bandwidth: HashMap<PeerId, (u64, u64)>,
+
+    /// The random number generator used for peer selection and gossip shuffling.
+    rng: Box<dyn RngCore + Send>,
 
     /// An LRU Time cache for storing seen messages (based on their ID). This cache prevents
     /// duplicates from being propagated to the application and on the network.
@@ -280,6 +421,10 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     /// forward messages to, outside of the scoring system.
     explicit_peers: HashSet<PeerId>,
 
+    /// The reconnection backoff of the disconnected explicit peers: the earliest time the peer can
+    /// be dialed again and the current backoff duration.
+    explicit_peer_backoffs: HashMap<PeerId, (Instant, Duration)>,
+
     /// A list of peers that have been blacklisted by the user.
     /// Messages are not sent to and are rejected from these peers.
     blacklisted_peers: HashSet<PeerId>,
@@ -293,6 +438,9 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     /// The last publish time for fanout topics.
     fanout_last_pub: HashMap<TopicHash, Instant>,
 
+    /// The number of messages published to each fanout topic since it was added to the fanout.
+    fanout_publish_count: HashMap<TopicHash, usize>,
+
     ///Storage for backoffs
     backoffs: BackoffStorage,
 
@@ -308,6 +456,22 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     /// be removed from this list which may result in a true outbound rediscovery.
     px_peers: HashSet<PeerId>,
 
+    /// The peer exchange peers we dialed, and the time they connected, that will be considered
+    /// outbound peers once their probation period elapses.
+    px_outbound_probation: HashMap<PeerId, Instant>,
+
+    /// The remote IP address of the first connection to each connected peer.
+    peer_ips: HashMap<PeerId, IpAddr>,
+
+    /// The topics whose mesh was flagged as possibly eclipsed on the last heartbeat.
+    possible_eclipses: HashSet<TopicHash>,
+
+    /// The connected peers whose score was below the graylist threshold on the last heartbeat.
+    graylisted_peers: HashSet<PeerId>,
+
+    /// The peers being disconnected, and the time after which their connections are closed.
+    draining_peers: HashMap<PeerId, Instant>,
+
     /// Stores optional peer score data together with thresholds, decay interval and gossip
     /// promises.
     peer_score: Box<dyn PeerScoreService + Send>,
@@ -332,6 +496,9 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     /// The filter used to handle message subscriptions.
     subscription_filter: F,
 
+    /// The policy deciding whether the established connections are accepted.
+    peer_acceptance_policy: Box<dyn PeerAcceptancePolicy + Send>,
+
     /// A general transformation function that can be applied to data received from the wire before
     /// calculating the message-id and sending to the application. This is designed to allow the
     /// user to implement arbitrary topic-based compression algorithms.
@@ -343,6 +510,14 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     /// A validator for incoming messages.
     message_validator: Box<dyn MessageValidator + Send>,
 
+    /// The validators for incoming messages of the topics overriding the configured validation
+    /// mode.
+    topic_message_validators: HashMap<TopicHash, Box<dyn MessageValidator + Send>>,
+
+    /// The authenticity of the published messages. The topic validation mode overrides must be
+    /// compatible with it.
+    privacy: MessageAuthenticity,
+
     /// A generator for message sequence numbers.
     message_seqno_generator: Option<Box<dyn MessageSeqNumberGenerator + Send>>,
 
@@ -357,7 +532,7 @@ where
 {
     /// Creates a Gossipsub [`Behaviour`] struct given a set of parameters specified via a
     /// [`Config`]. This has no subscription filter and uses no compression.
-    pub fn new(privacy: MessageAuthenticity, config: Config) -> Result<Self, &'static str> {
+    pub fn new(privacy: MessageAuthenticity, config: Config) -> Result<Self, BehaviourError> {
         Self::new_with_subscription_filter_and_transform(
             privacy,
             config,
@@ -375,7 +550,7 @@ where
         config: Config,
         metrics_registry: &mut Registry,
         metrics_config: MetricsConfig,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Self, BehaviourError> {
         Self::new_with_subscription_filter_and_transform(
             privacy,
             config,
@@ -398,7 +573,7 @@ where
         config: Config,
         metrics: Option<(&mut Registry, MetricsConfig)>,
         subscription_filter: F,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Self, BehaviourError> {
         Self::new_with_subscription_filter_and_transform(
             privacy,
             config,
@@ -421,7 +596,7 @@ where
         config: Config,
         metrics: Option<(&mut Registry, MetricsConfig)>,
         data_transform: D,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Self, BehaviourError> {
         Self::new_with_subscription_filter_and_transform(
             privacy,
             config,
@@ -445,28 +620,26 @@ where
         metrics: Option<(&mut Registry, MetricsConfig)>,
         subscription_filter: F,
         data_transform: D,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Self, BehaviourError> {
         // Set up the router given the configuration settings.
 
         // We do not allow configurations where a published message would also be rejected if it
         // were received locally.
         validate_config(&privacy, config.validation_mode())?;
+        validate_signing_key_type(&privacy, config.signature_key_type())?;
 
         let metrics: Box<dyn Metrics + Send> = match metrics {
             None => Box::new(NoopMetrics::new()),
-            Some((registry, cfg)) => Box::new(GossipsubMetrics::new(registry, cfg)),
+            Some((registry, cfg)) => Box::new(GossipsubMetrics::new(registry, cfg)?),
         };
 
-        let message_validator: Box<dyn MessageValidator + Send> = match &config.validation_mode() {
-            ValidationMode::Strict => Box::new(StrictMessageValidator::new()),
-            ValidationMode::Permissive => Box::new(PermissiveMessageValidator::new()),
-            ValidationMode::Anonymous => Box::new(AnonymousMessageValidator::new()),
-            ValidationMode::None => Box::new(NoopMessageValidator::new()),
-        };
+        let message_validator = new_message_validator(&config, config.validation_mode());
 
         let message_seqno_generator: Option<Box<dyn MessageSeqNumberGenerator + Send>> =
             match &privacy {
-                MessageAuthenticity::Signed(_) => Some(Box::new(LinearSequenceNumber::new())),
+                MessageAuthenticity::Signed(_) | MessageAuthenticity::ExternalSigned(_) => {
+                    Some(Box::new(LinearSequenceNumber::new()))
+                }
                 MessageAuthenticity::Author(_) | MessageAuthenticity::RandomAuthor => {
                     Some(Box::new(RandomSequenceNumber::new()))
                 }
@@ -475,6 +648,9 @@ where
 
         let message_signer: Box<dyn MessageSigner + Send> = match &privacy {
             MessageAuthenticity::Signed(keypair) => Box::new(Libp2pSigner::new(keypair)),
+            MessageAuthenticity::ExternalSigned(key) => {
+                Box::new(Libp2pSigner::with_signing_key(key.clone()))
+            }
             MessageAuthenticity::Author(peer_id) => Box::new(AuthorOnlySigner::new(*peer_id)),
             MessageAuthenticity::RandomAuthor => Box::new(RandomAuthorSigner::new()),
             MessageAuthenticity::Anonymous => Box::new(NoopSigner::new()),
@@ -484,26 +660,42 @@ where
             metrics,
             events: VecDeque::new(),
             control_pool: HashMap::new(),
+            control_history: VecDeque::with_capacity(CONTROL_HISTORY_SIZE),
+            grafts_sent: HashSet::new(),
+            peer_bandwidth: HashMap::new(),
+            unsubscribed_topics: HashMap::new(),
+            rng: Box::new(ThreadLocalRng),
             duplicate_cache: DuplicateCache::new(config.duplicate_cache_time()),
             fast_message_id_cache: TimeCache::new(config.duplicate_cache_time()),
             topic_peers: HashMap::new(),
             peer_topics: HashMap::new(),
             explicit_peers: HashSet::new(),
+            explicit_peer_backoffs: HashMap::new(),
             blacklisted_peers: HashSet::new(),
             mesh: HashMap::new(),
             fanout: HashMap::new(),
             fanout_last_pub: HashMap::new(),
+            fanout_publish_count: HashMap::new(),
             backoffs: BackoffStorage::new(
                 &config.prune_backoff(),
                 config.heartbeat_interval(),
                 config.backoff_slack(),
             ),
-            mcache: MessageCache::new(config.history_gossip(), config.history_length()),
+            mcache: MessageCache::new(
+                config.history_gossip(),
+                config.history_length(),
+                config.history_max_bytes(),
+            ),
             heartbeat: Heartbeat::new(
                 config.heartbeat_interval(),
                 config.heartbeat_initial_delay(),
             ),
             px_peers: HashSet::new(),
+            px_outbound_probation: HashMap::new(),
+            peer_ips: HashMap::new(),
+            possible_eclipses: HashSet::new(),
+            graylisted_peers: HashSet::new(),
+            draining_peers: HashMap::new(),
             peer_score: Box::new(NoopPeerScoreService::new()),
             count_received_ihave: HashMap::new(),
             count_sent_iwant: HashMap::new(),
@@ -512,8 +704,11 @@ where
             published_message_ids: DuplicateCache::new(config.published_message_ids_cache_time()),
             config,
             subscription_filter,
+            peer_acceptance_policy: Box::new(AcceptAllPeersPolicy),
             data_transform,
             message_validator,
+            topic_message_validators: HashMap::new(),
+            privacy,
             message_seqno_generator,
             message_signer,
         })
@@ -525,6 +720,11 @@ where
     D: DataTransform + Send + 'static,
     F: TopicSubscriptionFilter + Send + 'static,
 {
+    /// The active gossipsub configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
     /// Lists the hashes of the topics we are currently subscribed to.
     pub fn topics(&self) -> impl Iterator<Item = &TopicHash> {
         self.mesh.keys()
@@ -543,6 +743,62 @@ where
         res.into_iter()
     }
 
+    /// Returns the number of mesh peers for a certain topic hash.
+    pub fn mesh_peers_count(&self, topic_hash: &TopicHash) -> usize {
+        self.mesh.get(topic_hash).map_or(0, |peers| peers.len())
+    }
+
+    /// Lists the fanout peers for a topic we publish to without being subscribed. Returns an
+    /// empty list if the topic has no fanout.
+    pub fn fanout_peers(&self, topic_hash: &TopicHash) -> Vec<PeerId> {
+        self.fanout
+            .get(topic_hash)
+            .map(|peers| peers.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the peers known for a topic, partitioned into mesh, fanout and subscribed peers.
+    pub fn topic_peer_roles(&self, topic_hash: &TopicHash) -> TopicPeerRoles {
+        let mesh = self.mesh.get(topic_hash).cloned().unwrap_or_default();
+        let fanout = self.fanout.get(topic_hash).cloned().unwrap_or_default();
+        let subscribed = self
+            .topic_peers
+            .get(topic_hash)
+            .map(|peers| {
+                peers
+                    .iter()
+                    .filter(|p| !mesh.contains(p) && !fanout.contains(p))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        TopicPeerRoles {
+            mesh,
+            fanout,
+            subscribed,
+        }
+    }
+
+    /// Returns a snapshot of the state of each topic mesh.
+    pub fn mesh_snapshot(&self) -> HashMap<TopicHash, MeshInfo> {
+        self.mesh
+            .iter()
+            .map(|(topic_hash, peers)| {
+                let info = MeshInfo {
+                    peers: peers.len(),
+                    outbound_peers: peers
+                        .iter()
+                        .filter(|p| self.connected_peers.is_outbound(p))
+                        .count(),
+                    mesh_n_low: self.config.mesh_n_low(),
+                    mesh_n_high: self.config.mesh_n_high(),
+                };
+                (topic_hash.clone(), info)
+            })
+            .collect()
+    }
+
     /// Lists all known peers and their associated subscribed topics.
     pub fn all_peers(&self) -> impl Iterator<Item = (&PeerId, Vec<&TopicHash>)> {
         self.peer_topics
@@ -550,30 +806,95 @@ where
             .map(|(peer_id, topic_set)| (peer_id, topic_set.iter().collect()))
     }
 
+    /// Lists all the topics a given peer is subscribed to. Returns an empty list if the peer is
+    /// unknown.
+    pub fn peer_subscribed_topics(&self, peer_id: &PeerId) -> Vec<TopicHash> {
+        self.peer_topics
+            .get(peer_id)
+            .map(|topics| topics.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
     /// Lists all known peers and their associated protocol.
     pub fn peer_protocol(&self) -> impl Iterator<Item = (&PeerId, &PeerKind)> {
         self.connected_peers.peer_protocol()
     }
 
+    /// Lists the most recently sent control messages, from oldest to newest.
+    pub fn recent_control_messages(&self) -> impl Iterator<Item = &ControlMessageSummary> {
+        self.control_history.iter()
+    }
+
+    /// Returns the number of bytes sent to and received from a connected peer, as a
+    /// `(sent, received)` tuple. Messages dropped by the connection send queue are not counted as
+    /// sent.
+    pub fn peer_bandwidth(&self, peer_id: &PeerId) -> (u64, u64) {
+        self.peer_bandwidth
+            .get(peer_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns a snapshot of the state of the message cache.
+    pub fn mcache_stats(&self) -> McacheStats {
+        McacheStats {
+            messages: self.mcache.len(),
+            bytes: self.mcache.bytes(),
+            iwant_count_total: self.mcache.iwant_count_total(),
+            history_depth: self.mcache.history_depth(),
+        }
+    }
+
     /// Returns the gossipsub score for a given peer, if one exists.
     pub fn peer_score(&self, peer_id: &PeerId) -> Option<f64> {
         self.peer_score.peer_score(peer_id)
     }
 
+    /// Returns the values of the components of a peer's score, if peer scoring is enabled and the
+    /// peer is known.
+    pub fn peer_score_breakdown(&self, peer_id: &PeerId) -> Option<ScoreBreakdown> {
+        self.peer_score.peer_score_breakdown(peer_id)
+    }
+
+    /// Returns which of the peer score thresholds the peer is currently below. This helps
+    /// diagnosing why a peer is being ignored.
+    ///
+    /// If peer scoring is disabled, the peer is reported as above all the thresholds.
+    pub fn peer_score_thresholds_status(&self, peer_id: &PeerId) -> ThresholdStatus {
+        let below = |threshold: fn(&PeerScoreThresholds) -> f64| {
+            self.peer_score.score_below_threshold(peer_id, threshold).0
+        };
+
+        ThresholdStatus {
+            below_gossip_threshold: below(|ts| ts.gossip_threshold),
+            below_publish_threshold: below(|ts| ts.publish_threshold),
+            below_graylist_threshold: below(|ts| ts.graylist_threshold),
+            below_accept_px_threshold: below(|ts| ts.accept_px_threshold),
+        }
+    }
+
+    /// Lists the connected peers whose score was below the graylist threshold on the last
+    /// heartbeat. The RPCs received from these peers are ignored.
+    pub fn graylisted_peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.graylisted_peers.iter()
+    }
+
     /// Subscribe to a topic.
     ///
     /// Returns [`Ok(true)`] if the subscription worked. Returns [`Ok(false)`] if we were already
     /// subscribed.
     pub fn subscribe<H: Hasher>(&mut self, topic: &Topic<H>) -> Result<bool, SubscriptionError> {
         debug!("Subscribing to topic: {}", topic);
+        self.subscribe_topic_hash(topic.hash())
+    }
 
-        let topic_hash = topic.hash();
+    fn subscribe_topic_hash(&mut self, topic_hash: TopicHash) -> Result<bool, SubscriptionError> {
         if !self.subscription_filter.can_subscribe(&topic_hash) {
             return Err(SubscriptionError::NotAllowed);
         }
 
         if self.mesh.get(&topic_hash).is_some() {
-            debug!("Topic: {} is already in the mesh.", topic);
+            debug!("Topic: {} is already in the mesh.", topic_hash);
             return Ok(false);
         }
 
@@ -597,10 +918,12 @@ where
             }
         }
 
+        self.unsubscribed_topics.remove(&topic_hash);
+
         // call JOIN(topic)
         // this will add new peers to the mesh for the topic
         self.join(&topic_hash);
-        debug!("Subscribed to topic: {}", topic);
+        debug!("Subscribed to topic: {}", topic_hash);
         Ok(true)
     }
 
@@ -638,58 +961,138 @@ where
         // call LEAVE(topic)
         // this will remove the topic from the mesh
         self.leave(&topic_hash);
+        self.track_unsubscribed_topic(topic_hash.clone());
 
         debug!("Unsubscribed from topic: {:?}", topic_hash);
         Ok(true)
     }
 
-    /// Publishes a message with multiple topics to the network.
-    pub fn publish(
-        &mut self,
-        topic: impl Into<TopicHash>,
-        data: impl Into<Vec<u8>>,
-    ) -> Result<MessageId, PublishError> {
-        let raw_data = data.into();
-        let topic_hash = topic.into();
+    /// Records the unsubscription from the topic, to be announced to newly connected peers.
+    fn track_unsubscribed_topic(&mut self, topic_hash: TopicHash) {
+        if !self.config.rebroadcast_unsubscriptions() {
+            return;
+        }
 
-        // Transform the data before building a raw_message.
-        let transformed_data = self
-            .data_transform
-            .outbound_transform(&topic_hash, raw_data.clone())?;
+        self.prune_unsubscribed_topics();
+        self.unsubscribed_topics.insert(topic_hash, Instant::now());
+    }
 
-        let sequence_number: Option<u64> =
-            self.message_seqno_generator.as_mut().map(|gen| gen.next());
+    /// Removes the unsubscriptions older than the configured rebroadcast time.
+    fn prune_unsubscribed_topics(&mut self) {
+        let rebroadcast_time = self.config.unsubscription_rebroadcast_time();
+        self.unsubscribed_topics
+            .retain(|_, unsubscribed_at| unsubscribed_at.elapsed() < rebroadcast_time);
+    }
 
-        let mut message = MessageRpc::new_with_sequence_number(
-            topic_hash.clone(),
-            transformed_data,
-            sequence_number,
-        );
-        self.message_signer.sign(&mut message)?;
+    /// Unsubscribes from all the subscribed topics, leaving their meshes and announcing it to all
+    /// the peers.
+    ///
+    /// Every topic is left, even if its announcement fails to be sent. Returns the number of
+    /// topics left, or the topics whose announcement failed along with the error.
+    pub fn unsubscribe_all(&mut self) -> Result<usize, Vec<(TopicHash, PublishError)>> {
+        let topic_hashes = self.mesh.keys().cloned().collect::<Vec<_>>();
+        if topic_hashes.is_empty() {
+            debug!("Already unsubscribed from all topics");
+            return Ok(0);
+        }
 
-        // calculate the message id from the un-transformed data
-        let msg_id = self.config.message_id(&Message {
-            source: message.source(),
-            data: raw_data,
-            sequence_number,
-            topic: topic_hash.clone(),
-        });
+        let peer_list = self.peer_topics.keys().cloned().collect::<Vec<_>>();
+        let mut errors = Vec::new();
+        for topic_hash in &topic_hashes {
+            // announce to all peers
+            let event: RpcProto = Rpc {
+                messages: Vec::new(),
+                subscriptions: vec![Subscription {
+                    topic_hash: topic_hash.clone(),
+                    action: SubscriptionAction::Unsubscribe,
+                }],
+                control_msgs: Vec::new(),
+            }
+            .into();
 
-        let raw_message: RawMessage = message.into();
-        let event: RpcProto = Rpc {
-            subscriptions: Vec::new(),
-            messages: vec![raw_message.clone()],
-            control_msgs: Vec::new(),
+            let mut result = Ok(());
+            for peer in &peer_list {
+                debug!("Sending UNSUBSCRIBE to peer: {}", peer);
+                if let Err(err) = self.send_rpc_message(*peer, event.clone()) {
+                    warn!(
+                        "Failed to send UNSUBSCRIBE for topic {:?} to peer {}: {}",
+                        topic_hash, peer, err
+                    );
+                    result = Err(err);
+                }
+            }
+            if let Err(err) = result {
+                errors.push((topic_hash.clone(), err));
+            }
+
+            // call LEAVE(topic)
+            // this will remove the topic from the mesh
+            self.leave(topic_hash);
+            self.track_unsubscribed_topic(topic_hash.clone());
         }
-        .into();
 
-        // check that the size doesn't exceed the max transmission size
-        if event.encoded_len() > self.config.max_transmit_size() {
-            return Err(PublishError::MessageTooLarge);
+        if !errors.is_empty() {
+            return Err(errors);
         }
 
+        debug!("Unsubscribed from {} topics", topic_hashes.len());
+        Ok(topic_hashes.len())
+    }
+
+    /// Leaves the network gracefully: unsubscribes from all the topics, sending the PRUNEs to the
+    /// mesh peers, and drains and disconnects all the connected peers.
+    ///
+    /// The connections are closed on the first heartbeat past the configured
+    /// [`Config::disconnect_drain_time()`]. Returns the peers being disconnected.
+    ///
+    /// A failed UNSUBSCRIBE announcement does not prevent leaving the network: the topic is left
+    /// anyway, and the peers are drained and disconnected.
+    pub fn leave_network(&mut self) -> Vec<PeerId> {
+        if let Err(errors) = self.unsubscribe_all() {
+            for (topic_hash, err) in errors {
+                warn!(
+                    "Leaving the network without announcing the unsubscription from topic {:?}: {}",
+                    topic_hash, err
+                );
+            }
+        }
+
+        let peers = self.connected_peers.peers().cloned().collect::<Vec<_>>();
+        for peer_id in &peers {
+            self.disconnect_peer(peer_id, true);
+        }
+
+        debug!("Leaving the network, disconnecting {} peers", peers.len());
+        peers
+    }
+
+    /// Publishes a message with multiple topics to the network.
+    pub fn publish(
+        &mut self,
+        topic: impl Into<TopicHash>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<MessageId, PublishError> {
+        self.publish_with_options(topic, data, PublishOptions::default())
+    }
+
+    /// Publishes a message to the network, overriding the configured publish behaviour with the
+    /// given options for this call only.
+    pub fn publish_with_options(
+        &mut self,
+        topic: impl Into<TopicHash>,
+        data: impl Into<Vec<u8>>,
+        options: PublishOptions,
+    ) -> Result<MessageId, PublishError> {
+        let topic_hash = topic.into();
+        let flood_publish = options.flood.unwrap_or_else(|| self.config.flood_publish());
+        let (msg_id, raw_message, event) = self.build_publish_message(&topic_hash, data.into())?;
+
         // Check the if the message has been published before
         if self.duplicate_cache.contains(&msg_id) {
+            if self.config.republish_duplicates() {
+                return self.republish_message(&topic_hash, msg_id, event);
+            }
+
             // This message has already been seen. We don't re-publish messages that have already
             // been published on the network.
             warn!(
@@ -701,82 +1104,36 @@ where
 
         trace!("Publishing message: {:?}", msg_id);
 
+        let PublishPeers {
+            recipients: mut recipient_peers,
+            via_fanout,
+            fanout_candidates,
+            ..
+        } = self.select_publish_peers(&topic_hash, flood_publish);
+
         // If we are not flood publishing forward the message to mesh peers.
-        let mesh_peers_sent = !self.config.flood_publish()
+        let mesh_peers_sent = !flood_publish
             && self.forward_msg(&msg_id, raw_message.clone(), None, HashSet::new())?;
 
-        let mut recipient_peers = HashSet::new();
-        if let Some(set) = self.topic_peers.get(&topic_hash) {
-            if self.config.flood_publish() {
-                // Forward to all peers above score and all explicit peers
-                recipient_peers.extend(
-                    set.iter()
-                        .filter(|p| {
-                            self.explicit_peers.contains(*p)
-                                || !self
-                                    .peer_score
-                                    .score_below_threshold(p, |ts| ts.publish_threshold)
-                                    .0
-                        })
-                        .cloned(),
-                );
-            } else {
-                // Explicit peers
-                for peer in &self.explicit_peers {
-                    if set.contains(peer) {
-                        recipient_peers.insert(*peer);
-                    }
-                }
-
-                // Floodsub peers
-                for peer in self.connected_peers.floodsub_peers() {
-                    if !self
-                        .peer_score
-                        .score_below_threshold(peer, |ts| ts.publish_threshold)
-                        .0
-                    {
-                        recipient_peers.insert(*peer);
-                    }
-                }
-
-                // Gossipsub peers
-                if self.mesh.get(&topic_hash).is_none() {
-                    debug!("Topic: {:?} not in the mesh", topic_hash);
-                    // If we have fanout peers add them to the map.
-                    if self.fanout.contains_key(&topic_hash) {
-                        for peer in self.fanout.get(&topic_hash).expect("Topic must exist") {
-                            recipient_peers.insert(*peer);
-                        }
-                    } else {
-                        // We have no fanout peers, select mesh_n of them and add them to the fanout
-                        let mesh_n = self.config.mesh_n();
-                        let new_peers = get_random_peers(
-                            &self.topic_peers,
-                            &self.connected_peers,
-                            &topic_hash,
-                            mesh_n,
-                            {
-                                |p| {
-                                    !self.explicit_peers.contains(p)
-                                        && !self
-                                            .peer_score
-                                            .score_below_threshold(p, |pst| pst.publish_threshold)
-                                            .0
-                                }
-                            },
-                        );
-                        // Add the new peers to the fanout and recipient peers
-                        self.fanout.insert(topic_hash.clone(), new_peers.clone());
-                        for peer in new_peers {
-                            debug!("Peer added to fanout: {:?}", peer);
-                            recipient_peers.insert(peer);
-                        }
-                    }
-                    // We are publishing to fanout peers - update the time we published
-                    self.fanout_last_pub
-                        .insert(topic_hash.clone(), Instant::now());
-                }
+        if let Some(mut candidates) = fanout_candidates {
+            // We have no fanout peers, select mesh_n of them and add them to the fanout
+            let (new_peers, _) = candidates.partial_shuffle(&mut self.rng, self.config.mesh_n());
+            let new_peers = new_peers.iter().cloned().collect::<BTreeSet<_>>();
+            for peer in &new_peers {
+                debug!("Peer added to fanout: {:?}", peer);
+                recipient_peers.insert(*peer);
             }
+            self.fanout.insert(topic_hash.clone(), new_peers);
+        }
+
+        if via_fanout {
+            // We are publishing to fanout peers - update the time we published
+            self.fanout_last_pub
+                .insert(topic_hash.clone(), Instant::now());
+            *self
+                .fanout_publish_count
+                .entry(topic_hash.clone())
+                .or_default() += 1;
         }
 
         if recipient_peers.is_empty() && !mesh_peers_sent {
@@ -785,24 +1142,7 @@ where
 
         // If the message isn't a duplicate and we have sent it to some peers add it to the
         // duplicate cache and memcache.
-        self.duplicate_cache.insert(msg_id.clone());
-
-        let cached_message = CachedMessage {
-            source: raw_message.source,
-            data: raw_message.data,
-            sequence_number: raw_message.sequence_number,
-            topic: raw_message.topic,
-            signature: raw_message.signature,
-            key: raw_message.key,
-            validated: true, // all published messages are valid
-        };
-        self.mcache.put(&msg_id, cached_message);
-
-        // If the message is anonymous or has a random author add it to the published message IDs
-        // cache.
-        if self.message_signer.author().is_none() && !self.config.allow_self_origin() {
-            self.published_message_ids.insert(msg_id.clone());
-        }
+        self.cache_published_message(&msg_id, raw_message);
 
         // Send to peers we know are subscribed to the topic.
         let msg_bytes = event.encoded_len();
@@ -815,31 +1155,344 @@ where
         debug!("Published message: {:?}", &msg_id);
         self.metrics.register_published_message(&topic_hash);
 
+        // Join the mesh of the fanout topics published to frequently
+        if let Some(threshold) = self.config.fanout_join_threshold() {
+            let publish_count = self.fanout_publish_count.get(&topic_hash).copied();
+            if publish_count.is_some_and(|count| count >= threshold) {
+                debug!("Joining the mesh of fanout topic: {}", topic_hash);
+                if let Err(err) = self.subscribe_topic_hash(topic_hash) {
+                    warn!("Failed to join the mesh of a fanout topic: {:?}", err);
+                }
+            }
+        }
+
         Ok(msg_id)
     }
 
-    /// This function should be called when [`Config::validate_messages()`] is `true` after
-    /// the message got validated by the caller. Messages are stored in the ['Memcache'] and
-    /// validation is expected to be fast enough that the messages should still exist in the cache.
-    /// There are three possible validation outcomes and the outcome is given in acceptance.
-    ///
-    /// If acceptance = [`MessageAcceptance::Accept`] the message will get propagated to the
-    /// network. The `propagation_source` parameter indicates who the message was received by and
-    /// will not be forwarded back to that peer.
-    ///
-    /// If acceptance = [`MessageAcceptance::Reject`] the message will be deleted from the memcache
-    /// and the P₄ penalty will be applied to the `propagation_source`.
-    //
-    /// If acceptance = [`MessageAcceptance::Ignore`] the message will be deleted from the memcache
-    /// but no P₄ penalty will be applied.
-    ///
-    /// This function will return true if the message was found in the cache and false if was not
-    /// in the cache anymore.
+    /// Checks whether publishing the data on the topic would succeed, without publishing it.
     ///
-    /// This should only be called once per message.
-    pub fn report_message_validation_result(
-        &mut self,
-        msg_id: &MessageId,
+    /// Returns the error [`Self::publish()`] would return if the message exceeds the maximum
+    /// transmit size, was already published, or there are no peers to send it to. Messages
+    /// carrying a sequence number are never considered duplicates.
+    pub fn publish_preflight(
+        &self,
+        topic: impl Into<TopicHash>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<(), PublishError> {
+        self.publish_preflight_with_options(topic, data, PublishOptions::default())
+    }
+
+    /// Checks whether publishing the data on the topic with the given options would succeed,
+    /// without publishing it. See [`Self::publish_preflight()`].
+    pub fn publish_preflight_with_options(
+        &self,
+        topic: impl Into<TopicHash>,
+        data: impl Into<Vec<u8>>,
+        options: PublishOptions,
+    ) -> Result<(), PublishError> {
+        let topic_hash = topic.into();
+        let flood_publish = options.flood.unwrap_or_else(|| self.config.flood_publish());
+
+        // The sequence number value does not change the message size, use a placeholder
+        let sequence_number = self.message_seqno_generator.as_ref().map(|_| 0);
+        let (msg_id, _, _) = self.build_message(&topic_hash, data.into(), sequence_number)?;
+
+        if sequence_number.is_none() && self.duplicate_cache.contains(&msg_id) {
+            if !self.config.republish_duplicates() {
+                return Err(PublishError::Duplicate);
+            }
+
+            if self.select_republish_peers(&topic_hash).is_empty() {
+                return Err(PublishError::InsufficientPeers);
+            }
+            return Ok(());
+        }
+
+        if self
+            .select_publish_peers(&topic_hash, flood_publish)
+            .is_empty()
+        {
+            return Err(PublishError::InsufficientPeers);
+        }
+
+        Ok(())
+    }
+
+    /// Selects the peers a message published on the topic is sent to, besides the topic mesh
+    /// peers it is forwarded to.
+    fn select_publish_peers(&self, topic_hash: &TopicHash, flood_publish: bool) -> PublishPeers {
+        let above_publish_threshold = |peer: &PeerId| {
+            !self
+                .peer_score
+                .score_below_threshold(peer, |ts| ts.publish_threshold)
+                .0
+        };
+
+        let mut peers = PublishPeers {
+            recipients: HashSet::new(),
+            forward_to_mesh: !flood_publish
+                && self
+                    .mesh
+                    .get(topic_hash)
+                    .is_some_and(|peers| !peers.is_empty()),
+            via_fanout: false,
+            fanout_candidates: None,
+        };
+
+        let topic_peers = match self.topic_peers.get(topic_hash) {
+            Some(topic_peers) => topic_peers,
+            None => return peers,
+        };
+
+        if flood_publish {
+            // Forward to all peers above score and all explicit peers
+            peers.recipients.extend(
+                topic_peers
+                    .iter()
+                    .filter(|p| self.explicit_peers.contains(*p) || above_publish_threshold(p))
+                    .cloned(),
+            );
+            return peers;
+        }
+
+        // Explicit peers
+        peers.recipients.extend(
+            self.explicit_peers
+                .iter()
+                .filter(|p| topic_peers.contains(*p))
+                .cloned(),
+        );
+
+        // Floodsub peers
+        peers.recipients.extend(
+            self.connected_peers
+                .floodsub_peers()
+                .filter(|p| above_publish_threshold(p))
+                .cloned(),
+        );
+
+        // Gossipsub peers
+        if self.mesh.get(topic_hash).is_none() {
+            debug!("Topic: {:?} not in the mesh", topic_hash);
+            peers.via_fanout = true;
+
+            match self.fanout.get(topic_hash) {
+                // If we have fanout peers add them to the map.
+                Some(fanout_peers) => peers.recipients.extend(fanout_peers.iter().cloned()),
+                None => {
+                    peers.fanout_candidates = Some(
+                        topic_peers
+                            .iter()
+                            .filter(|p| {
+                                !self.explicit_peers.contains(*p)
+                                    && above_publish_threshold(p)
+                                    && self
+                                        .connected_peers
+                                        .kind(p)
+                                        .is_some_and(|k| k.is_gossipsub())
+                            })
+                            .cloned()
+                            .collect(),
+                    );
+                }
+            }
+        }
+
+        peers
+    }
+
+    /// Selects the peers an already published message on the topic is re-sent to.
+    fn select_republish_peers(&self, topic_hash: &TopicHash) -> BTreeSet<PeerId> {
+        self.mesh
+            .get(topic_hash)
+            .or_else(|| self.fanout.get(topic_hash))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Publishes a message to the given peers only, bypassing the mesh and fanout peer selection.
+    ///
+    /// Peers that are not connected or not subscribed to the topic are skipped. The message is not
+    /// added to the memcache, so it is never gossiped to, nor retrievable by, other peers.
+    pub fn publish_to_peers(
+        &mut self,
+        topic: impl Into<TopicHash>,
+        data: impl Into<Vec<u8>>,
+        peers: &[PeerId],
+    ) -> Result<MessageId, PublishError> {
+        let topic_hash = topic.into();
+        let (msg_id, _, event) = self.build_publish_message(&topic_hash, data.into())?;
+
+        // Check the if the message has been published before
+        if self.duplicate_cache.contains(&msg_id) {
+            warn!(
+                "Not publishing a message that has already been published. Msg-id {}",
+                msg_id
+            );
+            return Err(PublishError::Duplicate);
+        }
+
+        trace!("Publishing message to peers: {:?}", msg_id);
+
+        let recipient_peers = match self.topic_peers.get(&topic_hash) {
+            Some(set) => peers
+                .iter()
+                .filter(|p| set.contains(p))
+                .cloned()
+                .collect::<HashSet<_>>(),
+            None => HashSet::new(),
+        };
+
+        if recipient_peers.is_empty() {
+            return Err(PublishError::InsufficientPeers);
+        }
+
+        self.track_published_message_id(&msg_id);
+
+        let msg_bytes = event.encoded_len();
+        for peer_id in recipient_peers.iter() {
+            trace!("Sending message to peer: {:?}", peer_id);
+            self.send_rpc_message(*peer_id, event.clone())?;
+            self.metrics.msg_sent(&topic_hash, msg_bytes);
+        }
+
+        debug!("Published message to peers: {:?}", &msg_id);
+        self.metrics.register_published_message(&topic_hash);
+
+        Ok(msg_id)
+    }
+
+    /// Re-sends an already published message to the topic's mesh peers, or to its fanout peers if
+    /// we are not subscribed to the topic.
+    fn republish_message(
+        &mut self,
+        topic_hash: &TopicHash,
+        msg_id: MessageId,
+        event: RpcProto,
+    ) -> Result<MessageId, PublishError> {
+        let recipient_peers = self.select_republish_peers(topic_hash);
+
+        if recipient_peers.is_empty() {
+            return Err(PublishError::InsufficientPeers);
+        }
+
+        let msg_bytes = event.encoded_len();
+        for peer_id in recipient_peers {
+            trace!("Re-sending message to peer: {:?}", peer_id);
+            self.send_rpc_message(peer_id, event.clone())?;
+            self.metrics.msg_sent(topic_hash, msg_bytes);
+        }
+
+        debug!("Re-published message: {:?}", &msg_id);
+
+        Ok(msg_id)
+    }
+
+    /// Builds and signs a new message for publishing, checking its size.
+    fn build_publish_message(
+        &mut self,
+        topic_hash: &TopicHash,
+        raw_data: Vec<u8>,
+    ) -> Result<(MessageId, RawMessage, RpcProto), PublishError> {
+        let sequence_number: Option<u64> =
+            self.message_seqno_generator.as_mut().map(|gen| gen.next());
+
+        self.build_message(topic_hash, raw_data, sequence_number)
+    }
+
+    /// Builds and signs a message with the given sequence number.
+    fn build_message(
+        &self,
+        topic_hash: &TopicHash,
+        raw_data: Vec<u8>,
+        sequence_number: Option<u64>,
+    ) -> Result<(MessageId, RawMessage, RpcProto), PublishError> {
+        // Transform the data before building a raw_message.
+        let transformed_data = self
+            .data_transform
+            .outbound_transform(topic_hash, raw_data.clone())?;
+
+        let mut message = MessageRpc::new_with_sequence_number(
+            topic_hash.clone(),
+            transformed_data,
+            sequence_number,
+        );
+        self.message_signer.sign(&mut message)?;
+
+        // calculate the message id from the un-transformed data
+        let msg_id = self.config.message_id(&Message {
+            source: message.source(),
+            data: raw_data,
+            sequence_number,
+            topic: topic_hash.clone(),
+        });
+
+        let raw_message: RawMessage = message.into();
+        let event: RpcProto = Rpc {
+            subscriptions: Vec::new(),
+            messages: vec![raw_message.clone()],
+            control_msgs: Vec::new(),
+        }
+        .into();
+
+        // check that the size doesn't exceed the max transmission size
+        if event.encoded_len() > self.config.max_transmit_size() {
+            return Err(PublishError::MessageTooLarge);
+        }
+
+        Ok((msg_id, raw_message, event))
+    }
+
+    /// Adds a published message to the duplicate cache and memcache.
+    fn cache_published_message(&mut self, msg_id: &MessageId, raw_message: RawMessage) {
+        self.track_published_message_id(msg_id);
+
+        let cached_message = CachedMessage {
+            source: raw_message.source,
+            data: raw_message.data,
+            sequence_number: raw_message.sequence_number,
+            topic: raw_message.topic,
+            signature: raw_message.signature,
+            key: raw_message.key,
+            validated: true, // all published messages are valid
+            first_seen: Instant::now(),
+        };
+        self.mcache.put(msg_id, cached_message);
+    }
+
+    /// Records the id of a message published by us, so that its echoes are dropped.
+    fn track_published_message_id(&mut self, msg_id: &MessageId) {
+        self.duplicate_cache.insert(msg_id.clone());
+
+        // If the message is anonymous or has a random author add it to the published message IDs
+        // cache.
+        if self.message_signer.author().is_none() && !self.config.allow_self_origin() {
+            self.published_message_ids.insert(msg_id.clone());
+        }
+    }
+
+    /// This function should be called when [`Config::validate_messages()`] is `true` after
+    /// the message got validated by the caller. Messages are stored in the ['Memcache'] and
+    /// validation is expected to be fast enough that the messages should still exist in the cache.
+    /// There are three possible validation outcomes and the outcome is given in acceptance.
+    ///
+    /// If acceptance = [`MessageAcceptance::Accept`] the message will get propagated to the
+    /// network. The `propagation_source` parameter indicates who the message was received by and
+    /// will not be forwarded back to that peer.
+    ///
+    /// If acceptance = [`MessageAcceptance::Reject`] the message will be deleted from the memcache
+    /// and the P₄ penalty will be applied to the `propagation_source`.
+    //
+    /// If acceptance = [`MessageAcceptance::Ignore`] the message will be deleted from the memcache
+    /// but no P₄ penalty will be applied.
+    ///
+    /// This function will return true if the message was found in the cache and false if was not
+    /// in the cache anymore.
+    ///
+    /// This should only be called once per message.
+    pub fn report_message_validation_result(
+        &mut self,
+        msg_id: &MessageId,
         propagation_source: &PeerId,
         acceptance: MessageAcceptance,
     ) -> Result<bool, PublishError> {
@@ -861,13 +1514,21 @@ where
 
                 self.metrics
                     .register_msg_validation(&raw_message.topic, &acceptance);
+                let latency =
+                    u64::try_from(raw_message.first_seen.elapsed().as_millis()).unwrap_or(u64::MAX);
+                self.metrics.observe_validation_latency(latency);
 
-                self.forward_msg(
+                let topic = raw_message.topic.clone();
+                let first_seen = raw_message.first_seen;
+                if self.forward_msg(
                     msg_id,
                     raw_message.into(),
                     Some(propagation_source),
                     originating_peers,
-                )?;
+                )? {
+                    self.metrics
+                        .observe_forward_latency(&topic, first_seen.elapsed());
+                }
                 return Ok(true);
             }
             MessageAcceptance::Reject => RejectReason::ValidationFailed,
@@ -877,6 +1538,9 @@ where
         if let Some((raw_message, originating_peers)) = self.mcache.remove(msg_id) {
             self.metrics
                 .register_msg_validation(&raw_message.topic, &acceptance);
+            let latency =
+                u64::try_from(raw_message.first_seen.elapsed().as_millis()).unwrap_or(u64::MAX);
+            self.metrics.observe_validation_latency(latency);
 
             // Tell peer_score about reject
             // Reject the original source, and any duplicates we've seen from other peers.
@@ -910,11 +1574,17 @@ where
         self.check_explicit_peer_connection(peer_id);
     }
 
+    /// Whether the peer is in the list of explicitly connected peers.
+    pub fn is_explicit_peer(&self, peer_id: &PeerId) -> bool {
+        self.explicit_peers.contains(peer_id)
+    }
+
     /// This removes the peer from explicitly connected peers, note that this does not disconnect
     /// the peer.
     pub fn remove_explicit_peer(&mut self, peer_id: &PeerId) {
         debug!("Removing explicit peer {}", peer_id);
         self.explicit_peers.remove(peer_id);
+        self.explicit_peer_backoffs.remove(peer_id);
     }
 
     /// Blacklists a peer. All messages from this peer will be rejected and any message that was
@@ -932,6 +1602,121 @@ where
         }
     }
 
+    /// Blacklists several peers at once. See [`Self::blacklist_peer()`].
+    pub fn blacklist_peers(&mut self, peer_ids: &[PeerId]) {
+        for peer_id in peer_ids {
+            self.blacklist_peer(peer_id);
+        }
+    }
+
+    /// Removes several peers from the blacklist at once.
+    pub fn remove_blacklisted_peers(&mut self, peer_ids: &[PeerId]) {
+        for peer_id in peer_ids {
+            self.remove_blacklisted_peer(peer_id);
+        }
+    }
+
+    /// Replaces the whole blacklist with the given peers.
+    ///
+    /// The newly blacklisted peers that are connected are disconnected.
+    pub fn set_blacklist(&mut self, peer_ids: HashSet<PeerId>) {
+        let newly_blacklisted = peer_ids
+            .difference(&self.blacklisted_peers)
+            .filter(|peer_id| self.connected_peers.kind(peer_id).is_some())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        debug!("Blacklist has been replaced: {} peers", peer_ids.len());
+        self.blacklisted_peers = peer_ids;
+
+        for peer_id in newly_blacklisted {
+            self.disconnect_peer(&peer_id, false);
+        }
+    }
+
+    /// Disconnects a peer.
+    ///
+    /// If `drain` is set, the control actions pooled for the peer are sent right away and the
+    /// connection is only closed after the configured
+    /// [`Config::disconnect_drain_time()`], so the peer is not left with a stale mesh view.
+    /// The connection is closed on a heartbeat, so the drain lasts at least a heartbeat interval.
+    /// Draining an already draining peer does not extend its drain time. Otherwise, the
+    /// connection is closed immediately.
+    pub fn disconnect_peer(&mut self, peer_id: &PeerId, drain: bool) {
+        if !drain {
+            debug!("Disconnecting peer: {}", peer_id);
+            self.control_pool.remove(peer_id);
+            self.events.push_back(ToSwarm::CloseConnection {
+                peer_id: *peer_id,
+                connection: CloseConnection::All,
+            });
+            return;
+        }
+
+        debug!("Draining peer before disconnecting: {}", peer_id);
+        if let Some(controls) = self.control_pool.remove(peer_id).map(Vec::from) {
+            if self.send_control_rpc_message(*peer_id, controls).is_err() {
+                error!("Failed to drain control pool. Message too large");
+            }
+        }
+        let drain_time = self.config.disconnect_drain_time();
+        self.draining_peers
+            .entry(*peer_id)
+            .or_insert_with(|| Instant::now() + drain_time);
+    }
+
+    /// Whether the peer is being drained before disconnecting it.
+    pub fn is_draining_peer(&self, peer_id: &PeerId) -> bool {
+        self.draining_peers.contains_key(peer_id)
+    }
+
+    /// Removes the peer from the topic mesh without blacklisting it, sending it a PRUNE and
+    /// applying the prune backoff.
+    ///
+    /// Returns `false` if the peer was not in the topic mesh.
+    pub fn prune_peer(&mut self, peer_id: &PeerId, topic: &TopicHash) -> bool {
+        let in_mesh = self
+            .mesh
+            .get(topic)
+            .map_or(false, |peers| peers.contains(peer_id));
+        if !in_mesh {
+            return false;
+        }
+
+        debug!("Manually pruning peer {} from topic: {}", peer_id, topic);
+        self.remove_peer_from_mesh(peer_id, topic, None, true, Churn::Manual);
+
+        let on_unsubscribe = false;
+        let control = self.make_prune(topic, peer_id, self.config.do_px(), on_unsubscribe);
+        Self::control_pool_add(
+            &mut self.control_pool,
+            self.config.max_control_pool_length(),
+            &mut self.metrics,
+            *peer_id,
+            control,
+        );
+
+        true
+    }
+
+    /// Lists the explicitly connected peers.
+    pub fn explicit_peers(&self) -> Vec<PeerId> {
+        self.explicit_peers.iter().cloned().collect()
+    }
+
+    /// Lists the blacklisted peers.
+    pub fn blacklisted_peers(&self) -> Vec<PeerId> {
+        self.blacklisted_peers.iter().cloned().collect()
+    }
+
+    /// Seeds the random number generator used for mesh peer selection and gossip emission.
+    ///
+    /// By default, the thread-local random number generator is used. Setting a fixed seed makes
+    /// the peer selection reproducible, e.g., in tests.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng = Box::new(StdRng::seed_from_u64(seed));
+    }
+
     /// Activates the peer scoring system with the given parameters. This will reset all scores
     /// if there was already another peer scoring system activated. Returns an error if the
     /// params are not valid or if they got already set.
@@ -962,6 +1747,16 @@ where
         Ok(())
     }
 
+    /// Sets the policy deciding whether connections with peers are accepted. Connections denied by
+    /// the policy are closed before the peer enters the gossipsub state. By default, all
+    /// connections are accepted.
+    pub fn set_peer_acceptance_policy<P>(&mut self, policy: P)
+    where
+        P: PeerAcceptancePolicy + Send + 'static,
+    {
+        self.peer_acceptance_policy = Box::new(policy);
+    }
+
     /// Sets scoring parameters for a topic.
     ///
     /// The [`Self::with_peer_score()`] must first be called to initialise peer scoring.
@@ -982,6 +1777,24 @@ where
             .peer_score_set_application_score(peer_id, new_score)
     }
 
+    /// Sets the validation mode of the messages received on a topic, overriding the configured
+    /// [`ValidationMode`] for that topic.
+    ///
+    /// Fails if the validation mode would reject the messages published with the configured
+    /// [`MessageAuthenticity`], e.g., a strict validation mode if message signing is disabled.
+    pub fn set_topic_validation_mode(
+        &mut self,
+        topic: TopicHash,
+        mode: ValidationMode,
+    ) -> Result<(), &'static str> {
+        validate_config(&self.privacy, &mode)?;
+
+        let message_validator = new_message_validator(&self.config, &mode);
+        self.topic_message_validators
+            .insert(topic, message_validator);
+        Ok(())
+    }
+
     /// Gossipsub JOIN(topic) - adds topic peers to mesh and sends them GRAFT messages.
     fn join(&mut self, topic_hash: &TopicHash) {
         debug!("Running JOIN for topic: {:?}", topic_hash);
@@ -1022,6 +1835,7 @@ where
             // remove the last published time
             self.fanout_last_pub.remove(topic_hash);
         }
+        self.fanout_publish_count.remove(topic_hash);
 
         let fanout_added = added_peers.len();
         self.metrics
@@ -1041,6 +1855,7 @@ where
                         && !self.peer_score.score_below_threshold(peer, |_| 0.0).0
                         && !self.backoffs.is_backoff_with_slack(topic_hash, peer)
                 },
+                &mut self.rng,
             );
             added_peers.extend(new_peers.clone());
             // add them to the mesh
@@ -1065,6 +1880,8 @@ where
             self.peer_score.peer_score_graft(&peer_id, topic_hash);
             Self::control_pool_add(
                 &mut self.control_pool,
+                self.config.max_control_pool_length(),
+                &mut self.metrics,
                 peer_id,
                 ControlAction::Graft {
                     topic_hash: topic_hash.clone(),
@@ -1124,9 +1941,13 @@ where
                 topic_hash,
                 self.config.prune_peers(),
                 |p| p != peer && !self.peer_score.score_below_threshold(p, |_| 0.0).0,
+                &mut self.rng,
             )
             .into_iter()
-            .map(|p| PeerInfo { peer_id: Some(p) })
+            .map(|p| PeerInfo {
+                peer_id: Some(p),
+                addresses: Vec::new(),
+            })
             .collect()
         } else {
             Vec::new()
@@ -1161,7 +1982,13 @@ where
                 let on_unsubscribe = true;
                 let control =
                     self.make_prune(topic_hash, &peer, self.config.do_px(), on_unsubscribe);
-                Self::control_pool_add(&mut self.control_pool, peer, control);
+                Self::control_pool_add(
+                    &mut self.control_pool,
+                    self.config.max_control_pool_length(),
+                    &mut self.metrics,
+                    peer,
+                    control,
+                );
 
                 // If the peer did not previously exist in any mesh, inform the handler
                 peer_removed_from_mesh(
@@ -1179,8 +2006,30 @@ where
     }
 
     /// Checks if the given peer is still connected and if not dials the peer again.
+    ///
+    /// Reconnection attempts are exponentially backed off, starting at the heartbeat interval and
+    /// capped at the configured explicit peer maximum backoff.
     fn check_explicit_peer_connection(&mut self, peer_id: &PeerId) {
         if !self.peer_topics.contains_key(peer_id) {
+            let now = Instant::now();
+            let max_backoff = self.config.explicit_peer_max_backoff();
+            match self.explicit_peer_backoffs.entry(*peer_id) {
+                Entry::Occupied(mut entry) => {
+                    let (next_dial, backoff) = entry.get_mut();
+                    if now < *next_dial {
+                        trace!("Explicit peer {:?} reconnection backed off", peer_id);
+                        return;
+                    }
+
+                    *backoff = min(*backoff * 2, max_backoff);
+                    *next_dial = now + *backoff;
+                }
+                Entry::Vacant(entry) => {
+                    let backoff = min(self.config.heartbeat_interval(), max_backoff);
+                    entry.insert((now + backoff, backoff));
+                }
+            }
+
             // Connect to peer
             debug!("Connecting to explicit peer {:?}", peer_id);
             self.events.push_back(ToSwarm::Dial {
@@ -1235,7 +2084,11 @@ where
             }
 
             let message: MessageRpc = message.into();
-            if let Err(err) = self.message_validator.validate(&message) {
+            let message_validator = self
+                .topic_message_validators
+                .get(&TopicHash::from_raw(message.topic()))
+                .unwrap_or(&self.message_validator);
+            if let Err(err) = message_validator.validate(&message) {
                 // If the message is invalid, add it to the invalid messages and continue
                 // processing the other messages.
                 // TODO: Review this logic, together with invalid messages peer scoring.
@@ -1285,21 +2138,60 @@ where
             }
         }
 
-        for (count, raw_message) in valid_messages.into_iter().enumerate() {
-            // Only process the amount of messages the configuration allows.
-            if self.config.max_messages_per_rpc().is_some()
-                && Some(count) >= self.config.max_messages_per_rpc()
-            {
-                warn!("Received more messages than permitted. Ignoring further messages. Processed: {}", count);
-                break;
+        // Only process the amount of messages the configuration allows, and penalize the peer for
+        // oversending.
+        if let Some(max) = self.config.max_messages_per_rpc() {
+            if valid_messages.len() > max {
+                let dropped = valid_messages.len() - max;
+                warn!(
+                    "Received more messages than permitted from peer {}. Ignoring further messages. Processed: {}, dropped: {}",
+                    propagation_source, max, dropped
+                );
+                valid_messages.truncate(max);
+
+                self.peer_score
+                    .peer_score_add_penalty(propagation_source, 1);
+                self.events
+                    .push_back(ToSwarm::GenerateEvent(Event::RpcTruncated {
+                        peer_id: *propagation_source,
+                        dropped,
+                    }));
             }
+        }
+
+        for raw_message in valid_messages {
             self.handle_received_message(raw_message, propagation_source);
         }
 
         // Handle control messages
-        if let Some(rpc_control) = rpc.control {
-            for iwant in rpc_control.iwant {
-                let iwant_msgs = iwant.message_ids.into_iter().map(Into::into).collect();
+        if let Some(mut rpc_control) = rpc.control {
+            // Only process the amount of control entries the configuration allows, and penalize
+            // the peer once per exceeded control message type.
+            let exceeded_limits = [
+                truncate_to_limit(&mut rpc_control.ihave, self.config.max_ihave_per_rpc()),
+                truncate_to_limit(&mut rpc_control.iwant, self.config.max_iwant_per_rpc()),
+                truncate_to_limit(&mut rpc_control.graft, self.config.max_graft_per_rpc()),
+                truncate_to_limit(&mut rpc_control.prune, self.config.max_prune_per_rpc()),
+            ]
+            .into_iter()
+            .filter(|exceeded| *exceeded)
+            .count();
+            if exceeded_limits > 0 {
+                warn!(
+                    "Received more control entries than permitted from peer {}. Ignoring further entries.",
+                    propagation_source
+                );
+                self.peer_score
+                    .peer_score_add_penalty(propagation_source, exceeded_limits);
+            }
+
+            let iwant_msgs = rpc_control
+                .iwant
+                .into_iter()
+                .flat_map(|iwant| iwant.message_ids)
+                .map(Into::into)
+                .collect::<Vec<_>>();
+            if !iwant_msgs.is_empty() {
                 self.handle_iwant(propagation_source, iwant_msgs);
             }
 
@@ -1435,8 +2327,7 @@ where
 
             // Ask in random order
             let mut iwant_ids_vec: Vec<_> = iwant_ids.into_iter().collect();
-            let mut rng = thread_rng();
-            iwant_ids_vec.partial_shuffle(&mut rng, iask);
+            iwant_ids_vec.partial_shuffle(&mut self.rng, iask);
 
             iwant_ids_vec.truncate(iask);
             *iasked += iask;
@@ -1459,6 +2350,8 @@ where
 
             Self::control_pool_add(
                 &mut self.control_pool,
+                self.config.max_control_pool_length(),
+                &mut self.metrics,
                 *peer_id,
                 ControlAction::IWant {
                     message_ids: iwant_ids_vec,
@@ -1468,9 +2361,9 @@ where
         trace!("Completed IHAVE handling for peer: {:?}", peer_id);
     }
 
-    /// Handles an IWANT control message. Checks our cache of messages. If the message exists it is
-    /// forwarded to the requesting peer.
-    fn handle_iwant(&mut self, peer_id: &PeerId, iwant_msgs: Vec<MessageId>) {
+    /// Handles the IWANT control messages of an RPC. Checks our cache of messages. If the message
+    /// exists it is forwarded to the requesting peer.
+    fn handle_iwant(&mut self, peer_id: &PeerId, mut iwant_msgs: Vec<MessageId>) {
         // We ignore IWANT gossip from any peer whose score is below the gossip threshold
         if let (true, score) = self
             .peer_score
@@ -1484,6 +2377,17 @@ where
         }
 
         debug!("Handling IWANT for peer: {:?}", peer_id);
+
+        // Only answer the amount of requested messages the configuration allows, across all the
+        // IWANT entries of the RPC
+        if truncate_to_limit(&mut iwant_msgs, self.config.max_iwant_response_messages()) {
+            warn!(
+                "IWANT: Peer {} requested more messages than permitted; ignoring the excess",
+                peer_id
+            );
+            self.peer_score.peer_score_add_penalty(peer_id, 1);
+        }
+
         // build a hashmap of available messages
         let mut cached_messages = HashMap::new();
 
@@ -1762,12 +2666,9 @@ where
                         continue;
                     }
 
-                    // NOTE: We cannot dial any peers from PX currently as we typically will not
-                    // know their multiaddr. Until SignedRecords are spec'd this
-                    // remains a stub. By default `config.prune_peers()` is set to zero and
-                    // this is skipped. If the user modifies this, this will only be able to
-                    // dial already known peers (from an external discovery mechanism for
-                    // example).
+                    // NOTE: PX peers are dialed at the addresses of their signed peer record.
+                    // Peers without a signed peer record can only be dialed if already known
+                    // (from an external discovery mechanism for example).
                     if self.config.prune_peers() > 0 {
                         self.px_connect(px);
                     }
@@ -1786,22 +2687,22 @@ where
         px.retain(|p| p.peer_id.is_some());
         if px.len() > n {
             // only use at most prune_peers many random peers
-            let mut rng = thread_rng();
-            px.partial_shuffle(&mut rng, n);
+            px.partial_shuffle(&mut self.rng, n);
             px = px.into_iter().take(n).collect();
         }
 
         for p in px {
-            // TODO: Once signed records are spec'd: extract signed peer record if given and handle
-            // it, see https://github.com/libp2p/specs/pull/217
             if let Some(peer_id) = p.peer_id {
                 // mark as px peer
                 self.px_peers.insert(peer_id);
 
-                // dial peer
-                self.events.push_back(ToSwarm::Dial {
-                    opts: DialOpts::peer_id(peer_id).build(),
-                });
+                // dial peer, at the signed peer record addresses if any
+                let opts = if p.addresses.is_empty() {
+                    DialOpts::peer_id(peer_id).build()
+                } else {
+                    DialOpts::peer_id(peer_id).addresses(p.addresses).build()
+                };
+                self.events.push_back(ToSwarm::Dial { opts });
             }
         }
     }
@@ -1882,9 +2783,14 @@ where
         mut raw_message: RawMessage,
         propagation_source: &PeerId,
     ) {
+        let received_at = Instant::now();
+
         // Record the received metric
         self.metrics
             .msg_recvd_unfiltered(&raw_message.topic, raw_message.raw_protobuf_len());
+        if let Some(len) = self.data_transform.transformed_len(&raw_message.data) {
+            self.metrics.msg_decompressed_bytes(&raw_message.topic, len);
+        }
 
         let fast_message_id = self.config.fast_message_id(&raw_message);
 
@@ -1981,6 +2887,7 @@ where
                 // This will allow the message to be gossiped without explicitly calling
                 // `validate_message`.
                 validated: !self.config.validate_messages(),
+                first_seen: received_at,
             }
         };
         self.mcache.put(&msg_id, cached_message);
@@ -2004,16 +2911,19 @@ where
 
         // forward the message to mesh peers, if no validation is required
         if !self.config.validate_messages() {
-            if self
-                .forward_msg(
-                    &msg_id,
-                    raw_message,
-                    Some(propagation_source),
-                    HashSet::new(),
-                )
-                .is_err()
-            {
-                error!("Failed to forward message. Too large");
+            let topic = raw_message.topic.clone();
+            match self.forward_msg(
+                &msg_id,
+                raw_message,
+                Some(propagation_source),
+                HashSet::new(),
+            ) {
+                Ok(true) => {
+                    self.metrics
+                        .observe_forward_latency(&topic, received_at.elapsed());
+                }
+                Ok(false) => {}
+                Err(_) => error!("Failed to forward message. Too large"),
             }
             debug!("Completed message handling for message: {:?}", msg_id);
         }
@@ -2240,6 +3150,41 @@ where
         let mut to_prune = HashMap::new();
         let mut no_px = HashSet::new();
 
+        // close the connections of the drained peers
+        let now = Instant::now();
+        let drained_peers = self
+            .draining_peers
+            .iter()
+            .filter(|(_, close_at)| **close_at <= now)
+            .map(|(peer_id, _)| *peer_id)
+            .collect::<Vec<_>>();
+        for peer_id in drained_peers {
+            debug!("Disconnecting drained peer: {}", peer_id);
+            self.draining_peers.remove(&peer_id);
+            self.events.push_back(ToSwarm::CloseConnection {
+                peer_id,
+                connection: CloseConnection::All,
+            });
+        }
+
+        // count the peer exchange peers past their probation period as outbound peers
+        if let Some(probation) = self.config.px_outbound_probation() {
+            let trusted_peers = self
+                .px_outbound_probation
+                .iter()
+                .filter(|(_, connected_at)| **connected_at + probation <= now)
+                .map(|(peer_id, _)| *peer_id)
+                .collect::<Vec<_>>();
+            for peer_id in trusted_peers {
+                debug!(
+                    "Peer exchange peer passed the outbound probation: {}",
+                    peer_id
+                );
+                self.px_outbound_probation.remove(&peer_id);
+                self.connected_peers.set_outbound(&peer_id);
+            }
+        }
+
         // clean up expired backoffs
         self.backoffs.heartbeat();
 
@@ -2269,6 +3214,12 @@ where
             });
         }
 
+        // Track the peers crossing the graylist threshold
+        self.update_graylisted_peers();
+
+        // Opportunistic grafting is only enabled if peer scoring is enabled
+        let opportunistic_graft_threshold = self.peer_score.opportunistic_graft_threshold();
+
         // maintain the mesh for each topic
         for (topic_hash, peers) in self.mesh.iter_mut() {
             let explicit_peers = &self.explicit_peers;
@@ -2328,6 +3279,7 @@ where
                             && !backoffs.is_backoff_with_slack(topic_hash, peer)
                             && *scores.get(peer).unwrap_or(&0.0) >= 0.0
                     },
+                    &mut self.rng,
                 );
                 for peer in &peer_list {
                     let current_topic = to_graft.entry(*peer).or_insert_with(Vec::new);
@@ -2351,9 +3303,8 @@ where
                 let excess_peer_no = peers.len() - self.config.mesh_n();
 
                 // shuffle the peers and then sort by score ascending beginning with the worst
-                let mut rng = thread_rng();
                 let mut shuffled = peers.iter().cloned().collect::<Vec<_>>();
-                shuffled.shuffle(&mut rng);
+                shuffled.shuffle(&mut self.rng);
                 shuffled.sort_by(|p1, p2| {
                     let score_p1 = *scores.get(p1).unwrap_or(&0.0);
                     let score_p2 = *scores.get(p2).unwrap_or(&0.0);
@@ -2361,7 +3312,7 @@ where
                     score_p1.partial_cmp(&score_p2).unwrap_or(Ordering::Equal)
                 });
                 // shuffle everything except the last retain_scores many peers (the best ones)
-                shuffled[..peers.len() - self.config.retain_scores()].shuffle(&mut rng);
+                shuffled[..peers.len() - self.config.retain_scores()].shuffle(&mut self.rng);
 
                 // count total number of outbound peers
                 let mut outbound = {
@@ -2424,6 +3375,7 @@ where
                                 && *scores.get(peer).unwrap_or(&0.0) >= 0.0
                                 && self.connected_peers.is_outbound(peer)
                         },
+                        &mut self.rng,
                     );
                     for peer in &peer_list {
                         let current_topic = to_graft.entry(*peer).or_insert_with(Vec::new);
@@ -2437,83 +3389,88 @@ where
                 }
             }
 
-            // TODO: Review this opportunistic grafting section
-            // // should we try to improve the mesh with opportunistic grafting?
-            // if self.heartbeat_ticks % self.config.opportunistic_graft_ticks() == 0
-            //     && peers.len() > 1
-            //     && self.peer_score.is_some()
-            // {
-            //     if let Some((_, thresholds, _, _)) = &self.peer_score {
-            //         // Opportunistic grafting works as follows: we check the median score of peers
-            //         // in the mesh; if this score is below the opportunisticGraftThreshold, we
-            //         // select a few peers at random with score over the median.
-            //         // The intention is to (slowly) improve an underperforming mesh by introducing
-            //         // good scoring peers that may have been gossiping at us. This allows us to
-            //         // get out of sticky situations where we are stuck with poor peers and also
-            //         // recover from churn of good peers.
-            //
-            //         // now compute the median peer score in the mesh
-            //         let mut peers_by_score: Vec<_> = peers.iter().collect();
-            //         peers_by_score.sort_by(|p1, p2| {
-            //             let p1_score = *scores.get(p1).unwrap_or(&0.0);
-            //             let p2_score = *scores.get(p2).unwrap_or(&0.0);
-            //             p1_score.partial_cmp(&p2_score).unwrap_or(Ordering::Equal)
-            //         });
-            //
-            //         let middle = peers_by_score.len() / 2;
-            //         let median = if peers_by_score.len() % 2 == 0 {
-            //             let sub_middle_peer = *peers_by_score
-            //                 .get(middle - 1)
-            //                 .expect("middle < vector length and middle > 0 since peers.len() > 0");
-            //             let sub_middle_score = *scores.get(sub_middle_peer).unwrap_or(&0.0);
-            //             let middle_peer =
-            //                 *peers_by_score.get(middle).expect("middle < vector length");
-            //             let middle_score = *scores.get(middle_peer).unwrap_or(&0.0);
-            //
-            //             (sub_middle_score + middle_score) * 0.5
-            //         } else {
-            //             *scores
-            //                 .get(*peers_by_score.get(middle).expect("middle < vector length"))
-            //                 .unwrap_or(&0.0)
-            //         };
-            //
-            //         // if the median score is below the threshold, select a better peer (if any) and
-            //         // GRAFT
-            //         if median < thresholds.opportunistic_graft_threshold {
-            //             let peer_list = get_random_peers(
-            //                 topic_peers,
-            //                 &self.connected_peers,
-            //                 topic_hash,
-            //                 self.config.opportunistic_graft_peers(),
-            //                 |peer_id| {
-            //                     !peers.contains(peer_id)
-            //                         && !explicit_peers.contains(peer_id)
-            //                         && !backoffs.is_backoff_with_slack(topic_hash, peer_id)
-            //                         && *scores.get(peer_id).unwrap_or(&0.0) > median
-            //                 },
-            //             );
-            //             for peer in &peer_list {
-            //                 let current_topic = to_graft.entry(*peer).or_insert_with(Vec::new);
-            //                 current_topic.push(topic_hash.clone());
-            //             }
-            //             // update the mesh
-            //             debug!(
-            //                 "Opportunistically graft in topic {} with peers {:?}",
-            //                 topic_hash, peer_list
-            //             );
-            //             self.metrics.peers_included(topic_hash, Inclusion::Random, peer_list.len());
-            //             peers.extend(peer_list);
-            //         }
-            //     }
-            // }
+            // should we try to improve the mesh with opportunistic grafting?
+            if let Some(opportunistic_graft_threshold) = opportunistic_graft_threshold {
+                if heartbeat_ticks % self.config.opportunistic_graft_ticks() == 0 && peers.len() > 1
+                {
+                    // Opportunistic grafting works as follows: we check the median score of peers
+                    // in the mesh; if this score is below the opportunisticGraftThreshold, we
+                    // select a few peers at random with score over the median.
+                    // The intention is to (slowly) improve an underperforming mesh by introducing
+                    // good scoring peers that may have been gossiping at us. This allows us to
+                    // get out of sticky situations where we are stuck with poor peers and also
+                    // recover from churn of good peers.
+
+                    // now compute the median peer score in the mesh
+                    let mut peers_by_score: Vec<_> = peers.iter().collect();
+                    peers_by_score.sort_by(|p1, p2| {
+                        let p1_score = *scores.get(p1).unwrap_or(&0.0);
+                        let p2_score = *scores.get(p2).unwrap_or(&0.0);
+                        p1_score.partial_cmp(&p2_score).unwrap_or(Ordering::Equal)
+                    });
+
+                    let middle = peers_by_score.len() / 2;
+                    let median = if peers_by_score.len() % 2 == 0 {
+                        let sub_middle_peer = *peers_by_score
+                            .get(middle - 1)
+                            .expect("middle < vector length and middle > 0 since peers.len() > 0");
+                        let sub_middle_score = *scores.get(sub_middle_peer).unwrap_or(&0.0);
+                        let middle_peer =
+                            *peers_by_score.get(middle).expect("middle < vector length");
+                        let middle_score = *scores.get(middle_peer).unwrap_or(&0.0);
+
+                        (sub_middle_score + middle_score) * 0.5
+                    } else {
+                        *scores
+                            .get(*peers_by_score.get(middle).expect("middle < vector length"))
+                            .unwrap_or(&0.0)
+                    };
+
+                    // if the median score is below the threshold, select a better peer (if any)
+                    // and GRAFT
+                    if median < opportunistic_graft_threshold {
+                        let peer_list = get_random_peers(
+                            topic_peers,
+                            &self.connected_peers,
+                            topic_hash,
+                            self.config.opportunistic_graft_peers(),
+                            |peer_id| {
+                                !peers.contains(peer_id)
+                                    && !explicit_peers.contains(peer_id)
+                                    && !backoffs.is_backoff_with_slack(topic_hash, peer_id)
+                                    && *scores.get(peer_id).unwrap_or(&0.0) > median
+                            },
+                            &mut self.rng,
+                        );
+                        for peer in &peer_list {
+                            let current_topic = to_graft.entry(*peer).or_insert_with(Vec::new);
+                            current_topic.push(topic_hash.clone());
+                        }
+                        // update the mesh
+                        debug!(
+                            "Opportunistically graft in topic {} with peers {:?}",
+                            topic_hash, peer_list
+                        );
+                        self.metrics
+                            .peers_included(topic_hash, Inclusion::Random, peer_list.len());
+                        peers.extend(peer_list);
+                    }
+                }
+            }
 
             // Register the final count of peers in the mesh
             self.metrics.set_mesh_peers(topic_hash, peers.len())
         }
 
+        // check the mesh topology for possible eclipse attacks
+        if let Some(threshold) = self.config.eclipse_detection_threshold() {
+            self.detect_possible_eclipses(threshold);
+        }
+
         // remove expired fanout topics
         {
             let fanout = &mut self.fanout; // help the borrow checker
+            let fanout_publish_count = &mut self.fanout_publish_count;
             let fanout_ttl = self.config.fanout_ttl();
             self.fanout_last_pub.retain(|topic_hash, last_pub_time| {
                 if *last_pub_time + fanout_ttl < Instant::now() {
@@ -2522,6 +3479,7 @@ where
                         topic_hash
                     );
                     fanout.remove(topic_hash);
+                    fanout_publish_count.remove(topic_hash);
                     return false;
                 }
                 true
@@ -2575,6 +3533,7 @@ where
                             && !explicit_peers.contains(peer_id)
                             && *scores.get(peer_id).unwrap_or(&0.0) < publish_threshold
                     },
+                    &mut self.rng,
                 );
                 peers.extend(new_peers);
             }
@@ -2618,6 +3577,9 @@ where
         // piggyback pooled control messages
         self.flush_control_pool();
 
+        // start a new GRAFT deduplication cycle
+        self.grafts_sent.clear();
+
         // shift the memcache
         self.mcache.shift();
 
@@ -2626,10 +3588,82 @@ where
         self.metrics.observe_heartbeat_duration(duration);
     }
 
+    /// Flags the topic meshes where the fraction of peers sharing the same IP subnet, or learned via
+    /// peer exchange, reaches the given threshold. A [`Event::PossibleEclipse`] event is emitted
+    /// the first time a topic mesh is flagged.
+    fn detect_possible_eclipses(&mut self, threshold: f64) {
+        self.possible_eclipses
+            .retain(|topic_hash| self.mesh.contains_key(topic_hash));
+
+        for (topic_hash, peers) in self.mesh.iter() {
+            // Small meshes are not representative of the network topology
+            if peers.is_empty() || peers.len() < self.config.mesh_n_low() {
+                self.possible_eclipses.remove(topic_hash);
+                continue;
+            }
+
+            let px_peers = peers.iter().filter(|p| self.px_peers.contains(p)).count();
+
+            let mut subnets: HashMap<IpAddr, usize> = HashMap::new();
+            for ip in peers.iter().filter_map(|p| self.peer_ips.get(p)) {
+                *subnets.entry(get_ip_subnet(ip)).or_default() += 1;
+            }
+            let colocated_peers = subnets.values().max().copied().unwrap_or(0);
+
+            let mesh_size = peers.len() as f64;
+            let possible_eclipse = px_peers as f64 / mesh_size >= threshold
+                || colocated_peers as f64 / mesh_size >= threshold;
+
+            if !possible_eclipse {
+                self.possible_eclipses.remove(topic_hash);
+                continue;
+            }
+
+            if self.possible_eclipses.insert(topic_hash.clone()) {
+                warn!(
+                    "Possible eclipse of topic {}: {} px peers, {} colocated peers out of {} mesh peers",
+                    topic_hash,
+                    px_peers,
+                    colocated_peers,
+                    peers.len()
+                );
+                self.events
+                    .push_back(ToSwarm::GenerateEvent(Event::PossibleEclipse {
+                        topic: topic_hash.clone(),
+                    }));
+            }
+        }
+    }
+
+    /// Updates the set of graylisted peers, emitting an event for each peer crossing the graylist
+    /// threshold.
+    fn update_graylisted_peers(&mut self) {
+        for peer_id in self.connected_peers.peers() {
+            let (below_graylist, score) = self
+                .peer_score
+                .score_below_threshold(peer_id, |ts| ts.graylist_threshold);
+
+            if below_graylist {
+                if self.graylisted_peers.insert(*peer_id) {
+                    debug!("Peer {} graylisted [score = {}]", peer_id, score);
+                    self.events
+                        .push_back(ToSwarm::GenerateEvent(Event::PeerGraylisted {
+                            peer_id: *peer_id,
+                        }));
+                }
+            } else if self.graylisted_peers.remove(peer_id) {
+                debug!("Peer {} ungraylisted [score = {}]", peer_id, score);
+                self.events
+                    .push_back(ToSwarm::GenerateEvent(Event::PeerUngraylisted {
+                        peer_id: *peer_id,
+                    }));
+            }
+        }
+    }
+
     /// Emits gossip - Send IHAVE messages to a random set of gossip peers. This is applied to mesh
     /// and fanout peers
     fn emit_gossip(&mut self) {
-        let mut rng = thread_rng();
         for (topic_hash, peers) in self.mesh.iter().chain(self.fanout.iter()) {
             let mut message_ids = self.mcache.get_gossip_message_ids(topic_hash);
             if message_ids.is_empty() {
@@ -2645,7 +3679,7 @@ where
                 );
             } else {
                 // shuffle to emit in random order
-                message_ids.shuffle(&mut rng);
+                message_ids.shuffle(&mut self.rng);
             }
 
             // dynamic number of peers to gossip based on `gossip_factor` with minimum `gossip_lazy`
@@ -2669,6 +3703,7 @@ where
                             .score_below_threshold(peer, |ts| ts.gossip_threshold)
                             .0
                 },
+                &mut self.rng,
             );
 
             debug!("Gossiping IHAVE to {} peers.", to_msg_peers.len());
@@ -2680,13 +3715,15 @@ where
                     // We do this per peer so that we emit a different set for each peer.
                     // we have enough redundancy in the system that this will significantly increase
                     // the message coverage when we do truncate.
-                    peer_message_ids.partial_shuffle(&mut rng, self.config.max_ihave_length());
+                    peer_message_ids.partial_shuffle(&mut self.rng, self.config.max_ihave_length());
                     peer_message_ids.truncate(self.config.max_ihave_length());
                 }
 
                 // send an IHAVE message
                 Self::control_pool_add(
                     &mut self.control_pool,
+                    self.config.max_control_pool_length(),
+                    &mut self.metrics,
                     peer,
                     ControlAction::IHave {
                         topic_hash: topic_hash.clone(),
@@ -2844,6 +3881,21 @@ where
             return Ok(false);
         }
 
+        // If capped, forward the message only to the highest-scored peers
+        let recipient_peers = match self.config.max_forward_peers() {
+            Some(max) if recipient_peers.len() > max => {
+                let mut peers = recipient_peers.into_iter().collect::<Vec<_>>();
+                peers.sort_by(|a, b| {
+                    let score_a = self.peer_score.peer_score(a).unwrap_or_default();
+                    let score_b = self.peer_score.peer_score(b).unwrap_or_default();
+                    score_b.total_cmp(&score_a)
+                });
+                peers.truncate(max);
+                peers.into_iter().collect::<HashSet<_>>()
+            }
+            _ => recipient_peers,
+        };
+
         let event: RpcProto = Rpc {
             subscriptions: Vec::new(),
             messages: vec![message.clone()],
@@ -2861,21 +3913,31 @@ where
         Ok(true)
     }
 
-    // adds a control action to control_pool
+    // adds a control action to control_pool, dropping the oldest pooled action for the peer if
+    // the pool is full
     fn control_pool_add(
-        control_pool: &mut HashMap<PeerId, Vec<ControlAction>>,
+        control_pool: &mut HashMap<PeerId, VecDeque<ControlAction>>,
+        max_len: usize,
+        metrics: &mut Box<dyn Metrics + Send>,
         peer: PeerId,
         control: ControlAction,
     ) {
-        control_pool
-            .entry(peer)
-            .or_insert_with(Vec::new)
-            .push(control);
+        let controls = control_pool.entry(peer).or_default();
+        if max_len > 0 && controls.len() >= max_len {
+            warn!(
+                "Control pool for peer {} is full, dropping the oldest control action",
+                peer
+            );
+            controls.pop_front();
+            metrics.control_pool_overflow();
+        }
+        controls.push_back(control);
     }
 
     /// Takes each control action mapping and turns it into a message
     fn flush_control_pool(&mut self) {
         for (peer, controls) in self.control_pool.drain().collect::<Vec<_>>() {
+            let controls = Vec::from(controls);
             if self.send_control_rpc_message(peer, controls).is_err() {
                 error!("Failed to flush control pool. Message too large");
             }
@@ -2891,8 +3953,16 @@ where
         // If the message is oversized, try and fragment it. If it cannot be fragmented, log an
         // error and drop the message (all individual messages should be small enough to fit in the
         // max_transmit_size)
-        let messages = fragment_rpc_message(rpc, self.config.max_transmit_size())
-            .map_err(|_| PublishError::MessageTooLarge)?;
+        let messages = match fragment_rpc_message(rpc, self.config.max_transmit_size()) {
+            Ok(messages) => messages,
+            Err(_) => {
+                self.metrics.rpc_fragment_failed();
+                return Err(PublishError::MessageTooLarge);
+            }
+        };
+        if messages.len() > 1 {
+            self.metrics.rpc_fragmented();
+        }
 
         for message in messages {
             self.events.push_back(ToSwarm::NotifyHandler {
@@ -2907,17 +3977,56 @@ where
     fn send_control_rpc_message(
         &mut self,
         peer_id: PeerId,
-        control: Vec<ControlAction>,
+        mut control: Vec<ControlAction>,
     ) -> Result<(), PublishError> {
+        // Send at most one GRAFT per peer and topic within a heartbeat cycle
+        control.retain(|action| match action {
+            ControlAction::Graft { topic_hash } => {
+                let first_graft = self.grafts_sent.insert((peer_id, topic_hash.clone()));
+                if !first_graft {
+                    debug!(
+                        "GRAFT: Ignoring duplicate GRAFT to peer {} for topic {:?}",
+                        peer_id, topic_hash
+                    );
+                }
+                first_graft
+            }
+            ControlAction::Prune { topic_hash, .. } => {
+                // A PRUNE resets the GRAFT state, allowing the peer to be grafted again
+                self.grafts_sent.remove(&(peer_id, topic_hash.clone()));
+                true
+            }
+            _ => true,
+        });
+        if control.is_empty() {
+            return Ok(());
+        }
+
+        let summaries = control
+            .iter()
+            .map(|action| ControlMessageSummary::new(peer_id, action))
+            .collect::<Vec<_>>();
+
         let rpc = Rpc {
             subscriptions: Vec::new(),
             messages: Vec::new(),
             control_msgs: control,
         };
+        self.send_rpc_message(peer_id, rpc.into())?;
 
-        self.send_rpc_message(peer_id, rpc.into())
+        // Only record the control messages actually sent
+        for summary in summaries {
+            if self.control_history.len() == CONTROL_HISTORY_SIZE {
+                self.control_history.pop_front();
+            }
+            self.control_history.push_back(summary);
+        }
+        Ok(())
     }
 
+    /// Send the subscriptions to a peer. The RPC is fragmented, as any other, to fit within the
+    /// `max_transmit_size`; it fails with [`PublishError::MessageTooLarge`] if a subscription
+    /// does not fit in an RPC on its own.
     fn send_subscription_rpc_message(
         &mut self,
         peer_id: PeerId,
@@ -2928,10 +4037,26 @@ where
             messages: Vec::new(),
             control_msgs: Vec::new(),
         };
-
         self.send_rpc_message(peer_id, rpc.into())
     }
 
+    /// Consults the peer acceptance policy on a newly established connection.
+    fn check_peer_acceptance(
+        &mut self,
+        peer_id: PeerId,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        if self.peer_acceptance_policy.accept(&peer_id, remote_addr) {
+            return Ok(());
+        }
+
+        debug!(
+            "Connection with peer {} at {} rejected by the peer acceptance policy",
+            peer_id, remote_addr
+        );
+        Err(ConnectionDenied::new(ConnectionRejected { peer_id }))
+    }
+
     fn on_connection_established(
         &mut self,
         ConnectionEstablished {
@@ -2953,12 +4078,25 @@ where
         // as outbound peer if its first connection is outbound.
         let outbound =
             endpoint.is_dialer() && other_established == 0 && !self.px_peers.contains(&peer_id);
+
+        // Peer exchange peers we dialed are considered outbound peers after a probation period
+        if self.config.px_outbound_probation().is_some()
+            && endpoint.is_dialer()
+            && other_established == 0
+            && self.px_peers.contains(&peer_id)
+        {
+            self.px_outbound_probation.insert(peer_id, Instant::now());
+        }
         self.connected_peers
             .track_connection(peer_id, connection_id, PeerKind::Floodsub, outbound);
 
+        // The connection succeeded, reset the explicit peer reconnection backoff
+        self.explicit_peer_backoffs.remove(&peer_id);
+
         // Add the IP to the peer scoring system
         if let Some(ip) = get_ip_addr(endpoint.get_remote_address()) {
             self.peer_score.peer_score_add_ip(&peer_id, ip);
+            self.peer_ips.entry(peer_id).or_insert(ip);
         } else {
             trace!(
                 "Couldn't extract ip from endpoint of peer {} with endpoint {:?}",
@@ -2982,6 +4120,17 @@ where
                     });
                 }
 
+                // Clear any stale subscription the peer may keep from a previous connection
+                if self.config.rebroadcast_unsubscriptions() {
+                    self.prune_unsubscribed_topics();
+                    for topic_hash in self.unsubscribed_topics.keys() {
+                        subscriptions.push(Subscription {
+                            topic_hash: topic_hash.clone(),
+                            action: SubscriptionAction::Unsubscribe,
+                        });
+                    }
+                }
+
                 if !subscriptions.is_empty() {
                     // send our subscriptions to the peer
                     if self
@@ -3096,11 +4245,16 @@ where
 
             // Forget px and outbound status for this peer
             self.px_peers.remove(&peer_id);
+            self.px_outbound_probation.remove(&peer_id);
+            self.peer_ips.remove(&peer_id);
+            self.graylisted_peers.remove(&peer_id);
+            self.draining_peers.remove(&peer_id);
 
             // Remove peer from peer_topics and connected_peers
             // NOTE: It is possible the peer has already been removed from all mappings if it does not
             // support the protocol.
             self.peer_topics.remove(&peer_id);
+            self.peer_bandwidth.remove(&peer_id);
 
             // If metrics are enabled, register the disconnection of a peer based on its protocol.
             let peer_kind = self
@@ -3144,6 +4298,14 @@ where
                 endpoint_new
             )
         }
+
+        // Notify the application about the address change
+        self.events
+            .push_back(ToSwarm::GenerateEvent(Event::PeerAddressChanged {
+                peer_id,
+                old: endpoint_old.get_remote_address().clone(),
+                new: endpoint_new.get_remote_address().clone(),
+            }));
     }
 }
 
@@ -3158,26 +4320,34 @@ where
     fn handle_established_inbound_connection(
         &mut self,
         _: ConnectionId,
-        _: PeerId,
-        _: &Multiaddr,
+        peer_id: PeerId,
         _: &Multiaddr,
+        remote_addr: &Multiaddr,
     ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check_peer_acceptance(peer_id, remote_addr)?;
+
         Ok(Handler::new(
             ProtocolUpgrade::new(&self.config),
-            self.config.idle_timeout(),
+            self.config.inbound_idle_timeout(),
+            self.config.prioritize_control_messages(),
+            self.config.send_queue_high_water_mark(),
         ))
     }
 
     fn handle_established_outbound_connection(
         &mut self,
         _: ConnectionId,
-        _: PeerId,
-        _: &Multiaddr,
+        peer_id: PeerId,
+        addr: &Multiaddr,
         _: Endpoint,
     ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.check_peer_acceptance(peer_id, addr)?;
+
         Ok(Handler::new(
             ProtocolUpgrade::new(&self.config),
-            self.config.idle_timeout(),
+            self.config.outbound_idle_timeout(),
+            self.config.prioritize_control_messages(),
+            self.config.send_queue_high_water_mark(),
         ))
     }
 
@@ -3227,7 +4397,24 @@ where
                     }
                 }
             }
-            HandlerEvent::Rpc(rpc) => self.handle_received_rpc(&propagation_source, rpc),
+            HandlerEvent::Rpc(rpc) => {
+                let (_, received) = self.peer_bandwidth.entry(propagation_source).or_default();
+                *received += rpc.encoded_len() as u64;
+
+                self.handle_received_rpc(&propagation_source, rpc)
+            }
+            HandlerEvent::SendQueueDropped(count) => {
+                debug!(
+                    "Dropped {} messages to slow peer: {}",
+                    count, propagation_source
+                );
+                self.metrics.send_queue_dropped(count);
+            }
+            HandlerEvent::SendQueueAccepted(bytes) => {
+                // Only the messages accepted by the handler send queue count as sent
+                let (sent, _) = self.peer_bandwidth.entry(propagation_source).or_default();
+                *sent += bytes;
+            }
         }
     }
 
@@ -3266,3 +4453,2407 @@ impl<C: DataTransform, F: TopicSubscriptionFilter> fmt::Debug for Behaviour<C, F
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+
+    use libp2p::core::{ConnectedPoint, Endpoint, PeerRecord};
+    use libp2p::identity::Keypair;
+    use libp2p::swarm::behaviour::{AddressChange, ConnectionEstablished};
+    use libp2p::swarm::{ConnectionId, FromSwarm, NetworkBehaviour, ToSwarm};
+    use libp2p::{Multiaddr, PeerId};
+    use prometheus_client::encoding::text::encode;
+    use prometheus_client::registry::Registry;
+    use prost::Message as _;
+
+    use crate::gossipsub::config::{ConfigBuilder, MessageAuthenticity, ValidationMode};
+    use crate::gossipsub::error::PublishError;
+    use crate::gossipsub::event::Event;
+    use crate::gossipsub::handler::{HandlerEvent, HandlerIn};
+    use crate::gossipsub::message_id::MessageId;
+    use crate::gossipsub::metrics::Config as MetricsConfig;
+    use crate::gossipsub::peer_acceptance::CallbackPeerAcceptancePolicy;
+    use crate::gossipsub::peer_score::{PeerScoreParams, PeerScoreThresholds, TopicScoreParams};
+    use crate::gossipsub::rpc::{MessageRpc, PeerInfoProto, RpcProto};
+    use crate::gossipsub::signing::SignatureKeyType;
+    use crate::gossipsub::subscription_filter::AllowAllSubscriptionFilter;
+    use crate::gossipsub::topic::TopicHash;
+    use crate::gossipsub::transform::IdentityTransform;
+    use crate::gossipsub::types::{
+        ControlAction, ControlMessageKind, PeerInfo, PeerKind, PublishOptions, Subscription,
+        SubscriptionAction, TopicPeerRoles,
+    };
+    use crate::gossipsub::IdentTopic;
+
+    use super::Behaviour;
+
+    /// A gossipsub configuration builder with anonymous message validation, the base
+    /// configuration of the tests.
+    fn anonymous_config() -> ConfigBuilder {
+        let mut config = ConfigBuilder::default();
+        config.validation_mode(ValidationMode::Anonymous);
+        config
+    }
+
+    /// Build a behaviour with anonymous message authenticity.
+    fn new_behaviour(config: &ConfigBuilder) -> Behaviour {
+        let config = config.build().expect("valid gossipsub configuration");
+        Behaviour::new(MessageAuthenticity::Anonymous, config).expect("valid behaviour")
+    }
+
+    #[test]
+    fn behaviours_with_the_same_rng_seed_select_the_same_mesh_peers() {
+        // Given
+        let topic = IdentTopic::new("/waku/2/test");
+        let peers = (0..20).map(|_| PeerId::random()).collect::<Vec<_>>();
+
+        let new_seeded_behaviour = |seed: u64| {
+            let mut behaviour = new_behaviour(&anonymous_config());
+            behaviour.set_rng_seed(seed);
+
+            for (id, peer) in peers.iter().enumerate() {
+                behaviour.connected_peers.track_connection(
+                    *peer,
+                    ConnectionId::new_unchecked(id),
+                    PeerKind::Gossipsubv1_1,
+                    false,
+                );
+            }
+            behaviour
+                .topic_peers
+                .insert(topic.hash(), peers.iter().cloned().collect());
+            behaviour
+        };
+
+        let mut behaviour1 = new_seeded_behaviour(42);
+        let mut behaviour2 = new_seeded_behaviour(42);
+
+        // When
+        behaviour1.subscribe(&topic).expect("subscribe to topic");
+        behaviour2.subscribe(&topic).expect("subscribe to topic");
+
+        // Then
+        let mesh1 = &behaviour1.mesh[&topic.hash()];
+        let mesh2 = &behaviour2.mesh[&topic.hash()];
+        assert_eq!(mesh1.len(), behaviour1.config.mesh_n());
+        assert_eq!(mesh1, mesh2);
+    }
+
+    #[test]
+    fn address_change_emits_peer_address_changed_event() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let peer_id = PeerId::random();
+        let old_addr: Multiaddr = "/ip4/1.2.3.4/tcp/60000".parse().unwrap();
+        let new_addr: Multiaddr = "/ip4/5.6.7.8/tcp/60000".parse().unwrap();
+        let old = ConnectedPoint::Dialer {
+            address: old_addr.clone(),
+            role_override: Endpoint::Dialer,
+        };
+        let new = ConnectedPoint::Dialer {
+            address: new_addr.clone(),
+            role_override: Endpoint::Dialer,
+        };
+
+        // When
+        behaviour.on_swarm_event(FromSwarm::AddressChange(AddressChange {
+            peer_id,
+            connection_id: ConnectionId::new_unchecked(0),
+            old: &old,
+            new: &new,
+        }));
+
+        // Then
+        let event = behaviour.events.pop_front();
+        assert!(matches!(
+            event,
+            Some(ToSwarm::GenerateEvent(Event::PeerAddressChanged { peer_id: peer, old, new }))
+                if peer == peer_id && old == old_addr && new == new_addr
+        ));
+    }
+
+    #[test]
+    fn join_and_heartbeat_graft_of_the_same_peer_sends_a_single_graft() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .mesh_n_low(1)
+                .mesh_n(1)
+                .mesh_n_high(1)
+                .mesh_outbound_min(0),
+        );
+
+        let peer_id = PeerId::random();
+        let topic = IdentTopic::new("/waku/2/test");
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([peer_id]));
+
+        // Joining the topic grafts the peer and pools the GRAFT until the next heartbeat
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+        assert!(behaviour.control_pool.contains_key(&peer_id));
+
+        // The peer leaves the mesh before the heartbeat, so the mesh maintenance grafts it again
+        behaviour
+            .mesh
+            .get_mut(&topic.hash())
+            .expect("topic mesh")
+            .remove(&peer_id);
+
+        // When
+        behaviour.on_heartbeat(1);
+
+        // Then
+        assert!(behaviour.mesh[&topic.hash()].contains(&peer_id));
+
+        let sent_grafts = behaviour
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ToSwarm::NotifyHandler {
+                    peer_id: peer,
+                    event: HandlerIn::Message(rpc),
+                    ..
+                } if *peer == peer_id => rpc.control.as_ref(),
+                _ => None,
+            })
+            .map(|control| control.graft.len())
+            .sum::<usize>();
+        assert_eq!(sent_grafts, 1);
+    }
+
+    /// Simulates a looped topology: a message authored (and signed) by the local node is
+    /// forwarded back to it by a remote peer.
+    fn receive_self_authored_message(allow_self_origin: bool) -> Behaviour {
+        let config = ConfigBuilder::default()
+            .allow_self_origin(allow_self_origin)
+            .build()
+            .expect("valid gossipsub configuration");
+        let keypair = Keypair::generate_secp256k1();
+        let mut behaviour: Behaviour =
+            Behaviour::new(MessageAuthenticity::Signed(keypair), config).expect("valid behaviour");
+
+        let topic = IdentTopic::new("/waku/2/test");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let (_, raw_message, _) = behaviour
+            .build_publish_message(&topic.hash(), b"test-payload".to_vec())
+            .expect("build the message");
+
+        let remote_peer = PeerId::random();
+        behaviour.handle_received_message(raw_message, &remote_peer);
+
+        behaviour
+    }
+
+    #[test]
+    fn self_authored_message_is_accepted_when_self_origin_is_allowed() {
+        // When
+        let behaviour = receive_self_authored_message(true);
+
+        // Then
+        assert!(behaviour
+            .events
+            .iter()
+            .any(|event| matches!(event, ToSwarm::GenerateEvent(Event::Message { .. }))));
+    }
+
+    #[test]
+    fn self_authored_message_is_rejected_when_self_origin_is_not_allowed() {
+        // When
+        let behaviour = receive_self_authored_message(false);
+
+        // Then
+        assert!(!behaviour
+            .events
+            .iter()
+            .any(|event| matches!(event, ToSwarm::GenerateEvent(Event::Message { .. }))));
+    }
+
+    #[test]
+    fn duplicate_publish_is_resent_to_mesh_peers_when_republishing_is_enabled() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .flood_publish(false)
+                .republish_duplicates(true),
+        );
+
+        let topic_hash = TopicHash::from_raw("/waku/2/test");
+        let peer_id = PeerId::random();
+        behaviour
+            .topic_peers
+            .insert(topic_hash.clone(), BTreeSet::from([peer_id]));
+        behaviour
+            .mesh
+            .insert(topic_hash.clone(), BTreeSet::from([peer_id]));
+
+        let first_id = behaviour
+            .publish(topic_hash.clone(), b"test-payload".to_vec())
+            .expect("publish the message");
+
+        // When
+        let second_id = behaviour
+            .publish(topic_hash, b"test-payload".to_vec())
+            .expect("re-publish the duplicate message");
+
+        // Then
+        assert_eq!(first_id, second_id);
+
+        let sent_messages = behaviour
+            .events
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    ToSwarm::NotifyHandler {
+                        peer_id: peer,
+                        event: HandlerIn::Message(rpc),
+                        ..
+                    } if *peer == peer_id && !rpc.publish.is_empty()
+                )
+            })
+            .count();
+        assert_eq!(sent_messages, 2);
+    }
+
+    #[test]
+    fn low_scored_peer_is_reported_below_the_gossip_threshold() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+        behaviour
+            .with_peer_score(PeerScoreParams::default(), PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+
+        let peer_id = PeerId::random();
+        behaviour.peer_score.peer_score_add_peer(peer_id);
+
+        // When
+        // With the default application specific weight, the peer score is -20.0: below the
+        // gossip threshold (-10.0) but above the publish threshold (-50.0)
+        behaviour.set_application_score(&peer_id, -2.0);
+
+        // Then
+        let status = behaviour.peer_score_thresholds_status(&peer_id);
+        assert!(status.below_gossip_threshold);
+        assert!(!status.below_publish_threshold);
+        assert!(!status.below_graylist_threshold);
+        assert!(!status.is_all_clear());
+    }
+
+    #[test]
+    fn peer_is_reported_all_clear_when_scoring_is_disabled() {
+        // Given
+        let behaviour = new_behaviour(&anonymous_config());
+
+        // When
+        let status = behaviour.peer_score_thresholds_status(&PeerId::random());
+
+        // Then
+        assert!(status.is_all_clear());
+    }
+
+    #[test]
+    fn control_pool_is_bounded_per_peer() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().max_control_pool_length(3));
+
+        let peer_id = PeerId::random();
+
+        // When
+        for id in 0..5u8 {
+            Behaviour::<IdentityTransform, AllowAllSubscriptionFilter>::control_pool_add(
+                &mut behaviour.control_pool,
+                behaviour.config.max_control_pool_length(),
+                &mut behaviour.metrics,
+                peer_id,
+                ControlAction::IWant {
+                    message_ids: vec![MessageId::new(vec![id])],
+                },
+            );
+        }
+
+        // Then
+        let pooled = behaviour
+            .control_pool
+            .get(&peer_id)
+            .expect("pooled actions");
+        assert_eq!(pooled.len(), 3);
+        assert!(matches!(
+            &pooled[0],
+            ControlAction::IWant { message_ids } if message_ids == &vec![MessageId::new(vec![2])]
+        ));
+    }
+
+    #[test]
+    fn messages_published_to_peers_are_not_gossiped() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .mesh_n_low(2)
+                .mesh_n(2)
+                .mesh_n_high(2)
+                .mesh_outbound_min(0),
+        );
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let target_peer = PeerId::random();
+        let mesh_peer = PeerId::random();
+        let gossip_peer = PeerId::random();
+        for (n, peer_id) in [target_peer, mesh_peer, gossip_peer]
+            .into_iter()
+            .enumerate()
+        {
+            behaviour.connected_peers.track_connection(
+                peer_id,
+                ConnectionId::new_unchecked(n),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+        }
+        behaviour.topic_peers.insert(
+            topic.hash(),
+            BTreeSet::from([target_peer, mesh_peer, gossip_peer]),
+        );
+        behaviour
+            .mesh
+            .insert(topic.hash(), BTreeSet::from([target_peer, mesh_peer]));
+
+        let message_id = behaviour
+            .publish_to_peers(topic, b"payload".to_vec(), &[target_peer])
+            .expect("publish the message");
+
+        // When
+        behaviour.on_heartbeat(1);
+
+        // Then
+        assert!(behaviour.mcache.get(&message_id).is_none());
+        assert!(behaviour
+            .recent_control_messages()
+            .all(|summary| summary.kind != ControlMessageKind::IHave));
+    }
+
+    #[test]
+    fn recent_control_messages_include_sent_ihaves() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .mesh_n_low(1)
+                .mesh_n(1)
+                .mesh_n_high(1)
+                .mesh_outbound_min(0),
+        );
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let mesh_peer = PeerId::random();
+        let gossip_peer = PeerId::random();
+        for (n, peer_id) in [mesh_peer, gossip_peer].into_iter().enumerate() {
+            behaviour.connected_peers.track_connection(
+                peer_id,
+                ConnectionId::new_unchecked(n),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+        }
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([mesh_peer, gossip_peer]));
+        behaviour
+            .mesh
+            .insert(topic.hash(), BTreeSet::from([mesh_peer]));
+
+        let message_id = behaviour
+            .publish(topic.clone(), b"payload".to_vec())
+            .expect("publish the message");
+
+        // When
+        behaviour.on_heartbeat(1);
+
+        // Then
+        let ihaves = behaviour
+            .recent_control_messages()
+            .filter(|summary| summary.kind == ControlMessageKind::IHave)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ihaves.len(), 1);
+        assert_eq!(ihaves[0].peer_id, gossip_peer);
+        assert_eq!(ihaves[0].topic_hash, Some(topic.hash()));
+        assert_eq!(ihaves[0].message_ids, vec![message_id]);
+    }
+
+    #[test]
+    fn pooled_prune_is_sent_before_disconnecting_a_drained_peer() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().disconnect_drain_time(Duration::ZERO));
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+
+        let topic_hash = IdentTopic::new("/waku/2/default-waku/proto").hash();
+        Behaviour::<IdentityTransform, AllowAllSubscriptionFilter>::control_pool_add(
+            &mut behaviour.control_pool,
+            behaviour.config.max_control_pool_length(),
+            &mut behaviour.metrics,
+            peer_id,
+            ControlAction::Prune {
+                topic_hash: topic_hash.clone(),
+                peers: Vec::new(),
+                backoff: None,
+            },
+        );
+
+        // When
+        behaviour.disconnect_peer(&peer_id, true);
+        let closed_before_heartbeat = behaviour
+            .events
+            .iter()
+            .any(|event| matches!(event, ToSwarm::CloseConnection { .. }));
+
+        behaviour.on_heartbeat(1);
+
+        // Then
+        let prune_sent_at = behaviour.events.iter().position(|event| {
+            matches!(
+                event,
+                ToSwarm::NotifyHandler {
+                    peer_id: peer,
+                    event: HandlerIn::Message(rpc),
+                    ..
+                } if *peer == peer_id && rpc.control.as_ref().map_or(false, |control| {
+                    control.prune.iter().any(|prune| {
+                        prune.topic_id.as_deref() == Some(topic_hash.as_str())
+                    })
+                })
+            )
+        });
+        let closed_at = behaviour.events.iter().position(|event| {
+            matches!(
+                event,
+                ToSwarm::CloseConnection { peer_id: peer, .. } if *peer == peer_id
+            )
+        });
+
+        assert!(!closed_before_heartbeat, "the peer should be drained first");
+        assert!(prune_sent_at.is_some(), "the pooled PRUNE should be sent");
+        assert!(closed_at.is_some(), "the connection should be closed");
+        assert!(prune_sent_at < closed_at);
+        assert!(behaviour.draining_peers.is_empty());
+    }
+
+    #[test]
+    fn draining_a_peer_again_does_not_extend_its_drain_time() {
+        // Given
+        let mut behaviour =
+            new_behaviour(anonymous_config().disconnect_drain_time(Duration::from_secs(1)));
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+
+        behaviour.disconnect_peer(&peer_id, true);
+        let deadline = behaviour.draining_peers[&peer_id];
+
+        // When
+        std::thread::sleep(Duration::from_millis(10));
+        behaviour.disconnect_peer(&peer_id, true);
+
+        // Then
+        assert!(behaviour.is_draining_peer(&peer_id));
+        assert_eq!(behaviour.draining_peers[&peer_id], deadline);
+    }
+
+    #[test]
+    fn manually_pruned_peer_leaves_the_mesh_and_is_backed_off() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let topic_hash = topic.hash();
+        behaviour.subscribe(&topic).expect("subscribed");
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour.handle_graft(&peer_id, vec![topic_hash.clone()]);
+        assert!(behaviour.mesh[&topic_hash].contains(&peer_id));
+
+        // When
+        let pruned = behaviour.prune_peer(&peer_id, &topic_hash);
+        let pruned_again = behaviour.prune_peer(&peer_id, &topic_hash);
+
+        // Then
+        assert!(pruned);
+        assert!(!pruned_again, "the peer is no longer in the mesh");
+        assert!(!behaviour.mesh[&topic_hash].contains(&peer_id));
+        assert!(!behaviour.blacklisted_peers().contains(&peer_id));
+
+        let pooled = behaviour
+            .control_pool
+            .get(&peer_id)
+            .expect("pooled actions");
+        assert!(pooled.iter().any(|action| matches!(
+            action,
+            ControlAction::Prune { topic_hash: topic, backoff: Some(_), .. } if *topic == topic_hash
+        )));
+
+        // A GRAFT within the backoff period is rejected
+        behaviour.handle_graft(&peer_id, vec![topic_hash.clone()]);
+        assert!(!behaviour.mesh[&topic_hash].contains(&peer_id));
+    }
+
+    #[test]
+    fn leaving_the_network_prunes_all_topics_and_closes_all_connections() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().disconnect_drain_time(Duration::ZERO));
+
+        let topics = [
+            IdentTopic::new("/waku/2/default-waku/proto"),
+            IdentTopic::new("/waku/2/rs/0/1"),
+        ];
+        for topic in &topics {
+            behaviour.subscribe(topic).expect("subscribed");
+        }
+
+        let mesh_peer = PeerId::random();
+        let other_peer = PeerId::random();
+        for (n, peer_id) in [mesh_peer, other_peer].into_iter().enumerate() {
+            behaviour.connected_peers.track_connection(
+                peer_id,
+                ConnectionId::new_unchecked(n),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+        }
+        for topic in &topics {
+            behaviour
+                .mesh
+                .get_mut(&topic.hash())
+                .expect("subscribed topic mesh")
+                .insert(mesh_peer);
+        }
+
+        // When
+        let disconnected = behaviour.leave_network();
+        behaviour.on_heartbeat(1);
+
+        // Then
+        assert_eq!(disconnected.len(), 2);
+        assert!(behaviour.mesh.is_empty());
+        for topic in &topics {
+            let topic_hash = topic.hash();
+            let prune_sent = behaviour.events.iter().any(|event| {
+                matches!(
+                    event,
+                    ToSwarm::NotifyHandler {
+                        peer_id: peer,
+                        event: HandlerIn::Message(rpc),
+                        ..
+                    } if *peer == mesh_peer && rpc.control.as_ref().map_or(false, |control| {
+                        control.prune.iter().any(|prune| {
+                            prune.topic_id.as_deref() == Some(topic_hash.as_str())
+                        })
+                    })
+                )
+            });
+            assert!(prune_sent, "a PRUNE should be sent for {topic_hash}");
+        }
+        for peer_id in [mesh_peer, other_peer] {
+            let closed = behaviour.events.iter().any(|event| {
+                matches!(
+                    event,
+                    ToSwarm::CloseConnection { peer_id: peer, .. } if *peer == peer_id
+                )
+            });
+            assert!(closed, "the connection to {peer_id} should be closed");
+        }
+        assert!(behaviour.draining_peers.is_empty());
+    }
+
+    #[test]
+    fn forwarded_message_is_sent_to_the_highest_scored_peers_when_capped() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().max_forward_peers(Some(5)));
+        behaviour
+            .with_peer_score(PeerScoreParams::default(), PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+
+        let topic_hash = TopicHash::from_raw("/waku/2/test");
+        let peers = (0..20).map(|_| PeerId::random()).collect::<Vec<_>>();
+        for (score, peer_id) in peers.iter().enumerate() {
+            behaviour.peer_score.peer_score_add_peer(*peer_id);
+            behaviour.set_application_score(peer_id, score as f64);
+        }
+        behaviour
+            .mesh
+            .insert(topic_hash.clone(), peers.iter().cloned().collect());
+
+        let (msg_id, raw_message, _) = behaviour
+            .build_publish_message(&topic_hash, b"test-payload".to_vec())
+            .expect("build the message");
+
+        // When
+        let forwarded = behaviour
+            .forward_msg(&msg_id, raw_message, None, HashSet::new())
+            .expect("forward the message");
+
+        // Then
+        assert!(forwarded);
+
+        let recipients = behaviour
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ToSwarm::NotifyHandler {
+                    peer_id,
+                    event: HandlerIn::Message(_),
+                    ..
+                } => Some(*peer_id),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let highest_scored = peers[15..].iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(recipients, highest_scored);
+    }
+
+    /// Subscribes to two topics, unsubscribes from one of them, and then establishes two
+    /// connections with a peer. Returns the subscription RPCs sent to the peer.
+    fn subscriptions_sent_on_connection(
+        rebroadcast_unsubscriptions: bool,
+        unsubscription_rebroadcast_time: Duration,
+    ) -> Vec<Vec<Subscription>> {
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .rebroadcast_unsubscriptions(rebroadcast_unsubscriptions)
+                .unsubscription_rebroadcast_time(unsubscription_rebroadcast_time),
+        );
+
+        let active_topic = IdentTopic::new("/waku/2/test-active");
+        let stale_topic = IdentTopic::new("/waku/2/test-stale");
+        behaviour
+            .subscribe(&active_topic)
+            .expect("subscribe to topic");
+        behaviour
+            .subscribe(&stale_topic)
+            .expect("subscribe to topic");
+        behaviour
+            .unsubscribe(&stale_topic)
+            .expect("unsubscribe from topic");
+
+        let peer_id = PeerId::random();
+        let endpoint = ConnectedPoint::Dialer {
+            address: "/ip4/1.2.3.4/tcp/60000".parse().unwrap(),
+            role_override: Endpoint::Dialer,
+        };
+        for other_established in 0..2 {
+            behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id,
+                connection_id: ConnectionId::new_unchecked(other_established),
+                endpoint: &endpoint,
+                failed_addresses: &[],
+                other_established,
+            }));
+        }
+
+        behaviour
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ToSwarm::NotifyHandler {
+                    peer_id: peer,
+                    event: HandlerIn::Message(rpc),
+                    ..
+                } if *peer == peer_id && !rpc.subscriptions.is_empty() => Some(
+                    rpc.subscriptions
+                        .iter()
+                        .cloned()
+                        .map(Subscription::from)
+                        .collect(),
+                ),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn only_active_subscriptions_are_sent_to_a_connecting_peer() {
+        // When
+        let sent = subscriptions_sent_on_connection(false, Duration::from_secs(60));
+
+        // Then
+        assert_eq!(
+            sent,
+            vec![vec![Subscription {
+                topic_hash: IdentTopic::new("/waku/2/test-active").hash(),
+                action: SubscriptionAction::Subscribe,
+            }]]
+        );
+    }
+
+    #[test]
+    fn unsubscriptions_are_rebroadcast_to_a_connecting_peer_when_enabled() {
+        // When
+        let sent = subscriptions_sent_on_connection(true, Duration::from_secs(60));
+
+        // Then
+        assert_eq!(
+            sent,
+            vec![vec![
+                Subscription {
+                    topic_hash: IdentTopic::new("/waku/2/test-active").hash(),
+                    action: SubscriptionAction::Subscribe,
+                },
+                Subscription {
+                    topic_hash: IdentTopic::new("/waku/2/test-stale").hash(),
+                    action: SubscriptionAction::Unsubscribe,
+                },
+            ]]
+        );
+    }
+
+    #[test]
+    fn expired_unsubscriptions_are_not_rebroadcast() {
+        // When
+        let sent = subscriptions_sent_on_connection(true, Duration::ZERO);
+
+        // Then
+        assert_eq!(
+            sent,
+            vec![vec![Subscription {
+                topic_hash: IdentTopic::new("/waku/2/test-active").hash(),
+                action: SubscriptionAction::Subscribe,
+            }]]
+        );
+    }
+
+    #[test]
+    fn subscriptions_exceeding_max_transmit_size_are_split_across_rpcs() {
+        // Sweep the transmit size, so every split point of the subscriptions is exercised
+        for max_transmit_size in 100..300 {
+            // Given
+            let mut behaviour =
+                new_behaviour(anonymous_config().max_transmit_size(max_transmit_size));
+
+            let topics = (10..30)
+                .map(|i| IdentTopic::new(format!("/waku/2/test-topic-{i}")))
+                .collect::<Vec<_>>();
+            for topic in &topics {
+                behaviour.subscribe(topic).expect("subscribe to topic");
+            }
+
+            // When
+            let peer_id = PeerId::random();
+            let endpoint = ConnectedPoint::Dialer {
+                address: "/ip4/1.2.3.4/tcp/60000".parse().unwrap(),
+                role_override: Endpoint::Dialer,
+            };
+            behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id,
+                connection_id: ConnectionId::new_unchecked(0),
+                endpoint: &endpoint,
+                failed_addresses: &[],
+                other_established: 0,
+            }));
+
+            // Then
+            let rpcs = behaviour
+                .events
+                .iter()
+                .filter_map(|event| match event {
+                    ToSwarm::NotifyHandler {
+                        peer_id: peer,
+                        event: HandlerIn::Message(rpc),
+                        ..
+                    } if *peer == peer_id && !rpc.subscriptions.is_empty() => Some(rpc.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            assert!(rpcs.len() > 1, "the subscriptions should be split");
+            for rpc in &rpcs {
+                assert!(
+                    rpc.encoded_len() < max_transmit_size,
+                    "rpc of {} bytes exceeds the max transmit size of {} bytes",
+                    rpc.encoded_len(),
+                    max_transmit_size
+                );
+            }
+
+            let sent = rpcs
+                .into_iter()
+                .flat_map(|rpc| rpc.subscriptions)
+                .map(Subscription::from)
+                .map(|subscription| subscription.topic_hash)
+                .collect::<HashSet<_>>();
+            let expected = topics
+                .iter()
+                .map(|topic| topic.hash())
+                .collect::<HashSet<_>>();
+            assert_eq!(sent, expected);
+        }
+    }
+
+    #[test]
+    fn high_scored_peers_are_opportunistically_grafted() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .opportunistic_graft_ticks(2)
+                .opportunistic_graft_peers(2),
+        );
+        behaviour
+            .with_peer_score(PeerScoreParams::default(), PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+
+        let topic_hash = TopicHash::from_raw("/waku/2/test");
+
+        // A mesh of low scoring peers (score 10.0), below the opportunistic graft threshold (20.0)
+        let low_scored_peers = (0..6).map(|_| PeerId::random()).collect::<Vec<_>>();
+        // A few high scoring gossipers (score 50.0) not in the mesh
+        let high_scored_peers = (0..2).map(|_| PeerId::random()).collect::<Vec<_>>();
+
+        let all_peers = low_scored_peers.iter().chain(high_scored_peers.iter());
+        for (id, peer_id) in all_peers.clone().enumerate() {
+            behaviour.connected_peers.track_connection(
+                *peer_id,
+                ConnectionId::new_unchecked(id),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+            behaviour.peer_score.peer_score_add_peer(*peer_id);
+        }
+        for peer_id in &low_scored_peers {
+            behaviour.set_application_score(peer_id, 1.0);
+        }
+        for peer_id in &high_scored_peers {
+            behaviour.set_application_score(peer_id, 5.0);
+        }
+
+        behaviour
+            .topic_peers
+            .insert(topic_hash.clone(), all_peers.cloned().collect());
+        behaviour.mesh.insert(
+            topic_hash.clone(),
+            low_scored_peers.iter().cloned().collect(),
+        );
+
+        // When
+        for heartbeat_ticks in 1..=2 {
+            behaviour.on_heartbeat(heartbeat_ticks);
+        }
+
+        // Then
+        let mesh = behaviour.mesh.get(&topic_hash).expect("topic mesh");
+        assert_eq!(mesh.len(), 8);
+        assert!(high_scored_peers.iter().all(|peer| mesh.contains(peer)));
+    }
+
+    #[test]
+    fn explicit_and_blacklisted_peers_are_listed() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let explicit_peer = PeerId::random();
+        let blacklisted_peer = PeerId::random();
+        let unblacklisted_peer = PeerId::random();
+
+        // When
+        behaviour.add_explicit_peer(&explicit_peer);
+        behaviour.blacklist_peer(&blacklisted_peer);
+        behaviour.blacklist_peer(&unblacklisted_peer);
+        behaviour.remove_blacklisted_peer(&unblacklisted_peer);
+
+        // Then
+        assert_eq!(behaviour.explicit_peers(), vec![explicit_peer]);
+        assert_eq!(behaviour.blacklisted_peers(), vec![blacklisted_peer]);
+    }
+
+    #[test]
+    fn config_exposes_the_active_parameters() {
+        // Given
+        let mut config = anonymous_config();
+        config
+            .mesh_n_low(4)
+            .mesh_n(8)
+            .mesh_n_high(16)
+            .heartbeat_interval(Duration::from_millis(700))
+            .max_transmit_size(1024);
+
+        // When
+        let behaviour = new_behaviour(&config);
+
+        // Then
+        let config = behaviour.config();
+        assert_eq!(config.mesh_n_low(), 4);
+        assert_eq!(config.mesh_n(), 8);
+        assert_eq!(config.mesh_n_high(), 16);
+        assert_eq!(config.heartbeat_interval(), Duration::from_millis(700));
+        assert_eq!(config.max_transmit_size(), 1024);
+    }
+
+    #[test]
+    fn unsubscribe_all_leaves_every_mesh() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let topics = (0..3)
+            .map(|i| IdentTopic::new(format!("/waku/2/test-{i}")))
+            .collect::<Vec<_>>();
+        for topic in &topics {
+            behaviour.subscribe(topic).expect("subscribe to topic");
+        }
+
+        // A mesh peer on the first topic
+        let mesh_peer = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            mesh_peer,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour
+            .peer_topics
+            .insert(mesh_peer, topics.iter().map(|topic| topic.hash()).collect());
+        behaviour
+            .mesh
+            .get_mut(&topics[0].hash())
+            .expect("topic mesh")
+            .insert(mesh_peer);
+
+        // When
+        let left = behaviour.unsubscribe_all().expect("unsubscribe from all");
+        let left_again = behaviour.unsubscribe_all().expect("unsubscribe from all");
+
+        // Then
+        assert_eq!(left, 3);
+        assert_eq!(left_again, 0, "no topics left to unsubscribe from");
+        assert_eq!(behaviour.topics().count(), 0);
+
+        let unsubscribe_backoff = behaviour.config.unsubscribe_backoff().as_secs();
+        let pruned = behaviour
+            .control_pool
+            .get(&mesh_peer)
+            .expect("pooled PRUNE");
+        assert_eq!(pruned.len(), 1);
+        assert!(matches!(
+            &pruned[0],
+            ControlAction::Prune { topic_hash, backoff: Some(backoff), .. }
+                if *topic_hash == topics[0].hash() && *backoff == unsubscribe_backoff
+        ));
+    }
+
+    #[test]
+    fn unsubscribe_all_leaves_every_mesh_even_if_an_announcement_fails() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().max_transmit_size(100));
+
+        // The unsubscription from the first topic does not fit within the max transmit size
+        let oversized_topic = IdentTopic::new(format!("/waku/2/{}", "a".repeat(100)));
+        let topic = IdentTopic::new("/waku/2/test");
+        behaviour
+            .subscribe(&oversized_topic)
+            .expect("subscribe to topic");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour.peer_topics.insert(
+            peer_id,
+            BTreeSet::from([oversized_topic.hash(), topic.hash()]),
+        );
+
+        // When
+        let errors = behaviour
+            .unsubscribe_all()
+            .expect_err("the oversized unsubscription announcement fails");
+
+        // Then
+        assert!(matches!(
+            errors.as_slice(),
+            [(topic_hash, PublishError::MessageTooLarge)] if *topic_hash == oversized_topic.hash()
+        ));
+        assert_eq!(behaviour.topics().count(), 0);
+
+        let unsubscriptions = behaviour
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ToSwarm::NotifyHandler {
+                    peer_id: peer,
+                    event: HandlerIn::Message(rpc),
+                    ..
+                } if *peer == peer_id => Some(rpc.subscriptions.clone()),
+                _ => None,
+            })
+            .flatten()
+            .map(Subscription::from)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            unsubscriptions,
+            vec![Subscription {
+                topic_hash: topic.hash(),
+                action: SubscriptionAction::Unsubscribe,
+            }]
+        );
+    }
+
+    #[test]
+    fn leave_network_drains_the_peers_even_if_an_announcement_fails() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().max_transmit_size(100));
+
+        // The unsubscription from the topic does not fit within the max transmit size
+        let oversized_topic = IdentTopic::new(format!("/waku/2/{}", "a".repeat(100)));
+        behaviour
+            .subscribe(&oversized_topic)
+            .expect("subscribe to topic");
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour
+            .peer_topics
+            .insert(peer_id, BTreeSet::from([oversized_topic.hash()]));
+
+        // When
+        let disconnected = behaviour.leave_network();
+
+        // Then
+        assert_eq!(disconnected, vec![peer_id]);
+        assert!(behaviour.is_draining_peer(&peer_id));
+        assert_eq!(behaviour.topics().count(), 0);
+    }
+
+    #[test]
+    fn graylist_threshold_crossings_are_reported() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+        behaviour
+            .with_peer_score(PeerScoreParams::default(), PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour.peer_score.peer_score_add_peer(peer_id);
+
+        let graylist_events = |behaviour: &Behaviour| {
+            behaviour
+                .events
+                .iter()
+                .filter_map(|event| match event {
+                    ToSwarm::GenerateEvent(Event::PeerGraylisted { peer_id }) => {
+                        Some((*peer_id, true))
+                    }
+                    ToSwarm::GenerateEvent(Event::PeerUngraylisted { peer_id }) => {
+                        Some((*peer_id, false))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // When
+        // Score -100.0, below the graylist threshold (-80.0)
+        behaviour.set_application_score(&peer_id, -10.0);
+        behaviour.on_heartbeat(1);
+        behaviour.on_heartbeat(2);
+
+        let graylisted = behaviour.graylisted_peers().cloned().collect::<Vec<_>>();
+
+        behaviour.set_application_score(&peer_id, 0.0);
+        behaviour.on_heartbeat(3);
+
+        // Then
+        assert_eq!(graylisted, vec![peer_id]);
+        assert_eq!(behaviour.graylisted_peers().count(), 0);
+        assert_eq!(
+            graylist_events(&behaviour),
+            vec![(peer_id, true), (peer_id, false)]
+        );
+    }
+
+    #[test]
+    fn px_peers_are_dialed_at_their_signed_peer_record_addresses() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().prune_peers(16));
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let px_keypair = Keypair::generate_secp256k1();
+        let px_peer_id = px_keypair.public().to_peer_id();
+        let px_address: Multiaddr = "/ip4/192.0.2.1/tcp/60000".parse().unwrap();
+        let signed_peer_record = PeerRecord::new(&px_keypair, vec![px_address.clone()])
+            .expect("signed peer record")
+            .into_signed_envelope()
+            .into_protobuf_encoding();
+
+        let px = PeerInfo::try_from(PeerInfoProto {
+            peer_id: Some(px_peer_id.to_bytes().into()),
+            signed_peer_record: Some(signed_peer_record.into()),
+        })
+        .expect("valid peer info");
+
+        // When
+        behaviour.handle_prune(&PeerId::random(), vec![(topic.hash(), vec![px], None)]);
+
+        // Then
+        let dials = behaviour
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ToSwarm::Dial { opts } => Some(opts),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(dials.len(), 1);
+        assert_eq!(dials[0].get_peer_id(), Some(px_peer_id));
+        assert!(
+            format!("{:?}", dials[0]).contains(&px_address.to_string()),
+            "the PX peer should be dialed at its signed peer record address"
+        );
+    }
+
+    #[test]
+    fn unsigned_messages_are_rejected_on_strict_validation_topics() {
+        // Given
+        let config = ConfigBuilder::default()
+            .validation_mode(ValidationMode::Permissive)
+            .build()
+            .expect("valid gossipsub configuration");
+        let mut behaviour: Behaviour = Behaviour::new(
+            MessageAuthenticity::Signed(Keypair::generate_ed25519()),
+            config,
+        )
+        .expect("valid behaviour");
+
+        let strict_topic = IdentTopic::new("/waku/2/strict/proto");
+        let permissive_topic = IdentTopic::new("/waku/2/permissive/proto");
+        behaviour
+            .subscribe(&strict_topic)
+            .expect("subscribe to topic");
+        behaviour
+            .subscribe(&permissive_topic)
+            .expect("subscribe to topic");
+
+        behaviour
+            .set_topic_validation_mode(strict_topic.hash(), ValidationMode::Strict)
+            .expect("compatible validation mode");
+
+        let rpc = RpcProto {
+            subscriptions: Vec::new(),
+            publish: vec![
+                MessageRpc::new(strict_topic.hash(), b"strict".to_vec()).into_proto(),
+                MessageRpc::new(permissive_topic.hash(), b"permissive".to_vec()).into_proto(),
+            ],
+            control: None,
+        };
+
+        // When
+        behaviour.handle_received_rpc(&PeerId::random(), rpc);
+
+        // Then
+        let received_topics = behaviour
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ToSwarm::GenerateEvent(Event::Message { message, .. }) => {
+                    Some(message.topic.clone())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            received_topics,
+            vec![permissive_topic.hash()],
+            "the unsigned message on the strict topic should be rejected"
+        );
+    }
+
+    #[test]
+    fn signing_key_must_match_the_configured_signature_key_type() {
+        // Given
+        let config = ConfigBuilder::default()
+            .signature_key_type(Some(SignatureKeyType::Secp256k1))
+            .build()
+            .expect("valid gossipsub configuration");
+
+        let ed25519_keypair = Keypair::generate_ed25519();
+        let secp256k1_keypair = Keypair::generate_secp256k1();
+
+        // When
+        let ed25519_result: Result<Behaviour, _> =
+            Behaviour::new(MessageAuthenticity::Signed(ed25519_keypair), config.clone());
+        let secp256k1_result: Result<Behaviour, _> =
+            Behaviour::new(MessageAuthenticity::Signed(secp256k1_keypair), config);
+
+        // Then
+        assert!(
+            ed25519_result.is_err(),
+            "an ed25519 key should be rejected on a secp256k1-only network"
+        );
+        assert!(secp256k1_result.is_ok());
+    }
+
+    #[test]
+    fn topic_validation_mode_must_be_compatible_with_message_authenticity() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let strict_topic = IdentTopic::new("/waku/2/strict/proto");
+        let permissive_topic = IdentTopic::new("/waku/2/permissive/proto");
+
+        // When
+        let strict_result =
+            behaviour.set_topic_validation_mode(strict_topic.hash(), ValidationMode::Strict);
+        let permissive_result = behaviour
+            .set_topic_validation_mode(permissive_topic.hash(), ValidationMode::Permissive);
+
+        // Then
+        assert!(
+            strict_result.is_err(),
+            "unsigned published messages would be rejected on a strict topic"
+        );
+        assert!(permissive_result.is_ok());
+        assert!(!behaviour
+            .topic_message_validators
+            .contains_key(&strict_topic.hash()));
+        assert!(behaviour
+            .topic_message_validators
+            .contains_key(&permissive_topic.hash()));
+    }
+
+    #[test]
+    fn peer_score_breakdown_reports_mesh_message_deliveries() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config().message_id_fn(|message| MessageId::new(message.data.clone())),
+        );
+
+        let peer_id = PeerId::random();
+        let unscored_breakdown = behaviour.peer_score_breakdown(&peer_id);
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let mut score_params = PeerScoreParams::default();
+        score_params
+            .topics
+            .insert(topic.hash(), TopicScoreParams::default());
+        behaviour
+            .with_peer_score(score_params, PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour.peer_score.peer_score_add_peer(peer_id);
+        behaviour
+            .mesh
+            .get_mut(&topic.hash())
+            .expect("topic mesh")
+            .insert(peer_id);
+        behaviour
+            .peer_score
+            .peer_score_graft(&peer_id, &topic.hash());
+
+        let rpc = RpcProto {
+            subscriptions: Vec::new(),
+            publish: (0..3u8)
+                .map(|i| MessageRpc::new(topic.hash(), vec![i]).into_proto())
+                .collect(),
+            control: None,
+        };
+
+        // When
+        behaviour.handle_received_rpc(&peer_id, rpc);
+
+        // Then
+        assert_eq!(unscored_breakdown, None, "peer scoring is disabled");
+
+        let breakdown = behaviour
+            .peer_score_breakdown(&peer_id)
+            .expect("peer score breakdown");
+        let topic_breakdown = breakdown
+            .topics
+            .get(&topic.hash())
+            .expect("topic score breakdown");
+        assert_eq!(topic_breakdown.mesh_message_deliveries, 3.0);
+        assert_eq!(topic_breakdown.first_message_deliveries, 3.0);
+        assert_eq!(breakdown.score, behaviour.peer_score(&peer_id).unwrap());
+    }
+
+    #[test]
+    fn excess_ihave_entries_are_dropped_and_the_sender_penalized() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().max_ihave_per_rpc(Some(2)));
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let score_params = PeerScoreParams {
+            behaviour_penalty_weight: -1.0,
+            ..Default::default()
+        };
+        behaviour
+            .with_peer_score(score_params, PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour.peer_score.peer_score_add_peer(peer_id);
+
+        let rpc = RpcProto {
+            subscriptions: Vec::new(),
+            publish: Vec::new(),
+            control: Some(
+                (0..5u8)
+                    .map(|i| ControlAction::IHave {
+                        topic_hash: topic.hash(),
+                        message_ids: vec![MessageId::new(vec![i])],
+                    })
+                    .collect(),
+            ),
+        };
+
+        // When
+        behaviour.handle_received_rpc(&peer_id, rpc);
+
+        // Then
+        let requested = behaviour
+            .control_pool
+            .get(&peer_id)
+            .expect("pooled actions")
+            .iter()
+            .flat_map(|action| match action {
+                ControlAction::IWant { message_ids } => message_ids.clone(),
+                _ => Vec::new(),
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            requested,
+            HashSet::from([MessageId::new(vec![0]), MessageId::new(vec![1])]),
+            "only the first IHAVE entries up to the limit are processed"
+        );
+
+        let breakdown = behaviour
+            .peer_score_breakdown(&peer_id)
+            .expect("peer score breakdown");
+        assert_eq!(breakdown.behaviour_penalty, 1.0);
+    }
+
+    #[test]
+    fn iwant_response_is_truncated_and_the_requester_penalized() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .max_iwant_response_messages(Some(2))
+                .message_id_fn(|message| MessageId::new(message.data.clone())),
+        );
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let score_params = PeerScoreParams {
+            behaviour_penalty_weight: -1.0,
+            ..Default::default()
+        };
+        behaviour
+            .with_peer_score(score_params, PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let mesh_peer = PeerId::random();
+        let requester = PeerId::random();
+        for (n, peer_id) in [mesh_peer, requester].into_iter().enumerate() {
+            behaviour.connected_peers.track_connection(
+                peer_id,
+                ConnectionId::new_unchecked(n),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+            behaviour.peer_score.peer_score_add_peer(peer_id);
+        }
+        behaviour
+            .mesh
+            .get_mut(&topic.hash())
+            .expect("topic mesh")
+            .insert(mesh_peer);
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([mesh_peer]));
+
+        // Cache the messages requested later on
+        for i in 0..5u8 {
+            behaviour
+                .publish(topic.clone(), vec![i])
+                .expect("publish the message");
+        }
+        behaviour.events.clear();
+
+        let rpc = RpcProto {
+            subscriptions: Vec::new(),
+            publish: Vec::new(),
+            control: Some(
+                [ControlAction::IWant {
+                    message_ids: (0..5u8).map(|i| MessageId::new(vec![i])).collect(),
+                }]
+                .into_iter()
+                .collect(),
+            ),
+        };
+
+        // When
+        behaviour.handle_received_rpc(&requester, rpc);
+
+        // Then
+        let sent_messages = behaviour
+            .events
+            .iter()
+            .map(|event| match event {
+                ToSwarm::NotifyHandler {
+                    peer_id,
+                    event: HandlerIn::Message(rpc),
+                    ..
+                } if *peer_id == requester => rpc.publish.len(),
+                _ => 0,
+            })
+            .sum::<usize>();
+        assert_eq!(sent_messages, 2);
+
+        let breakdown = behaviour
+            .peer_score_breakdown(&requester)
+            .expect("peer score breakdown");
+        assert_eq!(breakdown.behaviour_penalty, 1.0);
+    }
+
+    #[test]
+    fn iwant_response_limit_applies_across_the_rpc_iwant_entries() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .max_iwant_response_messages(Some(2))
+                .message_id_fn(|message| MessageId::new(message.data.clone())),
+        );
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let score_params = PeerScoreParams {
+            behaviour_penalty_weight: -1.0,
+            ..Default::default()
+        };
+        behaviour
+            .with_peer_score(score_params, PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let mesh_peer = PeerId::random();
+        let requester = PeerId::random();
+        for (n, peer_id) in [mesh_peer, requester].into_iter().enumerate() {
+            behaviour.connected_peers.track_connection(
+                peer_id,
+                ConnectionId::new_unchecked(n),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+            behaviour.peer_score.peer_score_add_peer(peer_id);
+        }
+        behaviour
+            .mesh
+            .get_mut(&topic.hash())
+            .expect("topic mesh")
+            .insert(mesh_peer);
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([mesh_peer]));
+
+        // Cache the messages requested later on
+        for i in 0..5u8 {
+            behaviour
+                .publish(topic.clone(), vec![i])
+                .expect("publish the message");
+        }
+        behaviour.events.clear();
+
+        let rpc = RpcProto {
+            subscriptions: Vec::new(),
+            publish: Vec::new(),
+            control: Some(
+                (0..5u8)
+                    .map(|i| ControlAction::IWant {
+                        message_ids: vec![MessageId::new(vec![i])],
+                    })
+                    .collect(),
+            ),
+        };
+
+        // When
+        behaviour.handle_received_rpc(&requester, rpc);
+
+        // Then
+        let sent_messages = behaviour
+            .events
+            .iter()
+            .map(|event| match event {
+                ToSwarm::NotifyHandler {
+                    peer_id,
+                    event: HandlerIn::Message(rpc),
+                    ..
+                } if *peer_id == requester => rpc.publish.len(),
+                _ => 0,
+            })
+            .sum::<usize>();
+        assert_eq!(sent_messages, 2);
+
+        let breakdown = behaviour
+            .peer_score_breakdown(&requester)
+            .expect("peer score breakdown");
+        assert_eq!(breakdown.behaviour_penalty, 1.0);
+    }
+
+    #[test]
+    fn excess_rpc_messages_are_dropped_and_the_sender_penalized() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .message_id_fn(|message| MessageId::new(message.data.clone()))
+                .max_messages_per_rpc(Some(2)),
+        );
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let score_params = PeerScoreParams {
+            behaviour_penalty_weight: -1.0,
+            ..Default::default()
+        };
+        behaviour
+            .with_peer_score(score_params, PeerScoreThresholds::default())
+            .expect("valid peer score parameters");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour.peer_score.peer_score_add_peer(peer_id);
+
+        let rpc = RpcProto {
+            subscriptions: Vec::new(),
+            publish: (0..5u8)
+                .map(|i| MessageRpc::new(topic.hash(), vec![i]).into_proto())
+                .collect(),
+            control: None,
+        };
+
+        // When
+        behaviour.handle_received_rpc(&peer_id, rpc);
+
+        // Then
+        let received_messages = behaviour
+            .events
+            .iter()
+            .filter(|event| matches!(event, ToSwarm::GenerateEvent(Event::Message { .. })))
+            .count();
+        assert_eq!(received_messages, 2);
+        assert!(behaviour.events.iter().any(|event| matches!(
+            event,
+            ToSwarm::GenerateEvent(Event::RpcTruncated { peer_id: peer, dropped: 3 })
+                if peer == &peer_id
+        )));
+
+        let breakdown = behaviour
+            .peer_score_breakdown(&peer_id)
+            .expect("peer score breakdown");
+        assert_eq!(breakdown.behaviour_penalty, 1.0);
+    }
+
+    #[test]
+    fn forwarded_message_records_a_forward_latency_sample() {
+        // Given
+        let config = anonymous_config()
+            .build()
+            .expect("valid gossipsub configuration");
+        let mut registry = Registry::default();
+        let mut behaviour: Behaviour = Behaviour::new_with_metrics(
+            MessageAuthenticity::Anonymous,
+            config,
+            &mut registry,
+            MetricsConfig::default(),
+        )
+        .expect("valid behaviour");
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let source_peer = PeerId::random();
+        let mesh_peer = PeerId::random();
+        for (id, peer_id) in [source_peer, mesh_peer].into_iter().enumerate() {
+            behaviour.connected_peers.track_connection(
+                peer_id,
+                ConnectionId::new_unchecked(id),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+        }
+        behaviour
+            .mesh
+            .get_mut(&topic.hash())
+            .expect("topic mesh")
+            .insert(mesh_peer);
+
+        let rpc = RpcProto {
+            subscriptions: Vec::new(),
+            publish: vec![MessageRpc::new(topic.hash(), b"payload".to_vec()).into_proto()],
+            control: None,
+        };
+
+        // When
+        behaviour.handle_received_rpc(&source_peer, rpc);
+
+        // Then
+        let mut metrics = String::new();
+        encode(&mut metrics, &registry).expect("encode metrics");
+        assert!(metrics
+            .contains("forward_latency_seconds_count{hash=\"/waku/2/default-waku/proto\"} 1"));
+        // The latency is recorded in seconds, with sub-millisecond buckets
+        assert!(metrics.contains("le=\"0.0001\""));
+    }
+
+    #[test]
+    fn oversized_control_rpc_is_fragmented_and_counted() {
+        // Given
+        let config = anonymous_config()
+            .max_transmit_size(100)
+            .build()
+            .expect("valid gossipsub configuration");
+        let mut registry = Registry::default();
+        let mut behaviour: Behaviour = Behaviour::new_with_metrics(
+            MessageAuthenticity::Anonymous,
+            config,
+            &mut registry,
+            MetricsConfig::default(),
+        )
+        .expect("valid behaviour");
+
+        let peer_id = PeerId::random();
+        let grafts = (0..10)
+            .map(|n| ControlAction::Graft {
+                topic_hash: TopicHash::from_raw(format!("/waku/2/fragmentation-{n}/proto")),
+            })
+            .collect::<Vec<_>>();
+
+        // When
+        behaviour
+            .send_control_rpc_message(peer_id, grafts)
+            .expect("send the control rpc");
+
+        // Then
+        assert!(behaviour.events.len() > 1, "the rpc should be fragmented");
+
+        let mut metrics = String::new();
+        encode(&mut metrics, &registry).expect("encode metrics");
+        assert!(metrics.contains("rpc_fragmented_total 1"));
+        assert!(metrics.contains("rpc_fragment_fail_total 0"));
+    }
+
+    #[test]
+    fn messages_dropped_by_a_backed_up_send_queue_are_counted() {
+        // Given
+        let config = anonymous_config()
+            .send_queue_high_water_mark(Some(2))
+            .build()
+            .expect("valid gossipsub configuration");
+        let mut registry = Registry::default();
+        let mut behaviour: Behaviour = Behaviour::new_with_metrics(
+            MessageAuthenticity::Anonymous,
+            config,
+            &mut registry,
+            MetricsConfig::default(),
+        )
+        .expect("valid behaviour");
+
+        let peer_id = PeerId::random();
+
+        // When
+        behaviour.on_connection_handler_event(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            HandlerEvent::SendQueueDropped(3),
+        );
+
+        // Then
+        let mut metrics = String::new();
+        encode(&mut metrics, &registry).expect("encode metrics");
+        assert!(metrics.contains("send_queue_dropped_total 3"));
+    }
+
+    #[test]
+    fn only_bytes_accepted_by_the_send_queue_count_as_sent() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([peer_id]));
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+        behaviour
+            .publish(topic, b"payload".to_vec())
+            .expect("publish the message");
+
+        // When
+        let (sent_before_queued, _) = behaviour.peer_bandwidth(&peer_id);
+        behaviour.on_connection_handler_event(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            HandlerEvent::SendQueueAccepted(42),
+        );
+
+        // Then
+        assert_eq!(sent_before_queued, 0);
+        assert_eq!(behaviour.peer_bandwidth(&peer_id), (42, 0));
+    }
+
+    #[test]
+    fn set_blacklist_disconnects_and_rejects_connected_peers() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let connected_peer = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            connected_peer,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        let unblacklisted_peer = PeerId::random();
+        behaviour.blacklist_peers(&[unblacklisted_peer]);
+        behaviour.events.clear();
+
+        // When
+        behaviour.set_blacklist(HashSet::from([connected_peer]));
+
+        let rpc = RpcProto {
+            subscriptions: Vec::new(),
+            publish: vec![MessageRpc::new(topic.hash(), b"payload".to_vec()).into_proto()],
+            control: None,
+        };
+        behaviour.handle_received_rpc(&connected_peer, rpc);
+
+        // Then
+        assert_eq!(behaviour.blacklisted_peers(), vec![connected_peer]);
+        assert!(behaviour.events.iter().any(|event| matches!(
+            event,
+            ToSwarm::CloseConnection { peer_id, .. } if *peer_id == connected_peer
+        )));
+        assert!(
+            !behaviour
+                .events
+                .iter()
+                .any(|event| matches!(event, ToSwarm::GenerateEvent(Event::Message { .. }))),
+            "the blacklisted peer message should be rejected"
+        );
+    }
+
+    #[test]
+    fn publish_preflight_fails_with_insufficient_peers() {
+        // Given
+        let behaviour = new_behaviour(&anonymous_config());
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+
+        // When
+        let result = behaviour.publish_preflight(topic.hash(), b"payload".to_vec());
+
+        // Then
+        assert!(matches!(result, Err(PublishError::InsufficientPeers)));
+    }
+
+    #[test]
+    fn publish_preflight_honors_the_flood_publish_option() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().flood_publish(false));
+
+        // A topic peer not in the (empty) topic mesh
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        behaviour.subscribe(&topic).expect("subscribe to topic");
+
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([peer_id]));
+
+        // When
+        let mesh_result = behaviour.publish_preflight(topic.hash(), b"payload".to_vec());
+        let flood_result = behaviour.publish_preflight_with_options(
+            topic.hash(),
+            b"payload".to_vec(),
+            PublishOptions { flood: Some(true) },
+        );
+
+        // Then
+        assert!(matches!(mesh_result, Err(PublishError::InsufficientPeers)));
+        assert!(flood_result.is_ok());
+    }
+
+    #[test]
+    fn publish_preflight_checks_the_republish_peers_of_duplicates() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().republish_duplicates(true));
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([peer_id]));
+
+        // Flood published, so the topic has neither mesh nor fanout peers to republish to
+        behaviour
+            .publish(topic.hash(), b"payload".to_vec())
+            .expect("publish the message");
+
+        // When
+        let preflight_result = behaviour.publish_preflight(topic.hash(), b"payload".to_vec());
+        let publish_result = behaviour.publish(topic.hash(), b"payload".to_vec());
+
+        // Then
+        assert!(matches!(
+            preflight_result,
+            Err(PublishError::InsufficientPeers)
+        ));
+        assert!(matches!(
+            publish_result,
+            Err(PublishError::InsufficientPeers)
+        ));
+    }
+
+    #[test]
+    fn publish_preflight_fails_with_message_too_large() {
+        // Given
+        let behaviour = new_behaviour(anonymous_config().max_transmit_size(100));
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+
+        // When
+        let result = behaviour.publish_preflight(topic.hash(), vec![0u8; 256]);
+
+        // Then
+        assert!(matches!(result, Err(PublishError::MessageTooLarge)));
+    }
+
+    #[test]
+    fn publish_preflight_succeeds_with_topic_peers() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([peer_id]));
+
+        // When
+        let result = behaviour.publish_preflight(topic.hash(), b"payload".to_vec());
+
+        // Then
+        assert!(result.is_ok());
+        assert!(behaviour.events.is_empty(), "nothing should be published");
+        assert!(behaviour.publish(topic, b"payload".to_vec()).is_ok());
+    }
+
+    #[test]
+    fn flood_publish_option_reaches_non_mesh_subscribers() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().flood_publish(false));
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let mesh_peer = PeerId::random();
+        let non_mesh_peer = PeerId::random();
+        for (n, peer_id) in [mesh_peer, non_mesh_peer].into_iter().enumerate() {
+            behaviour.connected_peers.track_connection(
+                peer_id,
+                ConnectionId::new_unchecked(n),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+        }
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([mesh_peer, non_mesh_peer]));
+        behaviour
+            .mesh
+            .insert(topic.hash(), BTreeSet::from([mesh_peer]));
+
+        let published_to = |behaviour: &mut Behaviour| {
+            behaviour
+                .events
+                .drain(..)
+                .filter_map(|event| match event {
+                    ToSwarm::NotifyHandler {
+                        peer_id,
+                        event: HandlerIn::Message(rpc),
+                        ..
+                    } if !rpc.publish.is_empty() => Some(peer_id),
+                    _ => None,
+                })
+                .collect::<HashSet<_>>()
+        };
+
+        // When
+        behaviour
+            .publish(topic.clone(), b"mesh".to_vec())
+            .expect("publish the message");
+        let mesh_recipients = published_to(&mut behaviour);
+
+        behaviour
+            .publish_with_options(
+                topic,
+                b"flood".to_vec(),
+                PublishOptions { flood: Some(true) },
+            )
+            .expect("publish the message");
+        let flood_recipients = published_to(&mut behaviour);
+
+        // Then
+        assert_eq!(mesh_recipients, HashSet::from([mesh_peer]));
+        assert_eq!(flood_recipients, HashSet::from([mesh_peer, non_mesh_peer]));
+    }
+
+    #[test]
+    fn frequently_published_fanout_topic_joins_the_mesh() {
+        // Given
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .flood_publish(false)
+                .fanout_join_threshold(Some(3))
+                .message_id_fn(|message| MessageId::new(message.data.clone())),
+        );
+
+        let topic = IdentTopic::new("/waku/2/default-waku/proto");
+        let peer_id = PeerId::random();
+        behaviour.connected_peers.track_connection(
+            peer_id,
+            ConnectionId::new_unchecked(0),
+            PeerKind::Gossipsubv1_1,
+            false,
+        );
+        behaviour
+            .topic_peers
+            .insert(topic.hash(), BTreeSet::from([peer_id]));
+
+        // When
+        for n in 0..2u8 {
+            behaviour
+                .publish(topic.clone(), vec![n])
+                .expect("publish the message");
+        }
+        let joined_below_threshold = behaviour.mesh.contains_key(&topic.hash());
+
+        behaviour
+            .publish(topic.clone(), vec![2])
+            .expect("publish the message");
+
+        // Then
+        assert!(!joined_below_threshold);
+        assert!(!behaviour.fanout.contains_key(&topic.hash()));
+        assert_eq!(
+            behaviour.mesh.get(&topic.hash()),
+            Some(&BTreeSet::from([peer_id]))
+        );
+        let grafts = behaviour.control_pool.get(&peer_id).expect("pooled GRAFT");
+        assert_eq!(grafts.len(), 1);
+        assert!(matches!(
+            &grafts[0],
+            ControlAction::Graft { topic_hash } if *topic_hash == topic.hash()
+        ));
+    }
+
+    #[test]
+    fn topic_peers_are_partitioned_by_role() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().flood_publish(false));
+
+        let subscribed_topic = IdentTopic::new("/waku/2/subscribed/proto");
+        let fanout_topic = IdentTopic::new("/waku/2/fanout/proto");
+        behaviour
+            .subscribe(&subscribed_topic)
+            .expect("subscribe to topic");
+
+        let mesh_peer = PeerId::random();
+        let subscribed_peer = PeerId::random();
+        let fanout_peer = PeerId::random();
+        for (n, peer_id) in [mesh_peer, subscribed_peer, fanout_peer]
+            .into_iter()
+            .enumerate()
+        {
+            behaviour.connected_peers.track_connection(
+                peer_id,
+                ConnectionId::new_unchecked(n),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+        }
+        behaviour.topic_peers.insert(
+            subscribed_topic.hash(),
+            BTreeSet::from([mesh_peer, subscribed_peer]),
+        );
+        behaviour
+            .mesh
+            .insert(subscribed_topic.hash(), BTreeSet::from([mesh_peer]));
+        behaviour
+            .topic_peers
+            .insert(fanout_topic.hash(), BTreeSet::from([fanout_peer]));
+
+        // When
+        behaviour
+            .publish(fanout_topic.clone(), b"payload".to_vec())
+            .expect("publish the message");
+
+        let subscribed_roles = behaviour.topic_peer_roles(&subscribed_topic.hash());
+        let fanout_roles = behaviour.topic_peer_roles(&fanout_topic.hash());
+
+        // Then
+        assert_eq!(
+            subscribed_roles,
+            TopicPeerRoles {
+                mesh: BTreeSet::from([mesh_peer]),
+                fanout: BTreeSet::new(),
+                subscribed: BTreeSet::from([subscribed_peer]),
+            }
+        );
+        assert_eq!(
+            fanout_roles,
+            TopicPeerRoles {
+                mesh: BTreeSet::new(),
+                fanout: BTreeSet::from([fanout_peer]),
+                subscribed: BTreeSet::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn px_peer_is_not_outbound_during_its_probation() {
+        // Given
+        let probation = Duration::from_secs(30);
+        let mut behaviour =
+            new_behaviour(anonymous_config().px_outbound_probation(Some(probation)));
+
+        let px_peer = PeerId::random();
+        behaviour.px_peers.insert(px_peer);
+
+        let endpoint = ConnectedPoint::Dialer {
+            address: "/ip4/1.2.3.4/tcp/60000".parse().unwrap(),
+            role_override: Endpoint::Dialer,
+        };
+        behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+            peer_id: px_peer,
+            connection_id: ConnectionId::new_unchecked(0),
+            endpoint: &endpoint,
+            failed_addresses: &[],
+            other_established: 0,
+        }));
+
+        // When
+        behaviour.on_heartbeat(1);
+        let outbound_during_probation = behaviour.connected_peers.is_outbound(&px_peer);
+
+        // Simulate the probation period elapsing
+        let connected_at = behaviour
+            .px_outbound_probation
+            .get_mut(&px_peer)
+            .expect("px peer on probation");
+        *connected_at = connected_at
+            .checked_sub(probation)
+            .expect("connection time past the probation period");
+        behaviour.on_heartbeat(2);
+
+        // Then
+        assert!(
+            !outbound_during_probation,
+            "the px peer should not count towards mesh_outbound_min during its probation"
+        );
+        assert!(behaviour.connected_peers.is_outbound(&px_peer));
+        assert!(behaviour.px_outbound_probation.is_empty());
+    }
+
+    /// Build a behaviour with eclipse detection enabled and a topic mesh of 6 connected peers.
+    fn new_eclipse_detection_behaviour(topic_hash: &TopicHash) -> (Behaviour, Vec<PeerId>) {
+        let mut behaviour =
+            new_behaviour(anonymous_config().eclipse_detection_threshold(Some(0.75)));
+
+        let mesh_peers = (0..6).map(|_| PeerId::random()).collect::<Vec<_>>();
+        for (id, peer_id) in mesh_peers.iter().enumerate() {
+            behaviour.connected_peers.track_connection(
+                *peer_id,
+                ConnectionId::new_unchecked(id),
+                PeerKind::Gossipsubv1_1,
+                false,
+            );
+        }
+        behaviour
+            .topic_peers
+            .insert(topic_hash.clone(), mesh_peers.iter().cloned().collect());
+        behaviour
+            .mesh
+            .insert(topic_hash.clone(), mesh_peers.iter().cloned().collect());
+
+        (behaviour, mesh_peers)
+    }
+
+    fn possible_eclipse_events(behaviour: &Behaviour) -> Vec<TopicHash> {
+        behaviour
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ToSwarm::GenerateEvent(Event::PossibleEclipse { topic }) => Some(topic.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn px_dominated_mesh_is_reported_as_possible_eclipse() {
+        // Given
+        let topic_hash = TopicHash::from_raw("/waku/2/test");
+        let (mut behaviour, mesh_peers) = new_eclipse_detection_behaviour(&topic_hash);
+
+        // 5 out of 6 mesh peers were learned via peer exchange
+        behaviour.px_peers.extend(mesh_peers.iter().take(5));
+
+        // When
+        for heartbeat_ticks in 1..=2 {
+            behaviour.on_heartbeat(heartbeat_ticks);
+        }
+
+        // Then
+        assert_eq!(
+            possible_eclipse_events(&behaviour),
+            vec![topic_hash],
+            "the possible eclipse should be reported once"
+        );
+    }
+
+    #[test]
+    fn colocated_mesh_is_reported_as_possible_eclipse() {
+        // Given
+        let topic_hash = TopicHash::from_raw("/waku/2/test");
+        let (mut behaviour, mesh_peers) = new_eclipse_detection_behaviour(&topic_hash);
+
+        // 5 out of 6 mesh peers share the same /24 subnet
+        for (i, peer_id) in mesh_peers.iter().enumerate() {
+            let ip = if i < 5 {
+                IpAddr::V4(Ipv4Addr::new(10, 0, 0, i as u8 + 1))
+            } else {
+                IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1))
+            };
+            behaviour.peer_ips.insert(*peer_id, ip);
+        }
+
+        // When
+        behaviour.on_heartbeat(1);
+
+        // Then
+        assert_eq!(possible_eclipse_events(&behaviour), vec![topic_hash]);
+    }
+
+    #[test]
+    fn diverse_mesh_is_not_reported_as_possible_eclipse() {
+        // Given
+        let topic_hash = TopicHash::from_raw("/waku/2/test");
+        let (mut behaviour, mesh_peers) = new_eclipse_detection_behaviour(&topic_hash);
+
+        for (i, peer_id) in mesh_peers.iter().enumerate() {
+            let ip = IpAddr::V4(Ipv4Addr::new(10, i as u8, 0, 1));
+            behaviour.peer_ips.insert(*peer_id, ip);
+        }
+        behaviour.px_peers.extend(mesh_peers.iter().take(2));
+
+        // When
+        behaviour.on_heartbeat(1);
+
+        // Then
+        assert!(possible_eclipse_events(&behaviour).is_empty());
+    }
+
+    #[test]
+    fn explicit_peer_reconnection_is_backed_off() {
+        // Given
+        let mut behaviour = new_behaviour(anonymous_config().check_explicit_peers_ticks(1));
+
+        // An unreachable explicit peer
+        let peer_id = PeerId::random();
+        behaviour.add_explicit_peer(&peer_id);
+
+        // When
+        for heartbeat_ticks in 1..=10 {
+            behaviour.on_heartbeat(heartbeat_ticks);
+        }
+
+        // Then
+        let dials = behaviour
+            .events
+            .iter()
+            .filter(|event| matches!(event, ToSwarm::Dial { .. }))
+            .count();
+        assert_eq!(
+            dials, 1,
+            "the peer should only be dialed once within the backoff"
+        );
+
+        let (_, backoff) = behaviour
+            .explicit_peer_backoffs
+            .get(&peer_id)
+            .expect("explicit peer backoff");
+        assert_eq!(*backoff, behaviour.config.heartbeat_interval());
+    }
+
+    #[test]
+    fn connections_rejected_by_the_peer_acceptance_policy_are_denied() {
+        // Given
+        let mut behaviour = new_behaviour(&anonymous_config());
+
+        let rejected_peer = PeerId::random();
+        let accepted_peer = PeerId::random();
+        behaviour.set_peer_acceptance_policy(CallbackPeerAcceptancePolicy(
+            move |peer_id: &PeerId, _: &Multiaddr| *peer_id != rejected_peer,
+        ));
+
+        let local_addr: Multiaddr = "/ip4/127.0.0.1/tcp/60000".parse().unwrap();
+        let remote_addr: Multiaddr = "/ip4/192.0.2.1/tcp/60000".parse().unwrap();
+
+        // When
+        let rejected_inbound = behaviour.handle_established_inbound_connection(
+            ConnectionId::new_unchecked(0),
+            rejected_peer,
+            &local_addr,
+            &remote_addr,
+        );
+        let rejected_outbound = behaviour.handle_established_outbound_connection(
+            ConnectionId::new_unchecked(1),
+            rejected_peer,
+            &remote_addr,
+            Endpoint::Dialer,
+        );
+        let accepted_inbound = behaviour.handle_established_inbound_connection(
+            ConnectionId::new_unchecked(2),
+            accepted_peer,
+            &local_addr,
+            &remote_addr,
+        );
+
+        // Then
+        assert!(rejected_inbound.is_err());
+        assert!(rejected_outbound.is_err());
+        assert!(accepted_inbound.is_ok());
+    }
+
+    #[test]
+    fn connection_handlers_use_the_direction_idle_timeout() {
+        // Given
+        let inbound_idle_timeout = Duration::from_secs(30);
+        let outbound_idle_timeout = Duration::from_secs(600);
+        let mut behaviour = new_behaviour(
+            anonymous_config()
+                .inbound_idle_timeout(inbound_idle_timeout)
+                .outbound_idle_timeout(outbound_idle_timeout),
+        );
+
+        let local_addr: Multiaddr = "/ip4/127.0.0.1/tcp/60000".parse().unwrap();
+        let remote_addr: Multiaddr = "/ip4/192.0.2.1/tcp/60000".parse().unwrap();
+
+        // When
+        let inbound_handler = behaviour
+            .handle_established_inbound_connection(
+                ConnectionId::new_unchecked(0),
+                PeerId::random(),
+                &local_addr,
+                &remote_addr,
+            )
+            .expect("inbound connection accepted");
+        let outbound_handler = behaviour
+            .handle_established_outbound_connection(
+                ConnectionId::new_unchecked(1),
+                PeerId::random(),
+                &remote_addr,
+                Endpoint::Dialer,
+            )
+            .expect("outbound connection accepted");
+
+        // Then
+        assert_eq!(inbound_handler.idle_timeout(), Some(inbound_idle_timeout));
+        assert_eq!(outbound_handler.idle_timeout(), Some(outbound_idle_timeout));
+    }
+}
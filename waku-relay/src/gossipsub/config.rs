@@ -27,6 +27,7 @@ use libp2p::identity::Keypair;
 use libp2p::PeerId;
 
 use crate::gossipsub::message_id::{default_message_id_fn, FastMessageId, MessageId};
+use crate::gossipsub::signing::{SignatureKeyType, SigningKey};
 use crate::gossipsub::types::{Message, RawMessage};
 
 /// Determines if published messages should be signed or not.
@@ -40,6 +41,10 @@ pub enum MessageAuthenticity {
     /// Message signing is enabled. The author will be the owner of the key and the sequence number
     /// will be linearly increasing.
     Signed(Keypair),
+    /// Message signing is enabled and delegated to an external [`SigningKey`], e.g. a hardware
+    /// security module. The author will be the owner of the key and the sequence number will be
+    /// linearly increasing.
+    ExternalSigned(Arc<dyn SigningKey + Send + Sync>),
     /// Message signing is disabled.
     ///
     /// The specified [`PeerId`] will be used as the author of all published messages. The sequence
@@ -65,7 +70,10 @@ pub enum MessageAuthenticity {
 impl MessageAuthenticity {
     /// Returns true if signing is enabled.
     pub fn is_signing(&self) -> bool {
-        matches!(self, MessageAuthenticity::Signed(_))
+        matches!(
+            self,
+            MessageAuthenticity::Signed(_) | MessageAuthenticity::ExternalSigned(_)
+        )
     }
 
     pub fn is_anonymous(&self) -> bool {
@@ -110,6 +118,7 @@ pub struct Config {
     custom_id_version: Option<Version>,
     history_length: usize,
     history_gossip: usize,
+    history_max_bytes: Option<usize>,
     mesh_n: usize,
     mesh_n_low: usize,
     mesh_n_high: usize,
@@ -119,12 +128,19 @@ pub struct Config {
     heartbeat_initial_delay: Duration,
     heartbeat_interval: Duration,
     fanout_ttl: Duration,
+    fanout_join_threshold: Option<usize>,
     check_explicit_peers_ticks: u64,
+    explicit_peer_max_backoff: Duration,
     max_transmit_size: usize,
     idle_timeout: Duration,
+    inbound_idle_timeout: Option<Duration>,
+    outbound_idle_timeout: Option<Duration>,
+    prioritize_control_messages: bool,
+    send_queue_high_water_mark: Option<usize>,
     duplicate_cache_time: Duration,
     validate_messages: bool,
     validation_mode: ValidationMode,
+    signature_key_type: Option<SignatureKeyType>,
     message_id_fn: Arc<dyn Fn(&Message) -> MessageId + Send + Sync + 'static>,
     fast_message_id_fn: Option<Arc<dyn Fn(&RawMessage) -> FastMessageId + Send + Sync + 'static>>,
     allow_self_origin: bool,
@@ -134,16 +150,29 @@ pub struct Config {
     unsubscribe_backoff: Duration,
     backoff_slack: u32,
     flood_publish: bool,
+    republish_duplicates: bool,
     graft_flood_threshold: Duration,
     mesh_outbound_min: usize,
+    px_outbound_probation: Option<Duration>,
     opportunistic_graft_ticks: u64,
     opportunistic_graft_peers: usize,
     gossip_retransimission: u32,
     max_messages_per_rpc: Option<usize>,
+    max_ihave_per_rpc: Option<usize>,
+    max_iwant_per_rpc: Option<usize>,
+    max_graft_per_rpc: Option<usize>,
+    max_prune_per_rpc: Option<usize>,
+    max_iwant_response_messages: Option<usize>,
+    max_forward_peers: Option<usize>,
+    eclipse_detection_threshold: Option<f64>,
     max_ihave_length: usize,
     max_ihave_messages: usize,
+    max_control_pool_length: usize,
+    disconnect_drain_time: Duration,
     iwant_followup_time: Duration,
     support_floodsub: bool,
+    rebroadcast_unsubscriptions: bool,
+    unsubscription_rebroadcast_time: Duration,
     published_message_ids_cache_time: Duration,
 }
 
@@ -177,6 +206,13 @@ impl Config {
         self.history_gossip
     }
 
+    /// Maximum summed size in bytes of the messages data kept in the `memcache`. When exceeded,
+    /// the oldest messages are evicted before their heartbeats expire. If this is unset, there is
+    /// no limit (default is None).
+    pub fn history_max_bytes(&self) -> Option<usize> {
+        self.history_max_bytes
+    }
+
     /// Target number of peers for the mesh network (D in the spec, default is 6).
     pub fn mesh_n(&self) -> usize {
         self.mesh_n
@@ -230,12 +266,27 @@ impl Config {
         self.fanout_ttl
     }
 
+    /// The number of messages published to a fanout topic, before its fanout peers expire, after
+    /// which the node joins the topic mesh. Frequently published topics are better served by the
+    /// mesh, as the published messages are also relayed through it. The default is None, never
+    /// joining a fanout topic mesh.
+    pub fn fanout_join_threshold(&self) -> Option<usize> {
+        self.fanout_join_threshold
+    }
+
     /// The number of heartbeat ticks until we recheck the connection to explicit peers and
     /// reconnecting if necessary (default 300).
     pub fn check_explicit_peers_ticks(&self) -> u64 {
         self.check_explicit_peers_ticks
     }
 
+    /// The maximum backoff between reconnection attempts to an explicit peer. The backoff starts
+    /// at `heartbeat_interval` and doubles after each failed attempt, up to this value. It is reset
+    /// once a connection to the peer is established (default is 300 seconds).
+    pub fn explicit_peer_max_backoff(&self) -> Duration {
+        self.explicit_peer_max_backoff
+    }
+
     /// The maximum byte size for each gossipsub RPC (default is 65536 bytes).
     ///
     /// This represents the maximum size of the entire protobuf payload. It must be at least
@@ -253,6 +304,31 @@ impl Config {
         self.idle_timeout
     }
 
+    /// The idle timeout of the connections established by a remote peer (inbound connections).
+    /// The default is the [`Config::idle_timeout`].
+    pub fn inbound_idle_timeout(&self) -> Duration {
+        self.inbound_idle_timeout.unwrap_or(self.idle_timeout)
+    }
+
+    /// The idle timeout of the connections dialed by the local node (outbound connections).
+    /// The default is the [`Config::idle_timeout`].
+    pub fn outbound_idle_timeout(&self) -> Duration {
+        self.outbound_idle_timeout.unwrap_or(self.idle_timeout)
+    }
+
+    /// Whether the connection handler sends pending control messages (GRAFT, PRUNE, IHAVE, IWANT)
+    /// and subscriptions before any pending published message. Default is true.
+    pub fn prioritize_control_messages(&self) -> bool {
+        self.prioritize_control_messages
+    }
+
+    /// The maximum number of RPCs pending to be sent to a peer, per connection, past which new
+    /// RPCs carrying published messages are dropped. Control messages and subscriptions are always
+    /// queued. This bounds the memory held for slow peers. The default is `None` (unbounded).
+    pub fn send_queue_high_water_mark(&self) -> Option<usize> {
+        self.send_queue_high_water_mark
+    }
+
     /// Duplicates are prevented by storing message id's of known messages in an LRU time cache.
     /// This settings sets the time period that messages are stored in the cache. Duplicates can be
     /// received if duplicate messages are sent at a time greater than this setting apart. The
@@ -276,6 +352,14 @@ impl Config {
         &self.validation_mode
     }
 
+    /// The key type received messages must be signed with under the [`ValidationMode::Strict`]
+    /// validation mode, e.g. only secp256k1 keys as used by Waku nodes. If set, the behaviour
+    /// construction fails if the local signing key is of a different type. If this is unset,
+    /// messages signed with any key type are accepted. The default is None.
+    pub fn signature_key_type(&self) -> Option<SignatureKeyType> {
+        self.signature_key_type
+    }
+
     /// A user-defined function allowing the user to specify the message id of a gossipsub message.
     /// The default value is to concatenate the source peer id with a sequence number. Setting this
     /// parameter allows the user to address packets arbitrarily. One example is content based
@@ -364,6 +448,13 @@ impl Config {
         self.flood_publish
     }
 
+    /// Whether publishing an already seen message re-sends it to the mesh peers instead of
+    /// returning [`crate::gossipsub::PublishError::Duplicate`]. This is useful to retry the
+    /// propagation of a message that reached too few peers. The default is false.
+    pub fn republish_duplicates(&self) -> bool {
+        self.republish_duplicates
+    }
+
     /// If a GRAFT comes before `graft_flood_threshold` has elapsed since the last PRUNE,
     /// then there is an extra score penalty applied to the peer through P7.
     pub fn graft_flood_threshold(&self) -> Duration {
@@ -371,12 +462,19 @@ impl Config {
     }
 
     /// Minimum number of outbound peers in the mesh network before adding more (D_out in the spec).
-    /// This value must be smaller or equal than `mesh_n / 2` and smaller than `mesh_n_low`.
+    /// This value must be smaller or equal than `mesh_n_low / 2`.
     /// The default is 2.
     pub fn mesh_outbound_min(&self) -> usize {
         self.mesh_outbound_min
     }
 
+    /// The time a peer discovered through peer exchange, and dialed by us, must stay connected
+    /// before it is counted as an outbound peer towards `mesh_outbound_min`. If this is unset, peer
+    /// exchange peers are never counted as outbound peers. The default is None.
+    pub fn px_outbound_probation(&self) -> Option<Duration> {
+        self.px_outbound_probation
+    }
+
     /// Number of heartbeat ticks that specifcy the interval in which opportunistic grafting is
     /// applied. Every `opportunistic_graft_ticks` we will attempt to select some high-scoring mesh
     /// peers to replace lower-scoring ones, if the median score of our mesh peers falls below a
@@ -404,6 +502,57 @@ impl Config {
         self.max_messages_per_rpc
     }
 
+    /// The maximum number of IHAVE control entries we will process in a given RPC. Excess entries
+    /// are dropped and the sender receives a behaviour penalty. If this is unset, there is no
+    /// limit. The default is None.
+    pub fn max_ihave_per_rpc(&self) -> Option<usize> {
+        self.max_ihave_per_rpc
+    }
+
+    /// The maximum number of IWANT control entries we will process in a given RPC. Excess entries
+    /// are dropped and the sender receives a behaviour penalty. If this is unset, there is no
+    /// limit. The default is None.
+    pub fn max_iwant_per_rpc(&self) -> Option<usize> {
+        self.max_iwant_per_rpc
+    }
+
+    /// The maximum number of GRAFT control entries we will process in a given RPC. Excess entries
+    /// are dropped and the sender receives a behaviour penalty. If this is unset, there is no
+    /// limit. The default is None.
+    pub fn max_graft_per_rpc(&self) -> Option<usize> {
+        self.max_graft_per_rpc
+    }
+
+    /// The maximum number of PRUNE control entries we will process in a given RPC. Excess entries
+    /// are dropped and the sender receives a behaviour penalty. If this is unset, there is no
+    /// limit. The default is None.
+    pub fn max_prune_per_rpc(&self) -> Option<usize> {
+        self.max_prune_per_rpc
+    }
+
+    /// The maximum number of messages we will send in response to the IWANT control entries of a
+    /// given RPC. Excess requested message ids are ignored and the requester receives a behaviour
+    /// penalty. If this is unset, there is no limit. The default is None.
+    pub fn max_iwant_response_messages(&self) -> Option<usize> {
+        self.max_iwant_response_messages
+    }
+
+    /// The maximum number of peers a received message is forwarded to. If the mesh and explicit
+    /// peers exceed this limit, the highest-scored peers are selected. If this is unset, there is
+    /// no limit. The default is None.
+    pub fn max_forward_peers(&self) -> Option<usize> {
+        self.max_forward_peers
+    }
+
+    /// The fraction of a topic's mesh peers that, when sharing the same IP subnet or being learned
+    /// via peer exchange, flags the mesh as possibly eclipsed and emits an
+    /// [`crate::gossipsub::Event::PossibleEclipse`] event. The check runs on each heartbeat for
+    /// meshes with at least `mesh_n_low` peers. If this is unset, the detection is disabled. The
+    /// default is None.
+    pub fn eclipse_detection_threshold(&self) -> Option<f64> {
+        self.eclipse_detection_threshold
+    }
+
     /// The maximum number of messages to include in an IHAVE message.
     /// Also controls the maximum number of IHAVE ids we will accept and request with IWANT from a
     /// peer within a heartbeat, to protect from IHAVE floods. You should adjust this value from the
@@ -419,6 +568,20 @@ impl Config {
         self.max_ihave_messages
     }
 
+    /// The maximum number of control actions pooled for a peer between heartbeats. When the limit
+    /// is reached, the oldest pooled action is dropped. The default is 1000.
+    pub fn max_control_pool_length(&self) -> usize {
+        self.max_control_pool_length
+    }
+
+    /// The time given to the connection handler to send the pending messages of a peer
+    /// disconnected via [`crate::gossipsub::Behaviour::disconnect_peer()`] before the connection
+    /// is closed. The connection is closed on the first heartbeat past this time. The default is
+    /// 500 milliseconds.
+    pub fn disconnect_drain_time(&self) -> Duration {
+        self.disconnect_drain_time
+    }
+
     /// Time to wait for a message requested through IWANT following an IHAVE advertisement.
     /// If the message is not received within this window, a broken promise is declared and
     /// the router may apply behavioural penalties. The default is 3 seconds.
@@ -431,6 +594,20 @@ impl Config {
         self.support_floodsub
     }
 
+    /// Whether the subscriptions sent to a newly connected peer also announce the topics we have
+    /// unsubscribed from. This clears any stale subscription state a reconnecting peer may keep
+    /// from a previous connection. If disabled, only the active subscriptions are sent. The
+    /// default is false.
+    pub fn rebroadcast_unsubscriptions(&self) -> bool {
+        self.rebroadcast_unsubscriptions
+    }
+
+    /// For how long an unsubscribed topic is announced to newly connected peers, when
+    /// [`Config::rebroadcast_unsubscriptions()`] is enabled. The default is 60 seconds.
+    pub fn unsubscription_rebroadcast_time(&self) -> Duration {
+        self.unsubscription_rebroadcast_time
+    }
+
     /// Published message ids time cache duration. The default is 10 seconds.
     pub fn published_message_ids_cache_time(&self) -> Duration {
         self.published_message_ids_cache_time
@@ -453,6 +630,7 @@ impl fmt::Debug for Config {
         let _ = builder.field("custom_id_version", &self.custom_id_version);
         let _ = builder.field("history_length", &self.history_length);
         let _ = builder.field("history_gossip", &self.history_gossip);
+        let _ = builder.field("history_max_bytes", &self.history_max_bytes);
         let _ = builder.field("mesh_n", &self.mesh_n);
         let _ = builder.field("mesh_n_low", &self.mesh_n_low);
         let _ = builder.field("mesh_n_high", &self.mesh_n_high);
@@ -462,26 +640,64 @@ impl fmt::Debug for Config {
         let _ = builder.field("heartbeat_initial_delay", &self.heartbeat_initial_delay);
         let _ = builder.field("heartbeat_interval", &self.heartbeat_interval);
         let _ = builder.field("fanout_ttl", &self.fanout_ttl);
+        let _ = builder.field("fanout_join_threshold", &self.fanout_join_threshold);
+        let _ = builder.field("explicit_peer_max_backoff", &self.explicit_peer_max_backoff);
         let _ = builder.field("max_transmit_size", &self.max_transmit_size);
         let _ = builder.field("idle_timeout", &self.idle_timeout);
+        let _ = builder.field("inbound_idle_timeout", &self.inbound_idle_timeout);
+        let _ = builder.field("outbound_idle_timeout", &self.outbound_idle_timeout);
+        let _ = builder.field(
+            "prioritize_control_messages",
+            &self.prioritize_control_messages,
+        );
+        let _ = builder.field(
+            "send_queue_high_water_mark",
+            &self.send_queue_high_water_mark,
+        );
         let _ = builder.field("duplicate_cache_time", &self.duplicate_cache_time);
         let _ = builder.field("validate_messages", &self.validate_messages);
         let _ = builder.field("validation_mode", &self.validation_mode);
+        let _ = builder.field("signature_key_type", &self.signature_key_type);
         let _ = builder.field("allow_self_origin", &self.allow_self_origin);
         let _ = builder.field("do_px", &self.do_px);
         let _ = builder.field("prune_peers", &self.prune_peers);
         let _ = builder.field("prune_backoff", &self.prune_backoff);
         let _ = builder.field("backoff_slack", &self.backoff_slack);
         let _ = builder.field("flood_publish", &self.flood_publish);
+        let _ = builder.field("republish_duplicates", &self.republish_duplicates);
         let _ = builder.field("graft_flood_threshold", &self.graft_flood_threshold);
         let _ = builder.field("mesh_outbound_min", &self.mesh_outbound_min);
+        let _ = builder.field("px_outbound_probation", &self.px_outbound_probation);
         let _ = builder.field("opportunistic_graft_ticks", &self.opportunistic_graft_ticks);
         let _ = builder.field("opportunistic_graft_peers", &self.opportunistic_graft_peers);
         let _ = builder.field("max_messages_per_rpc", &self.max_messages_per_rpc);
+        let _ = builder.field("max_ihave_per_rpc", &self.max_ihave_per_rpc);
+        let _ = builder.field("max_iwant_per_rpc", &self.max_iwant_per_rpc);
+        let _ = builder.field("max_graft_per_rpc", &self.max_graft_per_rpc);
+        let _ = builder.field("max_prune_per_rpc", &self.max_prune_per_rpc);
+        let _ = builder.field(
+            "max_iwant_response_messages",
+            &self.max_iwant_response_messages,
+        );
+        let _ = builder.field("max_forward_peers", &self.max_forward_peers);
+        let _ = builder.field(
+            "eclipse_detection_threshold",
+            &self.eclipse_detection_threshold,
+        );
         let _ = builder.field("max_ihave_length", &self.max_ihave_length);
         let _ = builder.field("max_ihave_messages", &self.max_ihave_messages);
+        let _ = builder.field("max_control_pool_length", &self.max_control_pool_length);
+        let _ = builder.field("disconnect_drain_time", &self.disconnect_drain_time);
         let _ = builder.field("iwant_followup_time", &self.iwant_followup_time);
         let _ = builder.field("support_floodsub", &self.support_floodsub);
+        let _ = builder.field(
+            "rebroadcast_unsubscriptions",
+            &self.rebroadcast_unsubscriptions,
+        );
+        let _ = builder.field(
+            "unsubscription_rebroadcast_time",
+            &self.unsubscription_rebroadcast_time,
+        );
         let _ = builder.field(
             "published_message_ids_cache_time",
             &self.published_message_ids_cache_time,
@@ -503,6 +719,7 @@ impl Default for ConfigBuilder {
                 custom_id_version: None,
                 history_length: 5,
                 history_gossip: 3,
+                history_max_bytes: None,
                 mesh_n: 6,
                 mesh_n_low: 5,
                 mesh_n_high: 12,
@@ -512,31 +729,51 @@ impl Default for ConfigBuilder {
                 heartbeat_initial_delay: Duration::from_secs(5),
                 heartbeat_interval: Duration::from_secs(1),
                 fanout_ttl: Duration::from_secs(60),
+                fanout_join_threshold: None,
                 check_explicit_peers_ticks: 300,
+                explicit_peer_max_backoff: Duration::from_secs(300),
                 max_transmit_size: 65536,
                 idle_timeout: Duration::from_secs(120),
+                inbound_idle_timeout: None,
+                outbound_idle_timeout: None,
+                prioritize_control_messages: true,
+                send_queue_high_water_mark: None,
                 duplicate_cache_time: Duration::from_secs(60),
                 validate_messages: false,
                 validation_mode: ValidationMode::Strict,
+                signature_key_type: None,
                 message_id_fn: Arc::new(default_message_id_fn),
                 fast_message_id_fn: None,
                 allow_self_origin: false,
                 do_px: false,
-                prune_peers: 0, // NOTE: Increasing this currently has little effect until Signed records are implemented.
+                prune_peers: 0, // NOTE: The PX peers we send carry no signed peer record, only their peer id.
                 prune_backoff: Duration::from_secs(60),
                 unsubscribe_backoff: Duration::from_secs(10),
                 backoff_slack: 1,
                 flood_publish: true,
+                republish_duplicates: false,
                 graft_flood_threshold: Duration::from_secs(10),
                 mesh_outbound_min: 2,
+                px_outbound_probation: None,
                 opportunistic_graft_ticks: 60,
                 opportunistic_graft_peers: 2,
                 gossip_retransimission: 3,
                 max_messages_per_rpc: None,
+                max_ihave_per_rpc: None,
+                max_iwant_per_rpc: None,
+                max_graft_per_rpc: None,
+                max_prune_per_rpc: None,
+                max_iwant_response_messages: None,
+                max_forward_peers: None,
+                eclipse_detection_threshold: None,
                 max_ihave_length: 5000,
                 max_ihave_messages: 10,
+                max_control_pool_length: 1000,
+                disconnect_drain_time: Duration::from_millis(500),
                 iwant_followup_time: Duration::from_secs(3),
                 support_floodsub: false,
+                rebroadcast_unsubscriptions: false,
+                unsubscription_rebroadcast_time: Duration::from_secs(60),
                 published_message_ids_cache_time: Duration::from_secs(10),
             },
         }
@@ -583,6 +820,14 @@ impl ConfigBuilder {
         self
     }
 
+    /// Maximum summed size in bytes of the messages data kept in the `memcache`. When exceeded,
+    /// the oldest messages are evicted before their heartbeats expire. If this is unset, there is
+    /// no limit (default is None).
+    pub fn history_max_bytes(&mut self, max_bytes: Option<usize>) -> &mut Self {
+        self.config.history_max_bytes = max_bytes;
+        self
+    }
+
     /// Target number of peers for the mesh network (D in the spec, default is 6).
     pub fn mesh_n(&mut self, mesh_n: usize) -> &mut Self {
         self.config.mesh_n = mesh_n;
@@ -646,12 +891,29 @@ impl ConfigBuilder {
         self
     }
 
+    /// The maximum backoff between reconnection attempts to an explicit peer. The backoff starts
+    /// at `heartbeat_interval` and doubles after each failed attempt, up to this value. It is reset
+    /// once a connection to the peer is established (default is 300 seconds).
+    pub fn explicit_peer_max_backoff(&mut self, explicit_peer_max_backoff: Duration) -> &mut Self {
+        self.config.explicit_peer_max_backoff = explicit_peer_max_backoff;
+        self
+    }
+
     /// Time to live for fanout peers (default is 60 seconds).
     pub fn fanout_ttl(&mut self, fanout_ttl: Duration) -> &mut Self {
         self.config.fanout_ttl = fanout_ttl;
         self
     }
 
+    /// The number of messages published to a fanout topic, before its fanout peers expire, after
+    /// which the node joins the topic mesh. Frequently published topics are better served by the
+    /// mesh, as the published messages are also relayed through it. The default is None, never
+    /// joining a fanout topic mesh.
+    pub fn fanout_join_threshold(&mut self, threshold: Option<usize>) -> &mut Self {
+        self.config.fanout_join_threshold = threshold;
+        self
+    }
+
     /// The maximum byte size for each gossip (default is 2048 bytes).
     pub fn max_transmit_size(&mut self, max_transmit_size: usize) -> &mut Self {
         self.config.max_transmit_size = max_transmit_size;
@@ -666,6 +928,35 @@ impl ConfigBuilder {
         self
     }
 
+    /// The idle timeout of the connections established by a remote peer (inbound connections).
+    /// The default is the [`Config::idle_timeout`].
+    pub fn inbound_idle_timeout(&mut self, inbound_idle_timeout: Duration) -> &mut Self {
+        self.config.inbound_idle_timeout = Some(inbound_idle_timeout);
+        self
+    }
+
+    /// The idle timeout of the connections dialed by the local node (outbound connections).
+    /// The default is the [`Config::idle_timeout`].
+    pub fn outbound_idle_timeout(&mut self, outbound_idle_timeout: Duration) -> &mut Self {
+        self.config.outbound_idle_timeout = Some(outbound_idle_timeout);
+        self
+    }
+
+    /// Whether the connection handler sends pending control messages (GRAFT, PRUNE, IHAVE, IWANT)
+    /// and subscriptions before any pending published message. Default is true.
+    pub fn prioritize_control_messages(&mut self, prioritize_control_messages: bool) -> &mut Self {
+        self.config.prioritize_control_messages = prioritize_control_messages;
+        self
+    }
+
+    /// The maximum number of RPCs pending to be sent to a peer, per connection, past which new
+    /// RPCs carrying published messages are dropped. Control messages and subscriptions are always
+    /// queued. This bounds the memory held for slow peers. The default is `None` (unbounded).
+    pub fn send_queue_high_water_mark(&mut self, high_water_mark: Option<usize>) -> &mut Self {
+        self.config.send_queue_high_water_mark = high_water_mark;
+        self
+    }
+
     /// Duplicates are prevented by storing message id's of known messages in an LRU time cache.
     /// This settings sets the time period that messages are stored in the cache. Duplicates can be
     /// received if duplicate messages are sent at a time greater than this setting apart. The
@@ -691,6 +982,15 @@ impl ConfigBuilder {
         self
     }
 
+    /// The key type received messages must be signed with under the [`ValidationMode::Strict`]
+    /// validation mode, e.g. only secp256k1 keys as used by Waku nodes. If set, the behaviour
+    /// construction fails if the local signing key is of a different type. If this is unset,
+    /// messages signed with any key type are accepted. The default is None.
+    pub fn signature_key_type(&mut self, key_type: Option<SignatureKeyType>) -> &mut Self {
+        self.config.signature_key_type = key_type;
+        self
+    }
+
     /// A user-defined function allowing the user to specify the message id of a gossipsub message.
     /// The default value is to concatenate the source peer id with a sequence number. Setting this
     /// parameter allows the user to address packets arbitrarily. One example is content based
@@ -784,6 +1084,14 @@ impl ConfigBuilder {
         self
     }
 
+    /// Whether publishing an already seen message re-sends it to the mesh peers instead of
+    /// returning [`crate::gossipsub::PublishError::Duplicate`]. This is useful to retry the
+    /// propagation of a message that reached too few peers. The default is false.
+    pub fn republish_duplicates(&mut self, republish_duplicates: bool) -> &mut Self {
+        self.config.republish_duplicates = republish_duplicates;
+        self
+    }
+
     /// If a GRAFT comes before `graft_flood_threshold` has elapsed since the last PRUNE,
     /// then there is an extra score penalty applied to the peer through P7.
     pub fn graft_flood_threshold(&mut self, graft_flood_threshold: Duration) -> &mut Self {
@@ -792,13 +1100,21 @@ impl ConfigBuilder {
     }
 
     /// Minimum number of outbound peers in the mesh network before adding more (D_out in the spec).
-    /// This value must be smaller or equal than `mesh_n / 2` and smaller than `mesh_n_low`.
+    /// This value must be smaller or equal than `mesh_n_low / 2`.
     /// The default is 2.
     pub fn mesh_outbound_min(&mut self, mesh_outbound_min: usize) -> &mut Self {
         self.config.mesh_outbound_min = mesh_outbound_min;
         self
     }
 
+    /// The time a peer discovered through peer exchange, and dialed by us, must stay connected
+    /// before it is counted as an outbound peer towards `mesh_outbound_min`. If this is unset, peer
+    /// exchange peers are never counted as outbound peers. The default is None.
+    pub fn px_outbound_probation(&mut self, probation: Option<Duration>) -> &mut Self {
+        self.config.px_outbound_probation = probation;
+        self
+    }
+
     /// Number of heartbeat ticks that specifcy the interval in which opportunistic grafting is
     /// applied. Every `opportunistic_graft_ticks` we will attempt to select some high-scoring mesh
     /// peers to replace lower-scoring ones, if the median score of our mesh peers falls below a
@@ -830,6 +1146,64 @@ impl ConfigBuilder {
         self
     }
 
+    /// The maximum number of IHAVE control entries we will process in a given RPC. Excess entries
+    /// are dropped and the sender receives a behaviour penalty. If this is unset, there is no
+    /// limit. The default is None.
+    pub fn max_ihave_per_rpc(&mut self, max: Option<usize>) -> &mut Self {
+        self.config.max_ihave_per_rpc = max;
+        self
+    }
+
+    /// The maximum number of IWANT control entries we will process in a given RPC. Excess entries
+    /// are dropped and the sender receives a behaviour penalty. If this is unset, there is no
+    /// limit. The default is None.
+    pub fn max_iwant_per_rpc(&mut self, max: Option<usize>) -> &mut Self {
+        self.config.max_iwant_per_rpc = max;
+        self
+    }
+
+    /// The maximum number of GRAFT control entries we will process in a given RPC. Excess entries
+    /// are dropped and the sender receives a behaviour penalty. If this is unset, there is no
+    /// limit. The default is None.
+    pub fn max_graft_per_rpc(&mut self, max: Option<usize>) -> &mut Self {
+        self.config.max_graft_per_rpc = max;
+        self
+    }
+
+    /// The maximum number of PRUNE control entries we will process in a given RPC. Excess entries
+    /// are dropped and the sender receives a behaviour penalty. If this is unset, there is no
+    /// limit. The default is None.
+    pub fn max_prune_per_rpc(&mut self, max: Option<usize>) -> &mut Self {
+        self.config.max_prune_per_rpc = max;
+        self
+    }
+
+    /// The maximum number of messages we will send in response to the IWANT control entries of a
+    /// given RPC. Excess requested message ids are ignored and the requester receives a behaviour
+    /// penalty. If this is unset, there is no limit. The default is None.
+    pub fn max_iwant_response_messages(&mut self, max: Option<usize>) -> &mut Self {
+        self.config.max_iwant_response_messages = max;
+        self
+    }
+
+    /// The maximum number of peers a received message is forwarded to. If the mesh and explicit
+    /// peers exceed this limit, the highest-scored peers are selected. If this is unset, there is
+    /// no limit. The default is None.
+    pub fn max_forward_peers(&mut self, max: Option<usize>) -> &mut Self {
+        self.config.max_forward_peers = max;
+        self
+    }
+
+    /// The fraction of a topic's mesh peers that, when sharing the same IP subnet or being learned
+    /// via peer exchange, flags the mesh as possibly eclipsed and emits an
+    /// [`crate::gossipsub::Event::PossibleEclipse`] event. The check runs on each heartbeat for
+    /// meshes with at least `mesh_n_low` peers. If this is unset, the detection is disabled. The
+    /// default is None.
+    pub fn eclipse_detection_threshold(&mut self, threshold: Option<f64>) -> &mut Self {
+        self.config.eclipse_detection_threshold = threshold;
+        self
+    }
+
     /// The maximum number of messages to include in an IHAVE message.
     /// Also controls the maximum number of IHAVE ids we will accept and request with IWANT from a
     /// peer within a heartbeat, to protect from IHAVE floods. You should adjust this value from the
@@ -847,6 +1221,22 @@ impl ConfigBuilder {
         self
     }
 
+    /// The maximum number of control actions pooled for a peer between heartbeats. When the limit
+    /// is reached, the oldest pooled action is dropped. The default is 1000.
+    pub fn max_control_pool_length(&mut self, max_control_pool_length: usize) -> &mut Self {
+        self.config.max_control_pool_length = max_control_pool_length;
+        self
+    }
+
+    /// The time given to the connection handler to send the pending messages of a peer
+    /// disconnected via [`crate::gossipsub::Behaviour::disconnect_peer()`] before the connection
+    /// is closed. The connection is closed on the first heartbeat past this time. The default is
+    /// 500 milliseconds.
+    pub fn disconnect_drain_time(&mut self, disconnect_drain_time: Duration) -> &mut Self {
+        self.config.disconnect_drain_time = disconnect_drain_time;
+        self
+    }
+
     /// By default, gossipsub will reject messages that are sent to us that has the same message
     /// source as we have specified locally. Enabling this, allows these messages and prevents
     /// penalizing the peer that sent us the message. Default is false.
@@ -869,6 +1259,25 @@ impl ConfigBuilder {
         self
     }
 
+    /// Whether the subscriptions sent to a newly connected peer also announce the topics we have
+    /// unsubscribed from. This clears any stale subscription state a reconnecting peer may keep
+    /// from a previous connection. If disabled, only the active subscriptions are sent. The
+    /// default is false.
+    pub fn rebroadcast_unsubscriptions(&mut self, rebroadcast_unsubscriptions: bool) -> &mut Self {
+        self.config.rebroadcast_unsubscriptions = rebroadcast_unsubscriptions;
+        self
+    }
+
+    /// For how long an unsubscribed topic is announced to newly connected peers, when
+    /// [`Config::rebroadcast_unsubscriptions()`] is enabled. The default is 60 seconds.
+    pub fn unsubscription_rebroadcast_time(
+        &mut self,
+        unsubscription_rebroadcast_time: Duration,
+    ) -> &mut Self {
+        self.config.unsubscription_rebroadcast_time = unsubscription_rebroadcast_time;
+        self
+    }
+
     /// Published message ids time cache duration. The default is 10 seconds.
     pub fn published_message_ids_cache_time(
         &mut self,
@@ -896,20 +1305,27 @@ impl ConfigBuilder {
             );
         }
 
-        if !(self.config.mesh_outbound_min <= self.config.mesh_n_low
-            && self.config.mesh_n_low <= self.config.mesh_n
-            && self.config.mesh_n <= self.config.mesh_n_high)
-        {
+        if self.config.mesh_n_low > self.config.mesh_n {
+            anyhow::bail!(
+                "The mesh_n_low ({}) must be less than or equal to the mesh_n ({})",
+                self.config.mesh_n_low,
+                self.config.mesh_n
+            );
+        }
+
+        if self.config.mesh_n > self.config.mesh_n_high {
             anyhow::bail!(
-                "The following inequality doesn't hold \
-                mesh_outbound_min <= mesh_n_low <= mesh_n <= mesh_n_high"
+                "The mesh_n ({}) must be less than or equal to the mesh_n_high ({})",
+                self.config.mesh_n,
+                self.config.mesh_n_high
             );
         }
 
-        if self.config.mesh_outbound_min * 2 > self.config.mesh_n {
+        if self.config.mesh_outbound_min > self.config.mesh_n_low / 2 {
             anyhow::bail!(
-                "The following inequality doesn't hold \
-                mesh_outbound_min <= self.config.mesh_n / 2",
+                "The mesh_outbound_min ({}) must be less than or equal to half the mesh_n_low ({})",
+                self.config.mesh_outbound_min,
+                self.config.mesh_n_low
             );
         }
 
@@ -917,6 +1333,13 @@ impl ConfigBuilder {
             anyhow::bail!("The unsubscribe_backoff parameter should be positive.");
         }
 
+        if let Some(threshold) = self.config.eclipse_detection_threshold {
+            if !(threshold > 0.0 && threshold <= 1.0) {
+                anyhow::bail!("The eclipse_detection_threshold must be in the (0, 1] range.");
+            }
+        }
+
         Ok(self.config.clone())
     }
 }
+
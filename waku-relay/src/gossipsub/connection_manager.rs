@@ -99,6 +99,13 @@ impl ConnectionManager {
         }
     }
 
+    /// Mark a connected peer as an outbound peer.
+    pub(crate) fn set_outbound(&mut self, peer_id: &PeerId) {
+        if self.peers.contains_key(peer_id) {
+            self.outbound_peers.insert(*peer_id);
+        }
+    }
+
     pub(crate) fn set_kind(&mut self, peer_id: &PeerId, kind: PeerKind) {
         if let Some(peer) = self.peers.get_mut(peer_id) {
             peer.kind = kind;
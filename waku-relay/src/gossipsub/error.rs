@@ -22,7 +22,7 @@
 
 use std::io;
 
-use libp2p::identity::SigningError;
+use libp2p::identity::{PeerId, SigningError};
 
 /// Error associated with publishing a gossipsub message.
 #[derive(Debug)]
@@ -56,6 +56,13 @@ impl std::error::Error for PublishError {
     }
 }
 
+/// Error reported when a connection is denied by the [`crate::gossipsub::PeerAcceptancePolicy`].
+#[derive(Debug, thiserror::Error)]
+#[error("connection with peer {peer_id} rejected by the peer acceptance policy")]
+pub struct ConnectionRejected {
+    pub peer_id: PeerId,
+}
+
 /// Error associated with subscribing to a topic.
 #[derive(Debug)]
 pub enum SubscriptionError {
@@ -126,4 +133,31 @@ pub enum MessageValidationError {
     MessageSourcePresent,
     /// The data transformation failed.
     TransformFailed,
+    /// The message was signed with a key type other than the required one.
+    UnsupportedKeyType,
+}
+
+/// Error associated with registering the gossipsub metrics.
+#[derive(Debug, thiserror::Error)]
+pub enum MetricsError {
+    /// The configured metrics prefix is not a valid Prometheus metric name.
+    #[error("invalid metrics prefix: {0}")]
+    InvalidPrefix(String),
+}
+
+/// Error associated with creating a gossipsub [`crate::gossipsub::Behaviour`].
+#[derive(Debug, thiserror::Error)]
+pub enum BehaviourError {
+    /// The configuration is not valid for the message authenticity.
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(&'static str),
+    /// The metrics could not be registered.
+    #[error("metrics registration failed")]
+    Metrics(#[from] MetricsError),
+}
+
+impl From<&'static str> for BehaviourError {
+    fn from(error: &'static str) -> Self {
+        BehaviourError::InvalidConfig(error)
+    }
 }
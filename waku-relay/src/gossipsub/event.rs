@@ -1,5 +1,5 @@
 use crate::gossipsub::{Message, MessageId, TopicHash};
-use libp2p::PeerId;
+use libp2p::{Multiaddr, PeerId};
 
 /// Event that can be emitted by the gossipsub behaviour.
 #[derive(Debug)]
@@ -30,4 +30,32 @@ pub enum Event {
     },
     /// A peer that does not support gossipsub has connected.
     GossipsubNotSupported { peer_id: PeerId },
+    /// The remote address of a connection to a peer has changed.
+    PeerAddressChanged {
+        /// The remote peer.
+        peer_id: PeerId,
+        /// The previous remote address of the connection.
+        old: Multiaddr,
+        /// The new remote address of the connection.
+        new: Multiaddr,
+    },
+    /// The mesh of a topic is dominated by peers sharing an IP subnet or learned via peer
+    /// exchange, which may indicate an eclipse attack.
+    PossibleEclipse {
+        /// The topic whose mesh may be eclipsed.
+        topic: TopicHash,
+    },
+    /// The score of a connected peer dropped below the graylist threshold. All the RPCs from the
+    /// peer are ignored until its score recovers.
+    PeerGraylisted { peer_id: PeerId },
+    /// The score of a graylisted peer recovered above the graylist threshold.
+    PeerUngraylisted { peer_id: PeerId },
+    /// A peer sent an RPC with more messages than the configured maximum per RPC. The excess
+    /// messages were dropped and the peer penalized.
+    RpcTruncated {
+        /// The peer that sent the RPC.
+        peer_id: PeerId,
+        /// The number of dropped messages.
+        dropped: usize,
+    },
 }
@@ -19,6 +19,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::{
+    collections::VecDeque,
     pin::Pin,
     task::{Context, Poll},
     time::Duration,
@@ -36,7 +37,7 @@ use libp2p::swarm::handler::{
     SubstreamProtocol,
 };
 use libp2p::swarm::NegotiatedSubstream;
-use smallvec::SmallVec;
+use prost::Message as _;
 use void::Void;
 
 use crate::gossipsub::codec::Codec;
@@ -53,6 +54,11 @@ pub enum HandlerEvent {
     /// An inbound or outbound substream has been established with the peer and this informs over
     /// which protocol. This message only occurs once per connection.
     PeerKind(PeerKind),
+    /// RPCs carrying published messages were dropped, the send queue being past its high-water
+    /// mark.
+    SendQueueDropped(usize),
+    /// RPCs of the given total encoded size, in bytes, were accepted into the send queue.
+    SendQueueAccepted(u64),
 }
 
 /// A message sent from the behaviour to the handler.
@@ -74,6 +80,73 @@ pub enum HandlerIn {
 /// creation loops.
 const MAX_SUBSTREAM_ATTEMPTS: usize = 5;
 
+/// Outbound queue of RPC messages with two priority levels.
+///
+/// RPCs carrying no published messages (control messages and subscriptions) are considered
+/// control RPCs. If prioritization is enabled, these are drained before any pending data RPC.
+/// Within the same priority level, RPCs are sent in FIFO order.
+///
+/// If a high-water mark is set, data RPCs are dropped while the queue length is at or past the
+/// mark. Control RPCs are always queued.
+#[derive(Debug, Default)]
+struct SendQueue {
+    /// Whether control RPCs should be sent before data RPCs.
+    prioritize_control: bool,
+    /// The queue length past which data RPCs are dropped.
+    high_water_mark: Option<usize>,
+    /// Queue of control RPCs.
+    control: VecDeque<RpcProto>,
+    /// Queue of data RPCs. If prioritization is disabled, all RPCs are queued here.
+    data: VecDeque<RpcProto>,
+}
+
+impl SendQueue {
+    fn new(prioritize_control: bool, high_water_mark: Option<usize>) -> Self {
+        Self {
+            prioritize_control,
+            high_water_mark,
+            ..Default::default()
+        }
+    }
+
+    /// Queues the RPC. Returns `false` if the RPC was dropped, the queue being past its
+    /// high-water mark.
+    fn push(&mut self, rpc: RpcProto) -> bool {
+        let is_control = rpc.publish.is_empty();
+        if !is_control
+            && self
+                .high_water_mark
+                .map_or(false, |high_water_mark| self.len() >= high_water_mark)
+        {
+            return false;
+        }
+
+        if self.prioritize_control && is_control {
+            self.control.push_back(rpc);
+        } else {
+            self.data.push_back(rpc);
+        }
+        true
+    }
+
+    fn pop(&mut self) -> Option<RpcProto> {
+        self.control.pop_front().or_else(|| self.data.pop_front())
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.control.shrink_to_fit();
+        self.data.shrink_to_fit();
+    }
+
+    fn is_empty(&self) -> bool {
+        self.control.is_empty() && self.data.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.control.len() + self.data.len()
+    }
+}
+
 /// Protocol Handler that manages a single long-lived substream with a peer.
 pub struct EnabledHandler {
     /// Upgrade configuration for the gossipsub protocol.
@@ -86,7 +159,13 @@ pub struct EnabledHandler {
     inbound_substream: Option<InboundSubstreamState>,
 
     /// Queue of values that we want to send to the remote.
-    send_queue: SmallVec<[RpcProto; 16]>,
+    send_queue: SendQueue,
+
+    /// The number of RPCs dropped by the send queue not yet reported to the behaviour.
+    send_queue_dropped: usize,
+
+    /// The bytes accepted into the send queue not yet reported to the behaviour.
+    send_queue_accepted_bytes: u64,
 
     /// Flag indicating that an outbound substream is being established to prevent duplicate
     /// requests.
@@ -172,6 +251,20 @@ impl EnabledHandler {
             }
         }
 
+        if self.send_queue_dropped > 0 {
+            let dropped = std::mem::take(&mut self.send_queue_dropped);
+            return Poll::Ready(ConnectionHandlerEvent::Custom(
+                HandlerEvent::SendQueueDropped(dropped),
+            ));
+        }
+
+        if self.send_queue_accepted_bytes > 0 {
+            let accepted = std::mem::take(&mut self.send_queue_accepted_bytes);
+            return Poll::Ready(ConnectionHandlerEvent::Custom(
+                HandlerEvent::SendQueueAccepted(accepted),
+            ));
+        }
+
         // determine if we need to create the outbound stream
         if !self.send_queue.is_empty()
             && self.outbound_substream.is_none()
@@ -368,7 +461,12 @@ pub enum Handler {
 
 impl Handler {
     /// Builds a new [`Handler`].
-    pub fn new(protocol_config: ProtocolUpgrade, idle_timeout: Duration) -> Self {
+    pub fn new(
+        protocol_config: ProtocolUpgrade,
+        idle_timeout: Duration,
+        prioritize_control: bool,
+        send_queue_high_water_mark: Option<usize>,
+    ) -> Self {
         Handler::Enabled(EnabledHandler {
             listen_protocol: protocol_config,
             inbound_substream: None,
@@ -376,7 +474,9 @@ impl Handler {
             outbound_substream_establishing: false,
             outbound_substream_attempts: 0,
             inbound_substream_attempts: 0,
-            send_queue: SmallVec::new(),
+            send_queue: SendQueue::new(prioritize_control, send_queue_high_water_mark),
+            send_queue_dropped: 0,
+            send_queue_accepted_bytes: 0,
             peer_kind: None,
             peer_kind_sent: false,
             last_io_activity: Instant::now(),
@@ -384,6 +484,15 @@ impl Handler {
             in_mesh: false,
         })
     }
+
+    /// The idle timeout of the connection. `None` if the handler is disabled.
+    #[cfg(test)]
+    pub(crate) fn idle_timeout(&self) -> Option<Duration> {
+        match self {
+            Handler::Enabled(handler) => Some(handler.idle_timeout),
+            Handler::Disabled(_) => None,
+        }
+    }
 }
 
 impl ConnectionHandler for Handler {
@@ -457,7 +566,15 @@ impl ConnectionHandler for Handler {
     fn on_behaviour_event(&mut self, message: HandlerIn) {
         match self {
             Handler::Enabled(handler) => match message {
-                HandlerIn::Message(m) => handler.send_queue.push(m),
+                HandlerIn::Message(m) => {
+                    let len = m.encoded_len() as u64;
+                    if handler.send_queue.push(m) {
+                        handler.send_queue_accepted_bytes += len;
+                    } else {
+                        log::debug!("Send queue past its high-water mark. Dropping message");
+                        handler.send_queue_dropped += 1;
+                    }
+                }
                 HandlerIn::JoinedMesh => {
                     handler.in_mesh = true;
                 }
@@ -560,3 +677,86 @@ impl ConnectionHandler for Handler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::gossipsub::rpc::{ControlMessageProto, MessageProto, RpcProto};
+
+    use super::SendQueue;
+
+    fn control_rpc() -> RpcProto {
+        RpcProto {
+            subscriptions: Vec::new(),
+            publish: Vec::new(),
+            control: Some(ControlMessageProto::default()),
+        }
+    }
+
+    fn data_rpc(data: u8) -> RpcProto {
+        RpcProto {
+            subscriptions: Vec::new(),
+            publish: vec![MessageProto {
+                data: Some(vec![data].into()),
+                ..Default::default()
+            }],
+            control: None,
+        }
+    }
+
+    #[test]
+    fn send_queue_drains_control_messages_first() {
+        // Given
+        let mut queue = SendQueue::new(true, None);
+
+        // When
+        queue.push(data_rpc(1));
+        queue.push(control_rpc());
+        queue.push(data_rpc(2));
+        queue.push(control_rpc());
+
+        let sent = std::iter::from_fn(|| queue.pop()).collect::<Vec<_>>();
+
+        // Then
+        assert_eq!(sent.len(), 4);
+        assert!(sent[..2].iter().all(|rpc| rpc.publish.is_empty()));
+        assert_eq!(sent[2], data_rpc(1));
+        assert_eq!(sent[3], data_rpc(2));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn send_queue_without_prioritization_keeps_fifo_order() {
+        // Given
+        let mut queue = SendQueue::new(false, None);
+
+        // When
+        queue.push(data_rpc(1));
+        queue.push(control_rpc());
+
+        let sent = std::iter::from_fn(|| queue.pop()).collect::<Vec<_>>();
+
+        // Then
+        assert_eq!(sent, vec![data_rpc(1), control_rpc()]);
+    }
+
+    #[test]
+    fn send_queue_past_the_high_water_mark_drops_data_messages_only() {
+        // Given
+        let mut queue = SendQueue::new(true, Some(2));
+
+        // When
+        let queued = [
+            queue.push(data_rpc(1)),
+            queue.push(data_rpc(2)),
+            queue.push(data_rpc(3)),
+            queue.push(control_rpc()),
+            queue.push(data_rpc(4)),
+        ];
+
+        let sent = std::iter::from_fn(|| queue.pop()).collect::<Vec<_>>();
+
+        // Then
+        assert_eq!(queued, [true, true, false, true, false]);
+        assert_eq!(sent, vec![control_rpc(), data_rpc(1), data_rpc(2)]);
+    }
+}
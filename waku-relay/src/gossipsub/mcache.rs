@@ -18,12 +18,13 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use std::collections::{HashMap, HashSet};
 use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 
+use instant::Instant;
 use libp2p::PeerId;
-use log::{debug, trace};
+use log::{debug, trace, warn};
 
 use crate::gossipsub::message_id::MessageId;
 use crate::gossipsub::topic::TopicHash;
@@ -51,6 +52,9 @@ pub(crate) struct CachedMessage {
 
     /// Flag indicating if this message has been validated by the application or not.
     pub validated: bool,
+
+    /// The instant this message was first seen by the local node.
+    pub first_seen: Instant,
 }
 
 /// CacheEntry stored in the history.
@@ -66,11 +70,16 @@ pub(crate) struct MessageCache {
     msgs: HashMap<MessageId, (CachedMessage, HashSet<PeerId>)>,
     /// For every message and peer the number of times this peer asked for the message
     iwant_counts: HashMap<MessageId, HashMap<PeerId, u32>>,
-    history: Vec<Vec<CacheEntry>>,
+    history: Vec<VecDeque<CacheEntry>>,
     /// The number of indices in the cache history used for gossiping. That means that a message
     /// won't get gossiped anymore when shift got called `gossip` many times after inserting the
     /// message in the cache.
     gossip: usize,
+    /// The maximum summed size of the cached messages data. When exceeded, the oldest messages are
+    /// evicted.
+    max_bytes: Option<usize>,
+    /// The summed size of the cached messages data.
+    bytes: usize,
 }
 
 impl fmt::Debug for MessageCache {
@@ -79,25 +88,39 @@ impl fmt::Debug for MessageCache {
             .field("msgs", &self.msgs)
             .field("history", &self.history)
             .field("gossip", &self.gossip)
+            .field("max_bytes", &self.max_bytes)
+            .field("bytes", &self.bytes)
             .finish()
     }
 }
 
 /// Implementation of the MessageCache.
 impl MessageCache {
-    pub(crate) fn new(gossip: usize, history_capacity: usize) -> Self {
+    pub(crate) fn new(gossip: usize, history_capacity: usize, max_bytes: Option<usize>) -> Self {
         MessageCache {
             gossip,
             msgs: HashMap::default(),
             iwant_counts: HashMap::default(),
-            history: vec![Vec::new(); history_capacity],
+            history: vec![VecDeque::new(); history_capacity],
+            max_bytes,
+            bytes: 0,
         }
     }
 
-    /// Put a message into the memory cache.
+    /// Put a message into the memory cache. If the cache exceeds its bytes budget, the oldest
+    /// messages are evicted. A message larger than the whole bytes budget is not cached.
     ///
-    /// Returns true if the message didn't already exist in the cache.
+    /// Returns true if the message was cached, i.e., it didn't already exist in the cache and
+    /// fits the bytes budget.
     pub(crate) fn put(&mut self, message_id: &MessageId, msg: CachedMessage) -> bool {
+        if matches!(self.max_bytes, Some(max_bytes) if msg.data.len() > max_bytes) {
+            warn!(
+                "The message with id {} is larger than the cache bytes budget, not caching it.",
+                message_id
+            );
+            return false;
+        }
+
         match self.msgs.entry(message_id.clone()) {
             Entry::Occupied(_) => {
                 // Don't add duplicate entries to the cache.
@@ -108,10 +131,12 @@ impl MessageCache {
                     mid: message_id.clone(),
                     topic: msg.topic.clone(),
                 };
+                self.bytes += msg.data.len();
                 entry.insert((msg, HashSet::default()));
-                self.history[0].push(cache_entry);
+                self.history[0].push_back(cache_entry);
 
                 trace!("Put message {:?} in mcache", message_id);
+                self.evict_oldest();
                 true
             }
         }
@@ -211,6 +236,7 @@ impl MessageCache {
     pub(crate) fn shift(&mut self) {
         for entry in self.history.pop().expect("history is always > 1") {
             if let Some((msg, _)) = self.msgs.remove(&entry.mid) {
+                self.bytes -= msg.data.len();
                 if !msg.validated {
                     // If GossipsubConfig::validate_messages is true, the implementing
                     // application has to ensure that Gossipsub::validate_message gets called for
@@ -227,7 +253,7 @@ impl MessageCache {
         }
 
         // Insert an empty vec in position 0
-        self.history.insert(0, Vec::new());
+        self.history.insert(0, VecDeque::new());
     }
 
     /// Removes a message from the cache and returns it if existent
@@ -239,6 +265,167 @@ impl MessageCache {
         // history vector. Zhe id in the history vector will simply be ignored on popping.
 
         self.iwant_counts.remove(message_id);
-        self.msgs.remove(message_id)
+        let removed = self.msgs.remove(message_id);
+        if let Some((msg, _)) = &removed {
+            self.bytes -= msg.data.len();
+        }
+        removed
+    }
+
+    /// Evicts the oldest messages until the cached messages data fits the bytes budget.
+    fn evict_oldest(&mut self) {
+        let max_bytes = match self.max_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return,
+        };
+
+        while self.bytes > max_bytes {
+            // The oldest entries are at the front of the last non-empty history window
+            let entry = match self
+                .history
+                .iter_mut()
+                .rev()
+                .find_map(|window| window.pop_front())
+            {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            if let Some((msg, _)) = self.remove(&entry.mid) {
+                if !msg.validated {
+                    // The application can no longer report the validation result of the message
+                    warn!(
+                        "The message with id {} got evicted from the cache without being validated.",
+                        &entry.mid
+                    );
+                }
+                debug!(
+                    "Evict message from the cache, bytes budget exceeded: {}",
+                    &entry.mid
+                );
+            }
+        }
+    }
+
+    /// The number of messages in the cache.
+    pub(crate) fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    /// The total number of times the cached messages have been requested via IWANT, across all
+    /// messages and peers.
+    pub(crate) fn iwant_count_total(&self) -> usize {
+        self.iwant_counts
+            .values()
+            .flat_map(|counts| counts.values())
+            .map(|count| *count as usize)
+            .sum()
+    }
+
+    /// The summed size of the cached messages data.
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// The number of heartbeat windows the cache keeps messages for.
+    pub(crate) fn history_depth(&self) -> usize {
+        self.history.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use instant::Instant;
+    use libp2p::PeerId;
+
+    use crate::gossipsub::message_id::MessageId;
+    use crate::gossipsub::topic::TopicHash;
+
+    use super::{CachedMessage, MessageCache};
+
+    fn cached_message(data: u8) -> CachedMessage {
+        CachedMessage {
+            source: None,
+            data: vec![data],
+            sequence_number: None,
+            topic: TopicHash::from_raw("/waku/2/test"),
+            signature: None,
+            key: None,
+            validated: true,
+            first_seen: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn stats_count_cached_messages_and_iwant_requests() {
+        // Given
+        let mut mcache = MessageCache::new(3, 5, None);
+        let peer_a = PeerId::random();
+        let peer_b = PeerId::random();
+
+        let message_ids = (0..3).map(|i| MessageId::new(vec![i])).collect::<Vec<_>>();
+        for (i, message_id) in message_ids.iter().enumerate() {
+            mcache.put(message_id, cached_message(i as u8));
+        }
+
+        // When
+        mcache.get_with_iwant_counts(&message_ids[0], &peer_a);
+        mcache.get_with_iwant_counts(&message_ids[0], &peer_a);
+        mcache.get_with_iwant_counts(&message_ids[0], &peer_b);
+        mcache.get_with_iwant_counts(&message_ids[1], &peer_b);
+
+        // Then
+        assert_eq!(mcache.len(), 3);
+        assert_eq!(mcache.iwant_count_total(), 4);
+        assert_eq!(mcache.history_depth(), 5);
+
+        mcache.remove(&message_ids[0]);
+        assert_eq!(mcache.len(), 2);
+        assert_eq!(mcache.iwant_count_total(), 1);
+    }
+
+    #[test]
+    fn oldest_messages_are_evicted_past_the_bytes_budget() {
+        // Given
+        let mut mcache = MessageCache::new(3, 5, Some(3));
+
+        let message_ids = (0..5).map(|i| MessageId::new(vec![i])).collect::<Vec<_>>();
+        mcache.put(&message_ids[0], cached_message(0));
+        mcache.shift();
+        mcache.put(&message_ids[1], cached_message(1));
+        mcache.put(&message_ids[2], cached_message(2));
+
+        // When
+        mcache.put(&message_ids[3], cached_message(3));
+        mcache.put(&message_ids[4], cached_message(4));
+
+        // Then
+        assert_eq!(mcache.len(), 3);
+        assert_eq!(mcache.bytes(), 3);
+        assert!(mcache.get(&message_ids[0]).is_none());
+        assert!(mcache.get(&message_ids[1]).is_none());
+        assert!(mcache.get(&message_ids[2]).is_some());
+        assert!(mcache.get(&message_ids[3]).is_some());
+        assert!(mcache.get(&message_ids[4]).is_some());
+    }
+    #[test]
+    fn messages_larger_than_the_bytes_budget_are_not_cached() {
+        // Given
+        let mut mcache = MessageCache::new(3, 5, Some(3));
+
+        let message_id = MessageId::new(vec![0]);
+        mcache.put(&MessageId::new(vec![1]), cached_message(1));
+
+        let mut message = cached_message(0);
+        message.data = vec![0; 4];
+
+        // When
+        let cached = mcache.put(&message_id, message);
+
+        // Then
+        assert!(!cached);
+        assert!(mcache.get(&message_id).is_none());
+        assert_eq!(mcache.len(), 1, "no message should be evicted");
+        assert_eq!(mcache.bytes(), 1);
     }
 }
@@ -22,14 +22,16 @@
 //! protocol.
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::{Family, MetricConstructor};
 use prometheus_client::metrics::gauge::Gauge;
-use prometheus_client::metrics::histogram::{linear_buckets, Histogram};
+use prometheus_client::metrics::histogram::{exponential_buckets, linear_buckets, Histogram};
 use prometheus_client::registry::Registry;
 
+use crate::gossipsub::error::MetricsError;
 use crate::gossipsub::types::PeerKind;
 use crate::gossipsub::{MessageAcceptance, PeerScoreThresholds, TopicHash};
 
@@ -67,13 +69,30 @@ pub(crate) trait Metrics {
     fn msg_recvd(&mut self, topic: &TopicHash) {}
     /// Register that a message was received (could have been a duplicate).
     fn msg_recvd_unfiltered(&mut self, topic: &TopicHash, bytes: usize) {}
+    /// Register the size of a received message data after the data transform.
+    fn msg_decompressed_bytes(&mut self, topic: &TopicHash, bytes: usize) {}
     fn register_msg_validation(&mut self, topic: &TopicHash, validation: &MessageAcceptance) {}
     /// Register a memcache miss.
     fn memcache_miss(&mut self) {}
+    /// Register a pooled control action dropped because the peer's control pool was full.
+    fn control_pool_overflow(&mut self) {}
+    /// Register an RPC split into several RPCs to fit within the maximum transmit size.
+    fn rpc_fragmented(&mut self) {}
+    /// Register an RPC dropped because it could not be fragmented to fit within the maximum
+    /// transmit size.
+    fn rpc_fragment_failed(&mut self) {}
+    /// Register RPCs dropped because the peer's send queue was past its high-water mark.
+    fn send_queue_dropped(&mut self, count: usize) {}
     /// Register sending an IWANT msg for this topic.
     fn register_iwant(&mut self, topic: &TopicHash) {}
     /// Observes a heartbeat duration.
     fn observe_heartbeat_duration(&mut self, millis: u64) {}
+    /// Observes the time elapsed between a message being first seen and the application reporting
+    /// its validation result.
+    fn observe_validation_latency(&mut self, millis: u64) {}
+    /// Observes the time elapsed between a message being received and forwarded to the mesh
+    /// peers.
+    fn observe_forward_latency(&mut self, topic: &TopicHash, latency: Duration) {}
     /// Observe a score of a mesh peer.
     fn observe_mesh_peers_score(&mut self, topic: &TopicHash, score: f64) {}
     /// Register a new peers connection based on its protocol.
@@ -103,6 +122,9 @@ pub struct Config {
     pub max_never_subscribed_topics: usize,
     /// Buckets used for the score histograms.
     pub score_buckets: Vec<f64>,
+    /// An optional prefix prepended to all the metric names. This allows registering multiple
+    /// behaviours into the same registry without their metric names colliding.
+    pub prefix: Option<String>,
 }
 
 impl Config {
@@ -143,10 +165,22 @@ impl Default for Config {
             max_topics: DEFAULT_MAX_TOPICS,
             max_never_subscribed_topics: DEFAULT_MAX_NEVER_SUBSCRIBED_TOPICS,
             score_buckets,
+            prefix: None,
         }
     }
 }
 
+/// Checks that the prefix is a valid Prometheus metric name, i.e. it matches
+/// `[a-zA-Z_:][a-zA-Z0-9_:]*`.
+fn is_valid_metrics_prefix(prefix: &str) -> bool {
+    let mut chars = prefix.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
 /// Whether we have ever been subscribed to this topic.
 type EverSubscribed = bool;
 
@@ -203,6 +237,8 @@ pub(crate) struct GossipsubMetrics {
     topic_msg_recv_counts: Family<TopicHash, Counter>,
     /// Bytes received from gossip messages for each topic.
     topic_msg_recv_bytes: Family<TopicHash, Counter>,
+    /// Bytes of the gossip messages received for each topic, after the data transform.
+    topic_msg_decompressed_bytes: Family<TopicHash, Counter>,
 
     /* Metrics related to scoring */
     /// Histogram of the scores for each mesh topic.
@@ -217,26 +253,53 @@ pub(crate) struct GossipsubMetrics {
     peers_per_protocol: Family<ProtocolLabel, Gauge>,
     /// The time it takes to complete one iteration of the heartbeat.
     heartbeat_duration: Histogram,
+    /// The time elapsed between a message being first seen and the application reporting its
+    /// validation result.
+    validation_latency: Histogram,
+    /// The time elapsed between a message being received and forwarded to the mesh peers, per
+    /// topic.
+    forward_latency: Family<TopicHash, Histogram, HistBuilder>,
 
     /* Performance metrics */
     /// When the user validates a message, it tries to re propagate it to its mesh peers. If the
     /// message expires from the memcache before it can be validated, we count this a cache miss
     /// and it is an indicator that the memcache size should be increased.
     memcache_misses: Counter,
+    /// The number of pooled control actions dropped because the control pool of a peer was full.
+    /// A non-zero value might indicate the heartbeat is too slow.
+    control_pool_overflows: Counter,
+    /// The number of RPCs split into several RPCs to fit within the maximum transmit size. A high
+    /// value might indicate the maximum transmit size is too small.
+    rpc_fragmented: Counter,
+    /// The number of RPCs dropped because they could not be fragmented to fit within the maximum
+    /// transmit size.
+    rpc_fragment_fail: Counter,
+    /// The number of RPCs carrying published messages dropped because the peer's send queue was
+    /// past its high-water mark. A high value might indicate slow peers.
+    send_queue_dropped: Counter,
     /// The number of times we have decided that an IWANT control message is required for this
     /// topic. A very high metric might indicate an underperforming network.
     topic_iwant_msgs: Family<TopicHash, Counter>,
 }
 
 impl GossipsubMetrics {
-    pub(crate) fn new(registry: &mut Registry, config: Config) -> Self {
+    pub(crate) fn new(registry: &mut Registry, config: Config) -> Result<Self, MetricsError> {
         // Destructure the config to be sure everything is used.
         let Config {
             max_topics,
             max_never_subscribed_topics,
             score_buckets,
+            prefix,
         } = config;
 
+        let registry = match prefix {
+            Some(prefix) if !is_valid_metrics_prefix(&prefix) => {
+                return Err(MetricsError::InvalidPrefix(prefix));
+            }
+            Some(prefix) => registry.sub_registry_with_prefix(prefix),
+            None => registry,
+        };
+
         macro_rules! register_family {
             ($name:expr, $help:expr) => {{
                 let fam = Family::default();
@@ -312,6 +375,10 @@ impl GossipsubMetrics {
             "topic_msg_recv_bytes",
             "Bytes received from gossip messages for each topic"
         );
+        let topic_msg_decompressed_bytes = register_family!(
+            "msg_decompressed_bytes",
+            "Bytes of the gossip messages received for each topic after the data transform"
+        );
 
         let hist_builder = HistBuilder {
             buckets: score_buckets,
@@ -340,6 +407,24 @@ impl GossipsubMetrics {
             heartbeat_duration.clone(),
         );
 
+        let validation_latency = Histogram::new(exponential_buckets(1.0, 2.0, 12));
+        registry.register(
+            "validation_latency",
+            "Histogram of observed message validation latencies in milliseconds",
+            validation_latency.clone(),
+        );
+
+        // From 100 microseconds to ~3 seconds, forwarding usually takes less than a millisecond
+        let forward_latency: Family<_, _, HistBuilder> =
+            Family::new_with_constructor(HistBuilder {
+                buckets: exponential_buckets(0.0001, 2.0, 16).collect(),
+            });
+        registry.register(
+            "forward_latency_seconds",
+            "Histogram of observed message forwarding latencies in seconds per topic",
+            forward_latency.clone(),
+        );
+
         let topic_iwant_msgs = register_family!(
             "topic_iwant_msgs",
             "Number of times we have decided an IWANT is required for this topic"
@@ -353,8 +438,44 @@ impl GossipsubMetrics {
             );
             metric
         };
+        let control_pool_overflows = {
+            let metric = Counter::default();
+            registry.register(
+                "control_pool_overflows",
+                "Number of pooled control actions dropped because the control pool of a peer was full",
+                metric.clone(),
+            );
+            metric
+        };
+        let rpc_fragmented = {
+            let metric = Counter::default();
+            registry.register(
+                "rpc_fragmented",
+                "Number of RPCs split into several RPCs to fit within the maximum transmit size",
+                metric.clone(),
+            );
+            metric
+        };
+        let rpc_fragment_fail = {
+            let metric = Counter::default();
+            registry.register(
+                "rpc_fragment_fail",
+                "Number of RPCs dropped because they could not be fragmented to fit within the maximum transmit size",
+                metric.clone(),
+            );
+            metric
+        };
+        let send_queue_dropped = {
+            let metric = Counter::default();
+            registry.register(
+                "send_queue_dropped",
+                "Number of RPCs dropped because the peer send queue was past its high-water mark",
+                metric.clone(),
+            );
+            metric
+        };
 
-        Self {
+        Ok(Self {
             max_topics,
             max_never_subscribed_topics,
             topic_info: HashMap::default(),
@@ -373,13 +494,20 @@ impl GossipsubMetrics {
             topic_msg_recv_counts_unfiltered,
             topic_msg_recv_counts,
             topic_msg_recv_bytes,
+            topic_msg_decompressed_bytes,
             score_per_mesh,
             scoring_penalties,
             peers_per_protocol,
             heartbeat_duration,
+            validation_latency,
+            forward_latency,
             memcache_misses,
+            control_pool_overflows,
+            rpc_fragmented,
+            rpc_fragment_fail,
+            send_queue_dropped,
             topic_iwant_msgs,
-        }
+        })
     }
 
     fn non_subscription_topics_count(&self) -> usize {
@@ -513,6 +641,14 @@ impl Metrics for GossipsubMetrics {
                 .inc_by(bytes as u64);
         }
     }
+    /// Register the size of a received message data after the data transform.
+    fn msg_decompressed_bytes(&mut self, topic: &TopicHash, bytes: usize) {
+        if self.register_topic(topic).is_ok() {
+            self.topic_msg_decompressed_bytes
+                .get_or_create(topic)
+                .inc_by(bytes as u64);
+        }
+    }
     fn register_msg_validation(&mut self, topic: &TopicHash, validation: &MessageAcceptance) {
         if self.register_topic(topic).is_ok() {
             match validation {
@@ -526,6 +662,23 @@ impl Metrics for GossipsubMetrics {
     fn memcache_miss(&mut self) {
         self.memcache_misses.inc();
     }
+    /// Register a pooled control action dropped because the peer's control pool was full.
+    fn control_pool_overflow(&mut self) {
+        self.control_pool_overflows.inc();
+    }
+    /// Register an RPC split into several RPCs to fit within the maximum transmit size.
+    fn rpc_fragmented(&mut self) {
+        self.rpc_fragmented.inc();
+    }
+    /// Register an RPC dropped because it could not be fragmented to fit within the maximum
+    /// transmit size.
+    fn rpc_fragment_failed(&mut self) {
+        self.rpc_fragment_fail.inc();
+    }
+    /// Register RPCs dropped because the peer's send queue was past its high-water mark.
+    fn send_queue_dropped(&mut self, count: usize) {
+        self.send_queue_dropped.inc_by(count as u64);
+    }
     /// Register sending an IWANT msg for this topic.
     fn register_iwant(&mut self, topic: &TopicHash) {
         if self.register_topic(topic).is_ok() {
@@ -536,6 +689,20 @@ impl Metrics for GossipsubMetrics {
     fn observe_heartbeat_duration(&mut self, millis: u64) {
         self.heartbeat_duration.observe(millis as f64);
     }
+    /// Observes the time elapsed between a message being first seen and the application reporting
+    /// its validation result.
+    fn observe_validation_latency(&mut self, millis: u64) {
+        self.validation_latency.observe(millis as f64);
+    }
+    /// Observes the time elapsed between a message being received and forwarded to the mesh
+    /// peers.
+    fn observe_forward_latency(&mut self, topic: &TopicHash, latency: Duration) {
+        if self.register_topic(topic).is_ok() {
+            self.forward_latency
+                .get_or_create(topic)
+                .observe(latency.as_secs_f64());
+        }
+    }
     /// Observe a score of a mesh peer.
     fn observe_mesh_peers_score(&mut self, topic: &TopicHash, score: f64) {
         if self.register_topic(topic).is_ok() {
@@ -586,6 +753,8 @@ pub(crate) enum Churn {
     Unsub,
     /// Too many peers.
     Excess,
+    /// Peer manually pruned.
+    Manual,
 }
 
 /// Kinds of reasons a peer's score has been penalized
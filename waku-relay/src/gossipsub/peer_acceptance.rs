@@ -0,0 +1,34 @@
+use libp2p::identity::PeerId;
+use libp2p::Multiaddr;
+
+/// A policy consulted when a connection with a peer is established, before the peer enters the
+/// gossipsub state.
+///
+/// Unlike the static blacklist, the policy can base its decision on the remote address of the
+/// connection, e.g. to reject connections from specific IP ranges.
+pub trait PeerAcceptancePolicy {
+    /// Returns true iff the connection with the peer at the given remote address is accepted.
+    fn accept(&mut self, peer_id: &PeerId, remote_addr: &Multiaddr) -> bool;
+}
+
+/// A [`PeerAcceptancePolicy`] that accepts all connections.
+#[derive(Default, Clone)]
+pub struct AcceptAllPeersPolicy;
+
+impl PeerAcceptancePolicy for AcceptAllPeersPolicy {
+    fn accept(&mut self, _peer_id: &PeerId, _remote_addr: &Multiaddr) -> bool {
+        true
+    }
+}
+
+/// A [`PeerAcceptancePolicy`] backed by a callback.
+pub struct CallbackPeerAcceptancePolicy<T>(pub T);
+
+impl<T> PeerAcceptancePolicy for CallbackPeerAcceptancePolicy<T>
+where
+    T: FnMut(&PeerId, &Multiaddr) -> bool,
+{
+    fn accept(&mut self, peer_id: &PeerId, remote_addr: &Multiaddr) -> bool {
+        (self.0)(peer_id, remote_addr)
+    }
+}
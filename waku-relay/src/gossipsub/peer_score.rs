@@ -23,7 +23,7 @@
 
 pub use params::{
     score_parameter_decay, score_parameter_decay_with_base, PeerScoreParams, PeerScoreThresholds,
-    TopicScoreParams,
+    ScoreBreakdown, ThresholdStatus, TopicScoreBreakdown, TopicScoreParams,
 };
 pub use service::*;
 pub use stats::*;
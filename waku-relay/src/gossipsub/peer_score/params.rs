@@ -104,6 +104,58 @@ impl PeerScoreThresholds {
     }
 }
 
+/// The [`PeerScoreThresholds`] a peer's score is currently below.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThresholdStatus {
+    /// The peer score is below the gossip threshold; gossip to and from the peer is suppressed.
+    pub below_gossip_threshold: bool,
+    /// The peer score is below the publish threshold; the peer is not selected for flood
+    /// publishing nor as a fanout peer.
+    pub below_publish_threshold: bool,
+    /// The peer score is below the graylist threshold; all the messages from the peer are
+    /// ignored.
+    pub below_graylist_threshold: bool,
+    /// The peer score is below the accept PX threshold; its peer exchange info is ignored.
+    pub below_accept_px_threshold: bool,
+}
+
+impl ThresholdStatus {
+    /// Whether the peer score is above all the thresholds.
+    pub fn is_all_clear(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The values of the components of a peer's score, before being weighted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    /// The aggregate score.
+    pub score: f64,
+    /// The topic components, per scored topic.
+    pub topics: HashMap<TopicHash, TopicScoreBreakdown>,
+    /// P5: The application-specific score.
+    pub application_score: f64,
+    /// P6: The IP colocation factor, summed over the peer's IPs.
+    pub ip_colocation_factor: f64,
+    /// P7: The behavioural penalty counter.
+    pub behaviour_penalty: f64,
+}
+
+/// The values of the topic components of a peer's score, before being weighted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicScoreBreakdown {
+    /// P1: The time the peer has been in the topic mesh.
+    pub time_in_mesh: Duration,
+    /// P2: The first message deliveries counter.
+    pub first_message_deliveries: f64,
+    /// P3: The mesh message deliveries counter.
+    pub mesh_message_deliveries: f64,
+    /// P3b: The mesh failure penalty counter.
+    pub mesh_failure_penalty: f64,
+    /// P4: The invalid message deliveries counter.
+    pub invalid_message_deliveries: f64,
+}
+
 #[derive(Debug, Clone)]
 pub struct PeerScoreParams {
     /// Score parameters per topic.
@@ -10,15 +10,23 @@ use libp2p::identity::PeerId;
 use crate::gossipsub::gossip_promises::GossipPromises;
 use crate::gossipsub::message_id::MessageId;
 use crate::gossipsub::metrics::Metrics;
-use crate::gossipsub::peer_score::{PeerScore, PeerScoreThresholds, RejectReason};
+use crate::gossipsub::peer_score::{PeerScore, PeerScoreThresholds, RejectReason, ScoreBreakdown};
 use crate::gossipsub::topic::TopicHash;
 use crate::gossipsub::TopicScoreParams;
 
 pub(crate) trait PeerScoreService {
     fn peer_score(&self, peer_id: &PeerId) -> Option<f64>;
 
+    /// The values of the peer's score components. Returns `None` if peer scoring is disabled or
+    /// the peer is unknown.
+    fn peer_score_breakdown(&self, peer_id: &PeerId) -> Option<ScoreBreakdown>;
+
     fn publish_threshold(&self) -> f64;
 
+    /// The median mesh score below which opportunistic grafting is triggered. Returns `None` if
+    /// peer scoring is disabled.
+    fn opportunistic_graft_threshold(&self) -> Option<f64>;
+
     /// Determines if a peer's score is below a given `PeerScoreThreshold` chosen via the
     /// `threshold` parameter.
     fn score_below_threshold(
@@ -108,10 +116,18 @@ impl PeerScoreService for NoopPeerScoreService {
         None
     }
 
+    fn peer_score_breakdown(&self, _peer_id: &PeerId) -> Option<ScoreBreakdown> {
+        None
+    }
+
     fn publish_threshold(&self) -> f64 {
         0.0
     }
 
+    fn opportunistic_graft_threshold(&self) -> Option<f64> {
+        None
+    }
+
     fn score_below_threshold(
         &self,
         _peer_id: &PeerId,
@@ -253,10 +269,18 @@ impl PeerScoreService for GossipsubPeerScoreService {
         Some(self.scores.score(peer_id))
     }
 
+    fn peer_score_breakdown(&self, peer_id: &PeerId) -> Option<ScoreBreakdown> {
+        self.scores.score_breakdown(peer_id)
+    }
+
     fn publish_threshold(&self) -> f64 {
         self.thresholds.publish_threshold
     }
 
+    fn opportunistic_graft_threshold(&self) -> Option<f64> {
+        Some(self.thresholds.opportunistic_graft_threshold)
+    }
+
     fn score_below_threshold(
         &self,
         peer_id: &PeerId,
@@ -9,7 +9,9 @@ use log::{debug, trace, warn};
 use crate::gossipsub::error::MessageValidationError as ValidationError;
 use crate::gossipsub::metrics::{Metrics, NoopMetrics, Penalty};
 use crate::gossipsub::time_cache::TimeCache;
-use crate::gossipsub::{MessageId, PeerScoreParams, TopicHash, TopicScoreParams};
+use crate::gossipsub::{
+    MessageId, PeerScoreParams, ScoreBreakdown, TopicHash, TopicScoreBreakdown, TopicScoreParams,
+};
 
 /// The number of seconds delivery messages are stored in the cache.
 const TIME_CACHE_DURATION: u64 = 120;
@@ -316,6 +318,51 @@ impl PeerScore {
         score
     }
 
+    /// Returns the values of the score components for a peer, if the peer is known.
+    pub(crate) fn score_breakdown(&self, peer_id: &PeerId) -> Option<ScoreBreakdown> {
+        let peer_stats = self.peer_stats.get(peer_id)?;
+
+        let topics = peer_stats
+            .topics
+            .iter()
+            .filter(|(topic, _)| self.params.topics.contains_key(topic))
+            .map(|(topic, topic_stats)| {
+                let time_in_mesh = match topic_stats.mesh_status {
+                    MeshStatus::Active { mesh_time, .. } => mesh_time,
+                    MeshStatus::InActive => Duration::ZERO,
+                };
+                let breakdown = TopicScoreBreakdown {
+                    time_in_mesh,
+                    first_message_deliveries: topic_stats.first_message_deliveries,
+                    mesh_message_deliveries: topic_stats.mesh_message_deliveries,
+                    mesh_failure_penalty: topic_stats.mesh_failure_penalty,
+                    invalid_message_deliveries: topic_stats.invalid_message_deliveries,
+                };
+                (topic.clone(), breakdown)
+            })
+            .collect();
+
+        let ip_colocation_factor = peer_stats
+            .known_ips
+            .iter()
+            .filter(|ip| !self.params.ip_colocation_factor_whitelist.contains(*ip))
+            .filter_map(|ip| self.peer_ips.get(ip).map(|peers| peers.len() as f64))
+            .filter(|peers_in_ip| *peers_in_ip > self.params.ip_colocation_factor_threshold)
+            .map(|peers_in_ip| {
+                let surplus = peers_in_ip - self.params.ip_colocation_factor_threshold;
+                surplus * surplus
+            })
+            .sum();
+
+        Some(ScoreBreakdown {
+            score: self.score(peer_id),
+            topics,
+            application_score: peer_stats.application_score,
+            ip_colocation_factor,
+            behaviour_penalty: peer_stats.behaviour_penalty,
+        })
+    }
+
     pub(crate) fn add_penalty(&mut self, peer_id: &PeerId, count: usize) {
         if let Some(peer_stats) = self.peer_stats.get_mut(peer_id) {
             debug!(
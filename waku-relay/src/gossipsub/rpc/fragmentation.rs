@@ -1,3 +1,4 @@
+use prost::encoding::encoded_len_varint;
 use prost::Message;
 
 use crate::gossipsub::rpc::{ControlMessageProto, RpcProto};
@@ -6,6 +7,90 @@ pub enum FragmentationError {
     MessageTooLarge,
 }
 
+/// The encoded length of a length-delimited field: its key, its length prefix and its value. All
+/// the RPC and control message fields have a single byte key.
+fn field_len(value_len: usize) -> usize {
+    1 + encoded_len_varint(value_len as u64) + value_len
+}
+
+/// The RPCs a message is fragmented into.
+///
+/// The encoded length of the last RPC is tracked as objects are added, so the RPC is not
+/// re-encoded on every addition.
+struct Fragments {
+    rpc_list: Vec<RpcProto>,
+    max_size: usize,
+    /// The encoded length of the last RPC fields, but the control message.
+    data_len: usize,
+    /// The encoded length of the last RPC control message value, if any.
+    control_len: Option<usize>,
+}
+
+impl Fragments {
+    fn new(max_size: usize) -> Self {
+        Self {
+            rpc_list: vec![RpcProto::default()],
+            max_size,
+            data_len: 0,
+            control_len: None,
+        }
+    }
+
+    fn fits(&self, data_len: usize, control_len: Option<usize>) -> bool {
+        data_len + control_len.map_or(0, field_len) < self.max_size
+    }
+
+    // Create a new RPC if the last one is not empty and the lengths do not fit in it. Returns
+    // the last RPC lengths after the addition.
+    fn reserve(&mut self, data_len: usize, control_len: Option<usize>) -> (usize, Option<usize>) {
+        let is_empty = self.data_len == 0 && self.control_len.is_none();
+        let grown_control_len = |control: Option<usize>| match control_len {
+            None => control,
+            Some(len) => Some(control.unwrap_or(0) + len),
+        };
+
+        if !is_empty
+            && !self.fits(
+                self.data_len + data_len,
+                grown_control_len(self.control_len),
+            )
+        {
+            self.rpc_list.push(RpcProto::default());
+            self.data_len = 0;
+            self.control_len = None;
+        }
+
+        (
+            self.data_len + data_len,
+            grown_control_len(self.control_len),
+        )
+    }
+
+    /// Returns the RPC to add a non-control object of the given encoded length to.
+    fn data_rpc(&mut self, object_len: usize) -> &mut RpcProto {
+        (self.data_len, self.control_len) = self.reserve(field_len(object_len), None);
+        self.rpc_list
+            .last_mut()
+            .expect("Must have at least one element")
+    }
+
+    /// Returns the control message to add a control object of the given encoded length to.
+    fn control(&mut self, object_len: usize) -> &mut ControlMessageProto {
+        (self.data_len, self.control_len) = self.reserve(0, Some(field_len(object_len)));
+        self.rpc_list
+            .last_mut()
+            .expect("Always an element")
+            .control
+            .get_or_insert_with(ControlMessageProto::default)
+    }
+
+    /// Sets the control message of the RPC, as a whole.
+    fn set_control(&mut self, control: &ControlMessageProto) {
+        (self.data_len, self.control_len) = self.reserve(0, Some(control.encoded_len()));
+        self.rpc_list.last_mut().expect("Always an element").control = Some(control.clone());
+    }
+}
+
 // If a message is too large to be sent as-is, this attempts to fragment it into smaller RPC
 // messages to be sent.
 pub fn fragment_rpc_message(
@@ -16,49 +101,20 @@ pub fn fragment_rpc_message(
         return Ok(vec![rpc]);
     }
 
-    let new_rpc = RpcProto {
-        subscriptions: Vec::new(),
-        publish: Vec::new(),
-        control: None,
-    };
-
-    let mut rpc_list = vec![new_rpc.clone()];
-
-    // Gets an RPC if the object size will fit, otherwise create a new RPC. The last element
-    // will be the RPC to add an object.
-    macro_rules! create_or_add_rpc {
-        ($object_size: ident ) => {
-            let list_index = rpc_list.len() - 1; // the list is never empty
-
-            // create a new RPC if the new object plus 5% of its size (for length prefix
-            // buffers) exceeds the max transmit size.
-            if rpc_list[list_index].encoded_len() + (($object_size as f64) * 1.05) as usize
-                > max_size
-                && rpc_list[list_index] != new_rpc
-            {
-                // create a new rpc and use this as the current
-                rpc_list.push(new_rpc.clone());
-            }
-        };
-    }
+    let mut fragments = Fragments::new(max_size);
 
     macro_rules! add_item {
         ($object: ident, $type: ident ) => {
-            let object_size = $object.encoded_len();
+            let object_len = $object.encoded_len();
 
-            if object_size + 2 > max_size {
+            if field_len(object_len) >= max_size {
                 // This should not be possible. All received and published messages have already
                 // been vetted to fit within the size.
                 log::error!("Individual message too large to fragment");
                 return Err(FragmentationError::MessageTooLarge);
             }
 
-            create_or_add_rpc!(object_size);
-            rpc_list
-                .last_mut()
-                .expect("Must have at least one element")
-                .$type
-                .push($object.clone());
+            fragments.data_rpc(object_len).$type.push($object.clone());
         };
     }
 
@@ -72,60 +128,37 @@ pub fn fragment_rpc_message(
 
     // handle the control messages. If all are within the max_transmit_size, send them without
     // fragmenting, otherwise, fragment the control messages
-    let empty_control = ControlMessageProto::default();
     if let Some(control) = rpc.control.as_ref() {
-        if control.encoded_len() + 2 > max_size {
+        if field_len(control.encoded_len()) >= max_size {
             // fragment the RPC
             for ihave in &control.ihave {
-                let len = ihave.encoded_len();
-                create_or_add_rpc!(len);
-                rpc_list
-                    .last_mut()
-                    .expect("Always an element")
-                    .control
-                    .get_or_insert_with(|| empty_control.clone())
+                fragments
+                    .control(ihave.encoded_len())
                     .ihave
                     .push(ihave.clone());
             }
             for iwant in &control.iwant {
-                let len = iwant.encoded_len();
-                create_or_add_rpc!(len);
-                rpc_list
-                    .last_mut()
-                    .expect("Always an element")
-                    .control
-                    .get_or_insert_with(|| empty_control.clone())
+                fragments
+                    .control(iwant.encoded_len())
                     .iwant
                     .push(iwant.clone());
             }
             for graft in &control.graft {
-                let len = graft.encoded_len();
-                create_or_add_rpc!(len);
-                rpc_list
-                    .last_mut()
-                    .expect("Always an element")
-                    .control
-                    .get_or_insert_with(|| empty_control.clone())
+                fragments
+                    .control(graft.encoded_len())
                     .graft
                     .push(graft.clone());
             }
             for prune in &control.prune {
-                let len = prune.encoded_len();
-                create_or_add_rpc!(len);
-                rpc_list
-                    .last_mut()
-                    .expect("Always an element")
-                    .control
-                    .get_or_insert_with(|| empty_control.clone())
+                fragments
+                    .control(prune.encoded_len())
                     .prune
                     .push(prune.clone());
             }
         } else {
-            let len = control.encoded_len();
-            create_or_add_rpc!(len);
-            rpc_list.last_mut().expect("Always an element").control = Some(control.clone());
+            fragments.set_control(control);
         }
     }
 
-    Ok(rpc_list)
+    Ok(fragments.rpc_list)
 }
@@ -1,6 +1,8 @@
 use byteorder::{BigEndian, ByteOrder};
 use bytes::Bytes;
+use libp2p::core::{PeerRecord, SignedEnvelope};
 use libp2p::identity::PeerId;
+use libp2p::Multiaddr;
 
 use crate::gossipsub::{RawMessage, Rpc, TopicHash};
 use crate::gossipsub::rpc::proto::waku::relay::v2::{
@@ -70,7 +72,7 @@ impl From<PeerInfo> for PeerInfoProto {
     fn from(info: PeerInfo) -> Self {
         Self {
             peer_id: info.peer_id.map(|id| Bytes::from(id.to_bytes())),
-            /// TODO, see https://github.com/libp2p/specs/pull/217
+            // The signed peer records of other peers are not kept, only their addresses
             signed_peer_record: None,
         }
     }
@@ -82,12 +84,40 @@ impl TryFrom<PeerInfoProto> for PeerInfo {
     fn try_from(info: PeerInfoProto) -> Result<Self, Self::Error> {
         let peer_id = info.peer_id.unwrap();
         let peer_id = PeerId::from_bytes(&peer_id[..])?;
+
+        // A malformed signed peer record does not invalidate the peer info, the peer can still
+        // be dialed by its peer id
+        let addresses = info
+            .signed_peer_record
+            .map(|record| {
+                signed_peer_record_addresses(&record, &peer_id).unwrap_or_else(|err| {
+                    log::debug!("Invalid signed peer record of peer {}: {}", peer_id, err);
+                    Vec::new()
+                })
+            })
+            .unwrap_or_default();
+
         Ok(Self {
             peer_id: Some(peer_id),
+            addresses,
         })
     }
 }
 
+/// Decodes and verifies a signed peer record, returning the advertised addresses.
+fn signed_peer_record_addresses(record: &[u8], peer_id: &PeerId) -> anyhow::Result<Vec<Multiaddr>> {
+    let envelope = SignedEnvelope::from_protobuf_encoding(record)?;
+    let record = PeerRecord::from_signed_envelope(envelope)?;
+    if record.peer_id() != *peer_id {
+        anyhow::bail!(
+            "signed peer record of a different peer: {}",
+            record.peer_id()
+        );
+    }
+
+    Ok(record.addresses().to_vec())
+}
+
 impl FromIterator<ControlAction> for ControlMessageProto {
     fn from_iter<I: IntoIterator<Item = ControlAction>>(iter: I) -> Self {
         let mut control = ControlMessageProto {
@@ -1,7 +1,9 @@
-pub use signer::{AuthorOnlySigner, Libp2pSigner, MessageSigner, NoopSigner, RandomAuthorSigner};
+pub use signer::{
+    AuthorOnlySigner, Libp2pSigner, MessageSigner, NoopSigner, RandomAuthorSigner, SigningKey,
+};
 pub use validator::{
     AnonymousMessageValidator, MessageValidator, NoopMessageValidator, PermissiveMessageValidator,
-    StrictMessageValidator,
+    SignatureKeyType, StrictMessageValidator,
 };
 
 mod signer;
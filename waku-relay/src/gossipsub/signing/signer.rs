@@ -1,4 +1,6 @@
-use libp2p::identity::{Keypair, PeerId, SigningError};
+use std::sync::Arc;
+
+use libp2p::identity::{Keypair, PeerId, PublicKey, SigningError};
 use prost::Message as _;
 
 use crate::gossipsub::rpc::{MessageProto, MessageRpc};
@@ -28,6 +30,30 @@ impl MessageSigner for NoopSigner {
     }
 }
 
+/// A key able to sign arbitrary bytes on behalf of the local node.
+///
+/// This allows delegating the message signing step to an external signer, e.g. a hardware
+/// security module, instead of holding the private key in-process. Asynchronous signers must
+/// block until the signature is available, as signing happens within the `publish` call.
+pub trait SigningKey {
+    /// The public key matching the signing key.
+    fn public(&self) -> PublicKey;
+
+    /// Sign the given bytes.
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError>;
+}
+
+/// The default in-process [`SigningKey`] implementation.
+impl SigningKey for Keypair {
+    fn public(&self) -> PublicKey {
+        Keypair::public(self)
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+        Keypair::sign(self, msg)
+    }
+}
+
 const SIGNING_PREFIX: &[u8] = b"libp2p-pubsub:";
 
 /// Generate the Libp2p gossipsub signature for a message.
@@ -35,7 +61,7 @@ const SIGNING_PREFIX: &[u8] = b"libp2p-pubsub:";
 /// The signature is calculated over the bytes "libp2p-pubsub:<protobuf-message>".
 pub fn generate_message_signature(
     message: &MessageProto,
-    keypair: &Keypair,
+    key: &dyn SigningKey,
 ) -> Result<Vec<u8>, SigningError> {
     let mut msg = message.clone();
     msg.signature = None;
@@ -46,10 +72,10 @@ pub fn generate_message_signature(
     sign_bytes.extend(SIGNING_PREFIX.to_vec());
     sign_bytes.extend(msg.encode_to_vec());
 
-    keypair.sign(&sign_bytes)
+    key.sign(&sign_bytes)
 }
 
-/// A [`MessageSigner`] implementation that uses a [`Keypair`] to sign messages.
+/// A [`MessageSigner`] implementation that uses a [`SigningKey`] to sign messages.
 ///
 /// This signer will include the public key in the [`Message::key`] field if it is too large to be
 /// inlined in the [`Message::from`] field.
@@ -57,15 +83,21 @@ pub fn generate_message_signature(
 /// The signature is calculated over the bytes "libp2p-pubsub:<protobuf-message>". This is specified
 /// in the libp2p pubsub spec: https://github.com/libp2p/specs/tree/master/pubsub#message-signing
 pub struct Libp2pSigner {
-    keypair: Keypair,
+    key: Arc<dyn SigningKey + Send + Sync>,
     author: PeerId,
     inline_key: Option<Vec<u8>>,
 }
 
 impl Libp2pSigner {
     pub fn new(keypair: &Keypair) -> Self {
-        let peer_id = keypair.public().to_peer_id();
-        let key_enc = keypair.public().encode_protobuf();
+        Self::with_signing_key(Arc::new(keypair.clone()))
+    }
+
+    /// Create a signer delegating the signature generation to the given [`SigningKey`].
+    pub fn with_signing_key(key: Arc<dyn SigningKey + Send + Sync>) -> Self {
+        let public_key = key.public();
+        let peer_id = public_key.to_peer_id();
+        let key_enc = public_key.encode_protobuf();
         let inline_key = if key_enc.len() <= 42 {
             // The public key can be inlined in [`Message::from`], so we don't include it
             // specifically in the [`Message::key`] field.
@@ -76,7 +108,7 @@ impl Libp2pSigner {
         };
 
         Self {
-            keypair: keypair.clone(),
+            key,
             author: peer_id,
             inline_key,
         }
@@ -92,7 +124,7 @@ impl MessageSigner for Libp2pSigner {
         // Libp2p's pubsub message signature generation requires the `from` field to be set.
         message.set_source(Some(self.author));
 
-        let signature = generate_message_signature(message.as_proto(), &self.keypair)?;
+        let signature = generate_message_signature(message.as_proto(), self.key.as_ref())?;
         message.set_signature(Some(signature));
         message.set_key(self.inline_key.clone());
 
@@ -214,6 +246,62 @@ mod tests {
         }
     }
 
+    mod external_signer {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::gossipsub::signing::validator::{MessageValidator, StrictMessageValidator};
+
+        use super::*;
+
+        /// A mock of an external signer (e.g. an HSM) that keeps track of the signing requests.
+        struct MockExternalSigner {
+            keypair: Keypair,
+            requests: AtomicUsize,
+        }
+
+        impl SigningKey for MockExternalSigner {
+            fn public(&self) -> PublicKey {
+                self.keypair.public()
+            }
+
+            fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError> {
+                self.requests.fetch_add(1, Ordering::SeqCst);
+                self.keypair.sign(msg)
+            }
+        }
+
+        #[test]
+        fn sign() {
+            //// Given
+            let keypair = test_keypair();
+            let author = keypair.public().to_peer_id();
+
+            let external_signer = Arc::new(MockExternalSigner {
+                keypair: keypair.clone(),
+                requests: AtomicUsize::new(0),
+            });
+            let signer = Libp2pSigner::with_signing_key(external_signer.clone());
+
+            let mut message = test_message();
+
+            //// When
+            signer.sign(&mut message).expect("signing failed");
+
+            //// Then
+            assert_eq!(external_signer.requests.load(Ordering::SeqCst), 1);
+            assert_matches!(message.source(), Some(from_peer_id) => {
+                assert_eq!(from_peer_id, author);
+            });
+            assert_matches!(message.signature(), Some(signature) => {
+                assert!(verify_message_signature(message.as_proto(), signature, &keypair.public()));
+            });
+
+            // Validate with strict message validator
+            let validator = StrictMessageValidator::new();
+            assert_matches!(validator.validate(&message), Ok(()));
+        }
+    }
+
     mod author_only_signer {
         use crate::gossipsub::signing::validator::{MessageValidator, PermissiveMessageValidator};
 
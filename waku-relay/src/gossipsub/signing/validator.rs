@@ -7,6 +7,33 @@ use crate::gossipsub::rpc::{MessageProto, MessageRpc};
 
 pub(crate) const SIGNING_PREFIX: &[u8] = b"libp2p-pubsub:";
 
+/// The key types a message signature can be generated with.
+///
+/// See the libp2p peer-ids spec: https://github.com/libp2p/specs/blob/master/peer-ids/peer-ids.md#keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureKeyType {
+    Rsa,
+    Ed25519,
+    Secp256k1,
+    Ecdsa,
+}
+
+impl SignatureKeyType {
+    /// Get the key type of a public key.
+    ///
+    /// The key type is extracted from the public key protobuf encoding, whose first field is the
+    /// key type enum.
+    pub fn of(public_key: &PublicKey) -> Option<Self> {
+        match public_key.encode_protobuf().get(..2) {
+            Some([0x08, 0]) => Some(Self::Rsa),
+            Some([0x08, 1]) => Some(Self::Ed25519),
+            Some([0x08, 2]) => Some(Self::Secp256k1),
+            Some([0x08, 3]) => Some(Self::Ecdsa),
+            _ => None,
+        }
+    }
+}
+
 pub trait MessageValidator {
     fn validate(&self, message: &MessageRpc) -> Result<(), MessageValidationError>;
 }
@@ -134,12 +161,22 @@ impl MessageValidator for PermissiveMessageValidator {
 
 /// Validate all fields
 #[derive(Default)]
-pub struct StrictMessageValidator;
+pub struct StrictMessageValidator {
+    /// If set, only messages signed with this key type are accepted.
+    key_type: Option<SignatureKeyType>,
+}
 
 impl StrictMessageValidator {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Create a validator that only accepts messages signed with the given key type.
+    pub fn with_key_type(key_type: SignatureKeyType) -> Self {
+        Self {
+            key_type: Some(key_type),
+        }
+    }
 }
 
 impl MessageValidator for StrictMessageValidator {
@@ -173,6 +210,14 @@ impl MessageValidator for StrictMessageValidator {
             return Err(MessageValidationError::InvalidKey);
         }
 
+        // The key must be of the required type, if any
+        if let Some(key_type) = self.key_type {
+            if SignatureKeyType::of(&public_key) != Some(key_type) {
+                debug!("Message signed with an unsupported key type, expected {key_type:?}");
+                return Err(MessageValidationError::UnsupportedKeyType);
+            }
+        }
+
         // verify message signatures
         let signature = message.signature().unwrap();
         if !verify_message_signature(message.as_proto(), signature, &public_key) {
@@ -484,5 +529,33 @@ mod tests {
             // Then
             assert!(result.is_ok());
         }
+
+        #[test]
+        fn test_valid_required_key_type() {
+            // Given
+            let keypair = Keypair::generate_secp256k1();
+            let message = new_test_signed_message(&keypair, Some(1234));
+            let validator = StrictMessageValidator::with_key_type(SignatureKeyType::Secp256k1);
+
+            // When
+            let result = validator.validate(&message);
+
+            // Then
+            assert!(result.is_ok());
+        }
+
+        #[test]
+        fn test_error_unsupported_key_type() {
+            // Given
+            let keypair = Keypair::generate_ed25519();
+            let message = new_test_signed_message(&keypair, Some(1234));
+            let validator = StrictMessageValidator::with_key_type(SignatureKeyType::Secp256k1);
+
+            // When
+            let result = validator.validate(&message);
+
+            // Then
+            assert_matches!(result, Err(MessageValidationError::UnsupportedKeyType));
+        }
     }
 }
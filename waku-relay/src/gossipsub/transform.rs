@@ -44,6 +44,12 @@ pub trait DataTransform {
     /// Takes the data to be published (a topic and associated data) transforms the data. The
     /// transformed data will then be used to create a [`crate::RawMessage`] to be sent to peers.
     fn outbound_transform(&self, topic: &TopicHash, data: Vec<u8>) -> Result<Vec<u8>, io::Error>;
+
+    /// Returns the length of the received data once transformed (e.g. decompressed), if it can
+    /// be known. This is only used for metrics purposes. The default is None.
+    fn transformed_len(&self, _data: &[u8]) -> Option<usize> {
+        None
+    }
 }
 
 /// The default transform, the raw data is propagated as is to the application layer gossipsub.
@@ -63,4 +69,8 @@ impl DataTransform for IdentityTransform {
     fn outbound_transform(&self, _: &TopicHash, data: Vec<u8>) -> Result<Vec<u8>, io::Error> {
         Ok(data)
     }
+
+    fn transformed_len(&self, data: &[u8]) -> Option<usize> {
+        Some(data.len())
+    }
 }
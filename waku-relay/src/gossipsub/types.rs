@@ -19,10 +19,11 @@
 // DEALINGS IN THE SOFTWARE.
 
 //! A collection of types using the Gossipsub system.
+use std::collections::BTreeSet;
 use std::fmt;
 
 use libp2p::swarm::ConnectionId;
-use libp2p::PeerId;
+use libp2p::{Multiaddr, PeerId};
 use prometheus_client::encoding::EncodeLabelValue;
 use prost::Message as _;
 
@@ -181,9 +182,8 @@ pub enum SubscriptionAction {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PeerInfo {
     pub peer_id: Option<PeerId>,
-    //TODO add this when RFC: Signed Address Records got added to the spec (see pull request
-    // https://github.com/libp2p/specs/pull/217)
-    //pub signed_peer_record: ?,
+    /// The addresses advertised in the peer's signed peer record, if one was provided.
+    pub addresses: Vec<Multiaddr>,
 }
 
 /// A Control message received by the gossipsub system.
@@ -217,6 +217,121 @@ pub enum ControlAction {
     },
 }
 
+/// The kind of a gossipsub control message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ControlMessageKind {
+    /// IHAVE control message.
+    IHave,
+    /// IWANT control message.
+    IWant,
+    /// GRAFT control message.
+    Graft,
+    /// PRUNE control message.
+    Prune,
+}
+
+/// A read-only view of a control message sent to a peer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControlMessageSummary {
+    /// The peer the control message was sent to.
+    pub peer_id: PeerId,
+    /// The kind of control message.
+    pub kind: ControlMessageKind,
+    /// The topic the control message refers to. This is `None` for IWANT control messages.
+    pub topic_hash: Option<TopicHash>,
+    /// The advertised (IHAVE) or requested (IWANT) message ids. Empty for GRAFT and PRUNE control
+    /// messages.
+    pub message_ids: Vec<MessageId>,
+}
+
+impl ControlMessageSummary {
+    pub(crate) fn new(peer_id: PeerId, action: &ControlAction) -> Self {
+        let (kind, topic_hash, message_ids) = match action {
+            ControlAction::IHave {
+                topic_hash,
+                message_ids,
+            } => (
+                ControlMessageKind::IHave,
+                Some(topic_hash.clone()),
+                message_ids.clone(),
+            ),
+            ControlAction::IWant { message_ids } => {
+                (ControlMessageKind::IWant, None, message_ids.clone())
+            }
+            ControlAction::Graft { topic_hash } => (
+                ControlMessageKind::Graft,
+                Some(topic_hash.clone()),
+                Vec::new(),
+            ),
+            ControlAction::Prune { topic_hash, .. } => (
+                ControlMessageKind::Prune,
+                Some(topic_hash.clone()),
+                Vec::new(),
+            ),
+        };
+
+        Self {
+            peer_id,
+            kind,
+            topic_hash,
+            message_ids,
+        }
+    }
+}
+
+/// A snapshot of the state of a topic mesh.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MeshInfo {
+    /// The number of peers in the mesh.
+    pub peers: usize,
+    /// The number of outbound peers in the mesh.
+    pub outbound_peers: usize,
+    /// The configured lower bound of the mesh size (D_lo in the spec).
+    pub mesh_n_low: usize,
+    /// The configured upper bound of the mesh size (D_hi in the spec).
+    pub mesh_n_high: usize,
+}
+
+impl MeshInfo {
+    /// Whether the mesh has fewer peers than its configured lower bound.
+    pub fn is_below_mesh_n_low(&self) -> bool {
+        self.peers < self.mesh_n_low
+    }
+}
+
+/// The peers known for a topic, partitioned by their role in the topic message propagation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopicPeerRoles {
+    /// The peers in the topic mesh.
+    pub mesh: BTreeSet<PeerId>,
+    /// The fanout peers of a topic we publish to without being subscribed.
+    pub fanout: BTreeSet<PeerId>,
+    /// The peers subscribed to the topic that are neither mesh nor fanout peers.
+    pub subscribed: BTreeSet<PeerId>,
+}
+
+/// A snapshot of the state of the message cache.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct McacheStats {
+    /// The number of messages in the cache.
+    pub messages: usize,
+    /// The summed size in bytes of the cached messages data.
+    pub bytes: usize,
+    /// The total number of IWANT requests served from the cache for the cached messages.
+    pub iwant_count_total: usize,
+    /// The number of heartbeat windows the cache keeps messages for.
+    pub history_depth: usize,
+}
+
+/// Per-call options of [`crate::gossipsub::Behaviour::publish_with_options()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PublishOptions {
+    /// Whether to flood publish the message to all the topic peers above the publish threshold,
+    /// instead of the mesh peers. Defaults to the configured
+    /// [`crate::gossipsub::Config::flood_publish()`] if not set.
+    pub flood: Option<bool>,
+}
+
 /// An RPC received/sent.
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Rpc {
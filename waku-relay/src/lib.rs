@@ -1,5 +1,6 @@
 pub use behaviour::*;
 pub use event::*;
+pub use message_id::WakuMessageId;
 
 mod behaviour;
 pub mod error;
@@ -7,3 +8,4 @@ mod event;
 pub mod gossipsub;
 mod message_id;
 pub mod proto;
+mod topic;
@@ -51,6 +51,56 @@ pub fn deterministic_message_id_fn(message: &gossipsub::Message) -> MessageId {
     MessageId::new(result.to_vec())
 }
 
+/// Message ID generator keyed on the pubsub topic, the content topic and the payload of the Waku
+/// message.
+///
+/// Each field is prefixed with its length, as a big-endian `u64`, so moving bytes from one field
+/// to the next yields a different ID:
+///
+/// ```text
+/// message_id = sha256(concat(len(pubsub_topic), pubsub_topic,
+///                            len(message.content_topic), message.content_topic,
+///                            len(message.payload), message.payload))
+/// ```
+///
+/// Select it via [`gossipsub::ConfigBuilder::message_id_fn()`]:
+///
+/// ```ignore
+/// builder.message_id_fn(WakuMessageId::message_id_fn);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WakuMessageId;
+
+impl WakuMessageId {
+    /// Compute the message ID of a Waku message published on the pubsub topic.
+    pub fn compute(pubsub_topic: &str, message: &WakuMessage) -> MessageId {
+        let mut hasher = Sha256::new();
+        for field in [
+            pubsub_topic.as_bytes(),
+            message.content_topic.as_bytes(),
+            &message.payload[..],
+        ] {
+            hasher.update((field.len() as u64).to_be_bytes());
+            hasher.update(field);
+        }
+        let result = hasher.finalize_fixed();
+
+        MessageId::new(result.to_vec())
+    }
+
+    /// The message ID function to be set as the gossipsub [`gossipsub::Config::message_id()`]
+    /// function.
+    ///
+    /// If the message is not a valid `WakuMessage` (e.g., failed deserialization), this
+    /// function calls `fallback_message_id_fn`.
+    pub fn message_id_fn(message: &gossipsub::Message) -> MessageId {
+        match WakuMessage::decode(&message.data[..]) {
+            Ok(waku_message) => Self::compute(message.topic.as_str(), &waku_message),
+            _ => fallback_message_id_fn(message),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
@@ -58,7 +108,9 @@ mod tests {
 
     use waku_core::message::proto::waku::message::v1::WakuMessage;
 
-    use super::compute_deterministic_message_hash;
+    use crate::gossipsub::MessageId;
+
+    use super::{compute_deterministic_message_hash, WakuMessageId};
 
     /// https://rfc.vac.dev/spec/14/#test-vectors (Test vector 1)
     #[test]
@@ -150,4 +202,52 @@ mod tests {
             hex!("e1a9596237dbe2cc8aaf4b838c46a7052df6bc0d42ba214b998a8bfdbe8487d6")
         );
     }
+
+    /// Computed independently as the SHA-256 of the length-prefixed fields.
+    #[test]
+    fn test_waku_message_id_length_prefixed_fields() {
+        // Given
+        let pubsub_topic = "/waku/2/default-waku/proto";
+        let waku_message = WakuMessage {
+            payload: Bytes::from_static(&hex!("010203045445535405060708")),
+            content_topic: String::from("/waku/2/default-content/proto"),
+            meta: Some(Bytes::from_static(&hex!("73757065722d736563726574"))),
+            ..Default::default()
+        };
+
+        // When
+        let message_id = WakuMessageId::compute(pubsub_topic, &waku_message);
+
+        // Then
+        assert_eq!(
+            message_id,
+            MessageId::new(hex!(
+                "5fec0c5dc226c2a3bbf44a2fb44fc8c689185033e40e55b935f9589ddc3317d5"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_waku_message_id_fields_are_delimited() {
+        // Given
+        let pubsub_topic = "/waku/2/default-waku/proto";
+        let waku_message = WakuMessage {
+            payload: Bytes::from_static(b"payload"),
+            content_topic: String::from("/waku/2/default-content/proto"),
+            ..Default::default()
+        };
+        // The same bytes, with the content topic tail moved into the payload
+        let shifted_message = WakuMessage {
+            payload: Bytes::from_static(b"protopayload"),
+            content_topic: String::from("/waku/2/default-content/"),
+            ..Default::default()
+        };
+
+        // When
+        let message_id = WakuMessageId::compute(pubsub_topic, &waku_message);
+        let shifted_message_id = WakuMessageId::compute(pubsub_topic, &shifted_message);
+
+        // Then
+        assert_ne!(message_id, shifted_message_id);
+    }
 }
@@ -0,0 +1,100 @@
+use base64::prelude::*;
+
+use waku_core::pubsub_topic::PubsubTopic;
+
+use crate::error::TopicHashError;
+use crate::gossipsub::{IdentTopic, TopicHash};
+
+/// Length in bytes of a SHA256 digest.
+const SHA256_DIGEST_LENGTH: usize = 32;
+
+impl From<PubsubTopic> for IdentTopic {
+    fn from(topic: PubsubTopic) -> Self {
+        IdentTopic::new(topic.to_string())
+    }
+}
+
+/// Converts an identity [`TopicHash`] back into a [`PubsubTopic`].
+///
+/// Identity hashes are the raw UTF-8 topic string. Hashes that decode as a base64 encoded SHA256
+/// digest were created by the [`Sha256Topic`](crate::gossipsub::Sha256Topic) hasher, and the
+/// original topic cannot be recovered from them.
+impl TryFrom<&TopicHash> for PubsubTopic {
+    type Error = TopicHashError;
+
+    fn try_from(hash: &TopicHash) -> Result<Self, Self::Error> {
+        let raw = hash.as_str();
+        if raw.is_empty() {
+            return Err(TopicHashError::Empty);
+        }
+
+        if BASE64_STANDARD
+            .decode(raw)
+            .is_ok_and(|digest| digest.len() == SHA256_DIGEST_LENGTH)
+        {
+            return Err(TopicHashError::NotIdentityHash);
+        }
+
+        Ok(PubsubTopic::new(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gossipsub::Sha256Topic;
+
+    use super::*;
+
+    #[test]
+    fn test_pubsub_topic_round_trip_through_ident_topic() {
+        // Given
+        let topic = PubsubTopic::new("/waku/2/default-waku/proto");
+
+        // When
+        let ident_topic = IdentTopic::from(topic.clone());
+        let hash = ident_topic.hash();
+        let result = PubsubTopic::try_from(&hash);
+
+        // Then
+        assert_eq!(ident_topic.to_string(), "/waku/2/default-waku/proto");
+        assert_eq!(hash.as_str(), "/waku/2/default-waku/proto");
+        assert_eq!(result.unwrap(), topic);
+    }
+
+    #[test]
+    fn test_pubsub_topic_round_trip_static_sharding_topic() {
+        // Given
+        let topic = PubsubTopic::new("/waku/2/rs/16/32");
+
+        // When
+        let hash = IdentTopic::from(topic.clone()).hash();
+        let result = PubsubTopic::try_from(&hash);
+
+        // Then
+        assert_eq!(result.unwrap(), topic);
+    }
+
+    #[test]
+    fn test_pubsub_topic_from_sha256_topic_hash_fails() {
+        // Given
+        let hash = Sha256Topic::new("/waku/2/default-waku/proto").hash();
+
+        // When
+        let result = PubsubTopic::try_from(&hash);
+
+        // Then
+        assert!(matches!(result, Err(TopicHashError::NotIdentityHash)));
+    }
+
+    #[test]
+    fn test_pubsub_topic_from_empty_topic_hash_fails() {
+        // Given
+        let hash = TopicHash::from_raw("");
+
+        // When
+        let result = PubsubTopic::try_from(&hash);
+
+        // Then
+        assert!(matches!(result, Err(TopicHashError::Empty)));
+    }
+}
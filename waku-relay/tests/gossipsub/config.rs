@@ -0,0 +1,82 @@
+use waku_relay::gossipsub::ConfigBuilder;
+
+#[test]
+fn default_mesh_parameters_are_valid() {
+    //// Given
+    let builder = ConfigBuilder::default();
+
+    //// When
+    let result = builder.build();
+
+    //// Then
+    let config = result.expect("valid default configuration");
+    assert!(config.mesh_n_low() <= config.mesh_n());
+    assert!(config.mesh_n() <= config.mesh_n_high());
+    assert!(config.mesh_outbound_min() <= config.mesh_n_low() / 2);
+}
+
+#[test]
+fn mesh_outbound_min_equal_to_half_mesh_n_low_is_accepted() {
+    //// Given
+    let mut builder = ConfigBuilder::default();
+
+    //// When
+    builder
+        .mesh_n_low(4)
+        .mesh_n(6)
+        .mesh_n_high(12)
+        .mesh_outbound_min(2);
+    let result = builder.build();
+
+    //// Then
+    assert!(result.is_ok());
+}
+
+#[test]
+fn mesh_n_low_greater_than_mesh_n_is_rejected() {
+    //// Given
+    let mut builder = ConfigBuilder::default();
+
+    //// When
+    builder.mesh_n_low(7).mesh_n(6).mesh_n_high(12);
+    let result = builder.build();
+
+    //// Then
+    let error = result.expect_err("invalid configuration").to_string();
+    assert!(error.contains("mesh_n_low"));
+    assert!(error.contains("mesh_n "));
+}
+
+#[test]
+fn mesh_n_greater_than_mesh_n_high_is_rejected() {
+    //// Given
+    let mut builder = ConfigBuilder::default();
+
+    //// When
+    builder.mesh_n_low(4).mesh_n(13).mesh_n_high(12);
+    let result = builder.build();
+
+    //// Then
+    let error = result.expect_err("invalid configuration").to_string();
+    assert!(error.contains("mesh_n "));
+    assert!(error.contains("mesh_n_high"));
+}
+
+#[test]
+fn mesh_outbound_min_greater_than_half_mesh_n_low_is_rejected() {
+    //// Given
+    let mut builder = ConfigBuilder::default();
+
+    //// When
+    builder
+        .mesh_n_low(4)
+        .mesh_n(6)
+        .mesh_n_high(12)
+        .mesh_outbound_min(3);
+    let result = builder.build();
+
+    //// Then
+    let error = result.expect_err("invalid configuration").to_string();
+    assert!(error.contains("mesh_outbound_min"));
+    assert!(error.contains("mesh_n_low"));
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use tokio::time::timeout;
+
+use waku_relay::gossipsub::{ControlMessageKind, IdentTopic, MessageAuthenticity};
+
+use crate::testlib;
+use crate::testlib::any_memory_addr;
+use crate::testlib::swarm::{anonymous_config, new_test_node, poll_mesh, TEST_NODE_KEYS};
+
+#[tokio::test]
+async fn recent_control_messages_include_sent_grafts() {
+    testlib::init_logger();
+
+    //// Given
+    let pubsub_topic = IdentTopic::new("/waku/2/it-waku/test");
+
+    let [node_key, peer_key, ..] = TEST_NODE_KEYS.map(testlib::secp256k1_keypair);
+
+    let pubsub_config = anonymous_config();
+
+    //// Setup
+    let mut node = new_test_node(
+        &node_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+    node.listen_on(any_memory_addr())
+        .expect("listen on address");
+
+    let mut peer = new_test_node(&peer_key, MessageAuthenticity::Anonymous, pubsub_config);
+
+    let node_addr = timeout(
+        Duration::from_secs(5),
+        testlib::swarm::wait_for_new_listen_addr(&mut node),
+    )
+    .await
+    .expect("listening to start");
+
+    node.behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+    peer.behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+
+    //// When
+    peer.dial(node_addr).expect("dial to succeed");
+
+    // Wait for the mesh to be established
+    poll_mesh(Duration::from_millis(250), &mut node, &mut peer).await;
+
+    //// Then
+    let peer_id = *peer.local_peer_id();
+    let grafts = node
+        .behaviour()
+        .recent_control_messages()
+        .filter(|summary| summary.kind == ControlMessageKind::Graft)
+        .collect::<Vec<_>>();
+
+    assert!(!grafts.is_empty());
+    assert!(grafts.iter().all(|summary| summary.peer_id == peer_id));
+    assert!(grafts
+        .iter()
+        .all(|summary| summary.topic_hash == Some(pubsub_topic.hash())));
+    assert!(grafts.iter().all(|summary| summary.message_ids.is_empty()));
+}
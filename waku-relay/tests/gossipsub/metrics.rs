@@ -0,0 +1,261 @@
+use std::time::Duration;
+
+use assert_matches::assert_matches;
+use bytes::Bytes;
+use futures::StreamExt;
+use libp2p::identity::Keypair;
+use libp2p::swarm::SwarmEvent;
+use libp2p::Swarm;
+use prometheus_client::encoding::text::encode;
+use prometheus_client::registry::Registry;
+use tokio::time::timeout;
+
+use waku_relay::gossipsub::{
+    Behaviour, BehaviourError, Config, Event, IdentTopic, MessageAcceptance, MessageAuthenticity,
+    MetricsConfig, MetricsError,
+};
+
+use crate::testlib;
+use crate::testlib::any_memory_addr;
+use crate::testlib::swarm::{
+    anonymous_config, anonymous_config_builder, new_test_node, new_test_swarm, poll_mesh,
+    TEST_NODE_KEYS,
+};
+
+fn new_metrics_test_node(
+    keypair: &Keypair,
+    config: Config,
+    registry: &mut Registry,
+) -> Swarm<Behaviour> {
+    let behaviour = Behaviour::new_with_metrics(
+        MessageAuthenticity::Anonymous,
+        config,
+        registry,
+        MetricsConfig::default(),
+    )
+    .expect("valid gossipsub configuration");
+    new_test_swarm(keypair, behaviour)
+}
+
+async fn wait_for_message(swarm: &mut Swarm<Behaviour>) -> Event {
+    loop {
+        let event = swarm.select_next_some().await;
+        if let SwarmEvent::Behaviour(event @ Event::Message { .. }) = event {
+            return event;
+        }
+    }
+}
+
+#[tokio::test]
+async fn validation_latency_is_recorded_on_report() {
+    testlib::init_logger();
+
+    //// Given
+    let pubsub_topic = IdentTopic::new("/waku/2/it-waku/test");
+    let message_payload = Bytes::from_static(b"test-payload");
+
+    let [publisher_key, subscriber_key, ..] = TEST_NODE_KEYS.map(testlib::secp256k1_keypair);
+
+    let pubsub_config = anonymous_config_builder()
+        .validate_messages()
+        .build()
+        .expect("valid gossipsub configuration");
+
+    let mut registry = Registry::default();
+
+    //// Setup
+    let mut publisher = new_test_node(
+        &publisher_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+    publisher
+        .listen_on(any_memory_addr())
+        .expect("listen on address");
+
+    let mut subscriber = new_metrics_test_node(&subscriber_key, pubsub_config, &mut registry);
+
+    let publisher_addr = timeout(
+        Duration::from_secs(5),
+        testlib::swarm::wait_for_new_listen_addr(&mut publisher),
+    )
+    .await
+    .expect("listening to start");
+
+    publisher
+        .behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+    subscriber
+        .behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+
+    subscriber.dial(publisher_addr).expect("dial to succeed");
+
+    // Wait for pub-sub network to establish
+    poll_mesh(Duration::from_millis(250), &mut publisher, &mut subscriber).await;
+
+    publisher
+        .behaviour_mut()
+        .publish(pubsub_topic, message_payload)
+        .expect("publish the message");
+
+    let event = tokio::select! {
+        _ = timeout(Duration::from_millis(250), testlib::swarm::poll(&mut publisher)) => panic!("timeout reached"),
+        event = wait_for_message(&mut subscriber) => event,
+    };
+    let (message_id, propagation_source) = assert_matches!(event, Event::Message { message_id, propagation_source, .. } => (message_id, propagation_source));
+
+    //// When
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let reported = subscriber
+        .behaviour_mut()
+        .report_message_validation_result(
+            &message_id,
+            &propagation_source,
+            MessageAcceptance::Accept,
+        )
+        .expect("report validation result");
+
+    //// Then
+    assert!(reported);
+
+    let mut metrics = String::new();
+    encode(&mut metrics, &registry).expect("encode metrics");
+    assert!(metrics.contains("validation_latency_count 1"));
+    assert!(!metrics.contains("validation_latency_sum 0.0"));
+}
+
+#[tokio::test]
+async fn transformed_message_size_is_recorded_on_receive() {
+    testlib::init_logger();
+
+    //// Given
+    let pubsub_topic = IdentTopic::new("/waku/2/it-waku/test");
+    let message_payload = Bytes::from_static(b"test-payload");
+
+    let [publisher_key, subscriber_key, ..] = TEST_NODE_KEYS.map(testlib::secp256k1_keypair);
+
+    let pubsub_config = anonymous_config();
+
+    let mut registry = Registry::default();
+
+    //// Setup
+    let mut publisher = new_test_node(
+        &publisher_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+    publisher
+        .listen_on(any_memory_addr())
+        .expect("listen on address");
+
+    let mut subscriber = new_metrics_test_node(&subscriber_key, pubsub_config, &mut registry);
+
+    let publisher_addr = timeout(
+        Duration::from_secs(5),
+        testlib::swarm::wait_for_new_listen_addr(&mut publisher),
+    )
+    .await
+    .expect("listening to start");
+
+    publisher
+        .behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+    subscriber
+        .behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+
+    subscriber.dial(publisher_addr).expect("dial to succeed");
+
+    // Wait for pub-sub network to establish
+    poll_mesh(Duration::from_millis(250), &mut publisher, &mut subscriber).await;
+
+    //// When
+    publisher
+        .behaviour_mut()
+        .publish(pubsub_topic, message_payload.clone())
+        .expect("publish the message");
+
+    tokio::select! {
+        _ = timeout(Duration::from_millis(250), testlib::swarm::poll(&mut publisher)) => panic!("timeout reached"),
+        event = wait_for_message(&mut subscriber) => event,
+    };
+
+    //// Then
+    let mut metrics = String::new();
+    encode(&mut metrics, &registry).expect("encode metrics");
+
+    // The identity transform does not change the message data size
+    let expected_bytes = format!(" {}", message_payload.len());
+    assert!(metrics
+        .lines()
+        .any(|line| line.starts_with("msg_decompressed_bytes_total")
+            && line.ends_with(&expected_bytes)));
+}
+
+#[test]
+fn behaviours_sharing_a_registry_with_distinct_prefixes_do_not_collide() {
+    //// Given
+    let pubsub_config = anonymous_config();
+
+    let mut registry = Registry::default();
+
+    //// When
+    let _first: Behaviour = Behaviour::new_with_metrics(
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+        &mut registry,
+        MetricsConfig {
+            prefix: Some("relay_a".to_string()),
+            ..Default::default()
+        },
+    )
+    .expect("valid gossipsub configuration");
+    let _second: Behaviour = Behaviour::new_with_metrics(
+        MessageAuthenticity::Anonymous,
+        pubsub_config,
+        &mut registry,
+        MetricsConfig {
+            prefix: Some("relay_b".to_string()),
+            ..Default::default()
+        },
+    )
+    .expect("valid gossipsub configuration");
+
+    //// Then
+    let mut metrics = String::new();
+    encode(&mut metrics, &registry).expect("encode metrics");
+    assert!(metrics.contains("relay_a_heartbeat_duration_count 0"));
+    assert!(metrics.contains("relay_b_heartbeat_duration_count 0"));
+    assert!(!metrics.contains("\nheartbeat_duration_count"));
+}
+
+#[test]
+fn invalid_metrics_prefix_is_rejected() {
+    //// Given
+    let pubsub_config = anonymous_config();
+
+    let mut registry = Registry::default();
+
+    //// When
+    let result: Result<Behaviour, _> = Behaviour::new_with_metrics(
+        MessageAuthenticity::Anonymous,
+        pubsub_config,
+        &mut registry,
+        MetricsConfig {
+            prefix: Some("relay-a".to_string()),
+            ..Default::default()
+        },
+    );
+
+    //// Then
+    assert_matches!(
+        result,
+        Err(BehaviourError::Metrics(MetricsError::InvalidPrefix(prefix))) if prefix == "relay-a"
+    );
+}
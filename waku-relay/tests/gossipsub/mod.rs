@@ -1 +1,6 @@
+pub(crate) mod config;
+pub(crate) mod control;
+pub(crate) mod metrics;
+pub(crate) mod peers;
+pub(crate) mod publish;
 pub(crate) mod pubsub;
@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use libp2p::identity::PeerId;
+use tokio::time::timeout;
+
+use waku_relay::gossipsub::{IdentTopic, MessageAuthenticity};
+
+use crate::testlib;
+use crate::testlib::any_memory_addr;
+use crate::testlib::swarm::{
+    anonymous_config, anonymous_config_builder, new_test_node, poll_mesh, TEST_NODE_KEYS,
+};
+
+#[tokio::test]
+async fn peer_subscribed_topics_lists_remote_peer_subscriptions() {
+    testlib::init_logger();
+
+    //// Given
+    let topic_a = IdentTopic::new("/waku/2/it-waku/test-a");
+    let topic_b = IdentTopic::new("/waku/2/it-waku/test-b");
+
+    let [node_key, peer_key, ..] = TEST_NODE_KEYS.map(testlib::secp256k1_keypair);
+
+    let pubsub_config = anonymous_config();
+
+    //// Setup
+    let mut node = new_test_node(
+        &node_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+    node.listen_on(any_memory_addr())
+        .expect("listen on address");
+
+    let mut peer = new_test_node(&peer_key, MessageAuthenticity::Anonymous, pubsub_config);
+
+    let node_addr = timeout(
+        Duration::from_secs(5),
+        testlib::swarm::wait_for_new_listen_addr(&mut node),
+    )
+    .await
+    .expect("listening to start");
+
+    peer.behaviour_mut()
+        .subscribe(&topic_a)
+        .expect("subscribe to topic");
+    peer.behaviour_mut()
+        .subscribe(&topic_b)
+        .expect("subscribe to topic");
+
+    //// When
+    peer.dial(node_addr).expect("dial to succeed");
+
+    // Wait for the subscriptions to be exchanged
+    poll_mesh(Duration::from_millis(250), &mut node, &mut peer).await;
+
+    //// Then
+    let peer_topics = node
+        .behaviour()
+        .peer_subscribed_topics(peer.local_peer_id());
+    assert_eq!(peer_topics.len(), 2);
+    assert!(peer_topics.contains(&topic_a.hash()));
+    assert!(peer_topics.contains(&topic_b.hash()));
+
+    let unknown_peer_topics = node.behaviour().peer_subscribed_topics(&PeerId::random());
+    assert!(unknown_peer_topics.is_empty());
+}
+
+#[tokio::test]
+async fn peer_bandwidth_accounts_exchanged_bytes() {
+    testlib::init_logger();
+
+    //// Given
+    let pubsub_topic = IdentTopic::new("/waku/2/it-waku/test");
+    let message_payload = vec![0x42; 1024];
+
+    let [publisher_key, subscriber_key, ..] = TEST_NODE_KEYS.map(testlib::secp256k1_keypair);
+
+    let pubsub_config = anonymous_config();
+
+    //// Setup
+    let mut publisher = new_test_node(
+        &publisher_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+    publisher
+        .listen_on(any_memory_addr())
+        .expect("listen on address");
+
+    let mut subscriber = new_test_node(
+        &subscriber_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config,
+    );
+
+    let publisher_addr = timeout(
+        Duration::from_secs(5),
+        testlib::swarm::wait_for_new_listen_addr(&mut publisher),
+    )
+    .await
+    .expect("listening to start");
+
+    publisher
+        .behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+    subscriber
+        .behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+
+    subscriber.dial(publisher_addr).expect("dial to succeed");
+
+    // Wait for pub-sub network to establish
+    poll_mesh(Duration::from_millis(250), &mut publisher, &mut subscriber).await;
+
+    //// When
+    publisher
+        .behaviour_mut()
+        .publish(pubsub_topic, message_payload.clone())
+        .expect("publish the message");
+
+    poll_mesh(Duration::from_millis(250), &mut publisher, &mut subscriber).await;
+
+    //// Then
+    let payload_len = message_payload.len() as u64;
+
+    let (publisher_sent, publisher_received) = publisher
+        .behaviour()
+        .peer_bandwidth(subscriber.local_peer_id());
+    assert!(publisher_sent > payload_len);
+    assert!(publisher_received > 0);
+    assert!(publisher_received < payload_len);
+
+    let (subscriber_sent, subscriber_received) = subscriber
+        .behaviour()
+        .peer_bandwidth(publisher.local_peer_id());
+    assert!(subscriber_received > payload_len);
+    assert!(subscriber_sent > 0);
+    assert!(subscriber_sent < payload_len);
+}
+
+#[tokio::test]
+async fn mesh_snapshot_reports_mesh_peers() {
+    testlib::init_logger();
+
+    //// Given
+    let pubsub_topic = IdentTopic::new("/waku/2/it-waku/test");
+
+    let [node_key, peer_key, ..] = TEST_NODE_KEYS.map(testlib::secp256k1_keypair);
+
+    let pubsub_config = anonymous_config();
+
+    //// Setup
+    let mut node = new_test_node(
+        &node_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+    node.listen_on(any_memory_addr())
+        .expect("listen on address");
+
+    let mut peer = new_test_node(
+        &peer_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+
+    let node_addr = timeout(
+        Duration::from_secs(5),
+        testlib::swarm::wait_for_new_listen_addr(&mut node),
+    )
+    .await
+    .expect("listening to start");
+
+    node.behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+    peer.behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+
+    //// When
+    peer.dial(node_addr).expect("dial to succeed");
+
+    // Wait for pub-sub network to establish
+    poll_mesh(Duration::from_millis(250), &mut node, &mut peer).await;
+
+    //// Then
+    assert_eq!(peer.behaviour().mesh_peers_count(&pubsub_topic.hash()), 1);
+    assert_eq!(
+        peer.behaviour()
+            .mesh_peers_count(&IdentTopic::new("/waku/2/it-waku/other").hash()),
+        0
+    );
+
+    let snapshot = peer.behaviour().mesh_snapshot();
+    let mesh_info = snapshot
+        .get(&pubsub_topic.hash())
+        .expect("mesh info for the subscribed topic");
+    assert_eq!(mesh_info.peers, 1);
+    assert_eq!(mesh_info.outbound_peers, 1, "the dialed peer is outbound");
+    assert_eq!(mesh_info.mesh_n_low, pubsub_config.mesh_n_low());
+    assert_eq!(mesh_info.mesh_n_high, pubsub_config.mesh_n_high());
+    assert!(mesh_info.is_below_mesh_n_low());
+}
+
+#[tokio::test]
+async fn fanout_peers_lists_publish_only_topic_peers() {
+    testlib::init_logger();
+
+    //// Given
+    let pubsub_topic = IdentTopic::new("/waku/2/it-waku/test");
+    let message_payload = b"test payload".to_vec();
+
+    let [publisher_key, subscriber_key, ..] = TEST_NODE_KEYS.map(testlib::secp256k1_keypair);
+
+    // Disable flood publishing so the publish-only topic peers are selected from the fanout
+    let pubsub_config = anonymous_config_builder()
+        .flood_publish(false)
+        .build()
+        .expect("valid gossipsub configuration");
+
+    //// Setup
+    let mut publisher = new_test_node(
+        &publisher_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+    publisher
+        .listen_on(any_memory_addr())
+        .expect("listen on address");
+
+    let mut subscriber = new_test_node(
+        &subscriber_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config,
+    );
+
+    let publisher_addr = timeout(
+        Duration::from_secs(5),
+        testlib::swarm::wait_for_new_listen_addr(&mut publisher),
+    )
+    .await
+    .expect("listening to start");
+
+    // Only the subscriber is subscribed to the topic
+    subscriber
+        .behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+
+    subscriber.dial(publisher_addr).expect("dial to succeed");
+
+    // Wait for the subscriptions to be exchanged
+    poll_mesh(Duration::from_millis(250), &mut publisher, &mut subscriber).await;
+
+    assert!(publisher
+        .behaviour()
+        .fanout_peers(&pubsub_topic.hash())
+        .is_empty());
+
+    //// When
+    publisher
+        .behaviour_mut()
+        .publish(pubsub_topic.clone(), message_payload)
+        .expect("publish the message");
+
+    //// Then
+    assert_eq!(
+        publisher.behaviour().fanout_peers(&pubsub_topic.hash()),
+        vec![*subscriber.local_peer_id()]
+    );
+    assert!(publisher
+        .behaviour()
+        .fanout_peers(&IdentTopic::new("/waku/2/it-waku/other").hash())
+        .is_empty());
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::StreamExt;
+use libp2p::swarm::SwarmEvent;
+use libp2p::Swarm;
+use tokio::time::timeout;
+
+use waku_relay::gossipsub::{Behaviour, Event, IdentTopic, MessageAuthenticity};
+
+use crate::testlib;
+use crate::testlib::any_memory_addr;
+use crate::testlib::swarm::{anonymous_config, new_test_node, TEST_NODE_KEYS};
+
+/// Polls all the swarms for the given duration, and returns the number of messages received by
+/// each swarm.
+async fn poll_network(duration: Duration, swarms: &mut [Swarm<Behaviour>]) -> Vec<usize> {
+    let mut received = vec![0; swarms.len()];
+
+    let deadline = tokio::time::Instant::now() + duration;
+    loop {
+        let next_event =
+            futures::future::select_all(swarms.iter_mut().map(|s| s.select_next_some()));
+        match tokio::time::timeout_at(deadline, next_event).await {
+            Ok((SwarmEvent::Behaviour(Event::Message { .. }), index, _)) => received[index] += 1,
+            Ok((event, _, _)) => log::trace!("Event: {:?}", event),
+            Err(_) => break,
+        }
+    }
+
+    received
+}
+
+#[tokio::test]
+async fn publish_to_peers_only_reaches_the_specified_peers() {
+    testlib::init_logger();
+
+    //// Given
+    let pubsub_topic = IdentTopic::new("/waku/2/it-waku/test");
+    let message_payload = Bytes::from_static(b"test-payload");
+
+    let [publisher_key, subscriber_keys @ ..] = TEST_NODE_KEYS.map(testlib::secp256k1_keypair);
+
+    let pubsub_config = anonymous_config();
+
+    //// Setup
+    let mut publisher = new_test_node(
+        &publisher_key,
+        MessageAuthenticity::Anonymous,
+        pubsub_config.clone(),
+    );
+    publisher
+        .listen_on(any_memory_addr())
+        .expect("listen on address");
+
+    let publisher_addr = timeout(
+        Duration::from_secs(5),
+        testlib::swarm::wait_for_new_listen_addr(&mut publisher),
+    )
+    .await
+    .expect("listening to start");
+
+    publisher
+        .behaviour_mut()
+        .subscribe(&pubsub_topic)
+        .expect("subscribe to topic");
+
+    let mut swarms = vec![publisher];
+    for key in subscriber_keys.iter() {
+        let mut subscriber =
+            new_test_node(key, MessageAuthenticity::Anonymous, pubsub_config.clone());
+        subscriber
+            .behaviour_mut()
+            .subscribe(&pubsub_topic)
+            .expect("subscribe to topic");
+        subscriber
+            .dial(publisher_addr.clone())
+            .expect("dial to succeed");
+        swarms.push(subscriber);
+    }
+
+    // Wait for pub-sub network to establish
+    poll_network(Duration::from_millis(250), &mut swarms).await;
+
+    //// When
+    let target_peers = [*swarms[1].local_peer_id(), *swarms[2].local_peer_id()];
+    swarms[0]
+        .behaviour_mut()
+        .publish_to_peers(pubsub_topic, message_payload, &target_peers)
+        .expect("publish the message");
+
+    let received = poll_network(Duration::from_millis(250), &mut swarms).await;
+
+    //// Then
+    assert_eq!(
+        received[1], 1,
+        "first target peer should receive the message"
+    );
+    assert_eq!(
+        received[2], 1,
+        "second target peer should receive the message"
+    );
+    assert_eq!(
+        received[3], 0,
+        "non-target peer should not receive the message"
+    );
+}
@@ -3,43 +3,18 @@ use std::time::Duration;
 use assert_matches::assert_matches;
 use bytes::Bytes;
 use futures::StreamExt;
-use libp2p::identity::{Keypair, PeerId};
-use libp2p::swarm::{SwarmBuilder, SwarmEvent};
+use libp2p::swarm::SwarmEvent;
 use libp2p::{Multiaddr, Swarm};
 use tokio::time::timeout;
 use void::Void;
 
 use waku_relay::gossipsub::{
-    Behaviour, Config, ConfigBuilder, Event, IdentTopic, Message, MessageAuthenticity,
-    ValidationMode,
+    Behaviour, ConfigBuilder, Event, IdentTopic, Message, MessageAuthenticity, ValidationMode,
 };
 
 use crate::testlib;
 use crate::testlib::any_memory_addr;
-
-fn new_test_node(
-    keypair: &Keypair,
-    privacy: MessageAuthenticity,
-    config: Config,
-) -> Swarm<Behaviour> {
-    let peer_id = PeerId::from(keypair.public());
-    let transport = testlib::test_transport(keypair).expect("create the transport");
-    let behaviour = Behaviour::new(privacy, config).expect("valid gossipsub configuration");
-    SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build()
-}
-
-async fn poll_mesh(
-    duration: Duration,
-    swarm1: &mut Swarm<Behaviour>,
-    swarm2: &mut Swarm<Behaviour>,
-) {
-    timeout(
-        duration,
-        futures::future::join(testlib::swarm::poll(swarm1), testlib::swarm::poll(swarm2)),
-    )
-    .await
-    .expect_err("timeout to be reached");
-}
+use crate::testlib::swarm::{new_test_node, poll_mesh};
 
 async fn wait_for_start_listening(
     publisher: &mut Swarm<Behaviour>,
@@ -1,9 +1,53 @@
+use std::time::Duration;
+
 use futures::StreamExt;
-use libp2p::identity::{secp256k1, Keypair};
-use libp2p::swarm::SwarmEvent;
+use libp2p::identity::{secp256k1, Keypair, PeerId};
+use libp2p::swarm::{SwarmBuilder, SwarmEvent};
 use libp2p::{Multiaddr, Swarm};
+use tokio::time::timeout;
+
+use waku_relay::gossipsub::{
+    Behaviour, Config, ConfigBuilder, MessageAuthenticity, ValidationMode,
+};
+
+use crate::testlib::test_transport;
+
+/// The hex encoded secp256k1 secret keys of the test nodes.
+pub const TEST_NODE_KEYS: [&str; 4] = [
+    "dc404f7ed2d3cdb65b536e8d561255c84658e83775ee790ff46bf4d77690b0fe",
+    "9c0cd57a01ee12338915b42bf6232a386e467dcdbe172facd94e4623ffc9096c",
+    "1b35da3ee25de068d8ca2d03053a396b0897958ab97552cdc5d1d5004c4f6e49",
+    "1c8d10528d0bc07bfda942fffaab7f38efd579756937dac44925734ee6e9402c",
+];
 
-use waku_relay::gossipsub::Behaviour;
+/// A gossipsub configuration builder with anonymous message validation.
+pub fn anonymous_config_builder() -> ConfigBuilder {
+    let mut builder = ConfigBuilder::default();
+    builder.validation_mode(ValidationMode::Anonymous);
+    builder
+}
+
+/// The default gossipsub configuration with anonymous message validation.
+pub fn anonymous_config() -> Config {
+    anonymous_config_builder()
+        .build()
+        .expect("valid gossipsub configuration")
+}
+
+pub fn new_test_swarm(keypair: &Keypair, behaviour: Behaviour) -> Swarm<Behaviour> {
+    let peer_id = PeerId::from(keypair.public());
+    let transport = test_transport(keypair).expect("create the transport");
+    SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build()
+}
+
+pub fn new_test_node(
+    keypair: &Keypair,
+    privacy: MessageAuthenticity,
+    config: Config,
+) -> Swarm<Behaviour> {
+    let behaviour = Behaviour::new(privacy, config).expect("valid gossipsub configuration");
+    new_test_swarm(keypair, behaviour)
+}
 
 pub async fn poll(swarm: &mut Swarm<Behaviour>) {
     loop {
@@ -12,6 +56,17 @@ pub async fn poll(swarm: &mut Swarm<Behaviour>) {
     }
 }
 
+/// Polls both swarms for the given duration, letting the mesh form.
+pub async fn poll_mesh(
+    duration: Duration,
+    swarm1: &mut Swarm<Behaviour>,
+    swarm2: &mut Swarm<Behaviour>,
+) {
+    timeout(duration, futures::future::join(poll(swarm1), poll(swarm2)))
+        .await
+        .expect_err("timeout to be reached");
+}
+
 pub fn secp256k1_keypair(key: &str) -> Keypair {
     let raw_key = hex::decode(key).expect("key to be valid");
     let secret_key = secp256k1::SecretKey::try_from_bytes(raw_key).unwrap();